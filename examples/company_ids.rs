@@ -0,0 +1,58 @@
+//! Generate `src/hci/company_ids.rs` from `company_identifiers.yaml` of the
+//! Bluetooth SIG Assigned Numbers repository
+//! (https://bitbucket.org/bluetooth-SIG/public, under
+//! `assigned_numbers/company_identifiers`):
+//!
+//!     cargo run --example company_ids -- company_identifiers.yaml <revision> > src/hci/company_ids.rs
+//!
+//! where `<revision>` is the commit of the repository the file was taken from.
+
+use std::{env, fs, process};
+
+/// Value of a YAML scalar, unquoting single or double quoted strings
+fn scalar(s: &str) -> String {
+    let s = s.trim();
+    if let Some(s) = s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        s.replace("''", "'")
+    } else if let Some(s) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        s.replace("\\\"", "\"").replace("\\\\", "\\")
+    } else {
+        s.to_string()
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let [_, path, revision] = args.as_slice() else {
+        eprintln!("usage: company_ids <company_identifiers.yaml> <revision>");
+        process::exit(1);
+    };
+    let yaml = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        process::exit(1);
+    });
+
+    let mut companies = Vec::new();
+    let mut value = None;
+    for line in yaml.lines() {
+        let line = line.trim_start().trim_start_matches("- ");
+        if let Some(v) = line.strip_prefix("value:") {
+            let v = scalar(v);
+            value = u16::from_str_radix(v.trim_start_matches("0x").trim_start_matches("0X"), 16).ok();
+        } else if let (Some(name), Some(id)) = (line.strip_prefix("name:"), value.take()) {
+            companies.push((id, scalar(name)));
+        }
+    }
+    companies.sort_by_key(|(id, _)| *id);
+    companies.dedup_by_key(|(id, _)| *id);
+
+    println!("// Generated by examples/company_ids.rs from");
+    println!("// assigned_numbers/company_identifiers/company_identifiers.yaml of the");
+    println!("// Bluetooth SIG Assigned Numbers repository, revision {}.", revision);
+    println!("// {} entries. Don't edit by hand.", companies.len());
+    println!("&[");
+    for (id, name) in companies {
+        println!("    (0x{:04x}, {:?}),", id, name);
+    }
+    println!("]");
+}
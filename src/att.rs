@@ -1,9 +1,23 @@
-use nom::{IResult, bytes::complete::take, number::complete::le_u8};
-use num_enum::FromPrimitive;
+use nom::{
+    combinator::{all_consuming, rest},
+    multi::{length_data, many0},
+    number::complete::{le_u8, le_u16},
+    sequence::tuple,
+};
+use num_enum::{FromPrimitive, IntoPrimitive};
 use std::fmt;
+use crate::error::{self, Kind, Layer, PResult};
+use crate::hci::field;
+
+mod gatt;
+mod names;
+mod value;
+
+pub use gatt::{Attribute, Database, Gatt};
+pub(crate) use value::appearance;
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, IntoPrimitive)]
 enum OpCode {
     ErrorRsp                = 0x01,
     ExchangeMtuReq          = 0x02,
@@ -31,7 +45,7 @@ enum OpCode {
     ExecuteWriteRsp         = 0x19,
     ReadMultipleVariableReq = 0x20,
     ReadMultipleVariableRsp = 0x21,
-    ReadMultipleVariableNtf = 0x23,
+    MultipleHandleValueNtf  = 0x23,
     HandleValueNtf          = 0x1b,
     HandleValueInd          = 0x1d,
     HandleValueCfm          = 0x1e,
@@ -41,22 +55,471 @@ enum OpCode {
     Other(u8),
 }
 
+impl fmt::Display for OpCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use OpCode::*;
+        let name = match self {
+            ErrorRsp                => "Error Response",
+            ExchangeMtuReq          => "Exchange MTU Request",
+            ExchangeMtuRsp          => "Exchange MTU Response",
+            FindInformationReq      => "Find Information Request",
+            FindInformationRsp      => "Find Information Response",
+            FindByTypeValueReq      => "Find By Type Value Request",
+            FindByTypeValueRsp      => "Find By Type Value Response",
+            ReadByTypeReq           => "Read By Type Request",
+            ReadByTypeRsp           => "Read By Type Response",
+            ReadReq                 => "Read Request",
+            ReadRsp                 => "Read Response",
+            ReadBlobReq             => "Read Blob Request",
+            ReadBlobRsp             => "Read Blob Response",
+            ReadMultipleReq         => "Read Multiple Request",
+            ReadMultipleRsp         => "Read Multiple Response",
+            ReadByGroupTypeReq      => "Read By Group Type Request",
+            ReadByGroupTypeRsp      => "Read By Group Type Response",
+            WriteReq                => "Write Request",
+            WriteRsp                => "Write Response",
+            WriteCmd                => "Write Command",
+            PrepareWriteReq         => "Prepare Write Request",
+            PrepareWriteRsp         => "Prepare Write Response",
+            ExecuteWriteReq         => "Execute Write Request",
+            ExecuteWriteRsp         => "Execute Write Response",
+            ReadMultipleVariableReq => "Read Multiple Variable Request",
+            ReadMultipleVariableRsp => "Read Multiple Variable Response",
+            MultipleHandleValueNtf  => "Multiple Handle Value Notification",
+            HandleValueNtf          => "Handle Value Notification",
+            HandleValueInd          => "Handle Value Indication",
+            HandleValueCfm          => "Handle Value Confirmation",
+            SignedWriteCmd          => "Signed Write Command",
+            Other(_)                => "Unknown",
+        };
+
+        write!(f, "{} (0x{:02x})", name, u8::from(*self))
+    }
+}
+
+fn error_str(error: u8) -> &'static str {
+    match error {
+        0x01 => "Invalid Handle",
+        0x02 => "Read Not Permitted",
+        0x03 => "Write Not Permitted",
+        0x04 => "Invalid PDU",
+        0x05 => "Insufficient Authentication",
+        0x06 => "Request Not Supported",
+        0x07 => "Invalid Offset",
+        0x08 => "Insufficient Authorization",
+        0x09 => "Prepare Queue Full",
+        0x0a => "Attribute Not Found",
+        0x0b => "Attribute Not Long",
+        0x0c => "Encryption Key Size Too Short",
+        0x0d => "Invalid Attribute Value Length",
+        0x0e => "Unlikely Error",
+        0x0f => "Insufficient Encryption",
+        0x10 => "Unsupported Group Type",
+        0x11 => "Insufficient Resources",
+        0x12 => "Database Out Of Sync",
+        0x13 => "Value Not Allowed",
+        0x80..=0x9f => "Application Error",
+        0xfc => "Write Request Rejected",
+        0xfd => "Client Characteristic Configuration Descriptor Improperly Configured",
+        0xfe => "Procedure Already in Progress",
+        0xff => "Out of Range",
+        _    => "Reserved",
+    }
+}
+
+/// Attribute type, either a 16-bit SIG assigned number or a full 128-bit UUID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Uuid {
+    Uuid16(u16),
+    Uuid128(u128),
+}
+
+impl Uuid {
+    /// UUID from its little endian wire format
+    pub fn from_le_bytes(data: &[u8]) -> Option<Uuid> {
+        match data.len() {
+            2  => Some(Uuid::Uuid16(u16::from_le_bytes(data.try_into().unwrap()))),
+            16 => Some(Uuid::Uuid128(u128::from_le_bytes(data.try_into().unwrap()))),
+            _  => None,
+        }
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Uuid::Uuid16(v) => match names::uuid16_str(*v) {
+                Some(name) => write!(f, "{} (0x{:04x})", name, v),
+                None => write!(f, "0x{:04x}", v),
+            },
+            Uuid::Uuid128(v) => {
+                write!(f, "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+                    v >> 96, (v >> 80) & 0xffff, (v >> 64) & 0xffff, (v >> 48) & 0xffff, v & 0xffff_ffff_ffff)
+            },
+        }
+    }
+}
+
+/// Decoded parameters of an ATT PDU. PDUs with the same layout share a
+/// variant, e.g. `HandleValue` is used for writes, notifications and
+/// indications alike.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Param<'a> {
+    Error { request: u8, handle: u16, error: u8 },
+    Mtu(u16),
+    Range { start: u16, end: u16 },
+    /// Handle and attribute type pairs of a Find Information Response
+    Information(Vec<(u16, Uuid)>),
+    FindByTypeValue { start: u16, end: u16, attr_type: u16, value: &'a [u8] },
+    /// Found attribute handle and group end handle pairs
+    HandlesInformation(Vec<(u16, u16)>),
+    ByType { start: u16, end: u16, attr_type: Uuid },
+    /// Handle and value pairs of a Read By Type Response
+    AttributeData(Vec<(u16, &'a [u8])>),
+    /// Handle, end group handle and value of a Read By Group Type Response
+    GroupData(Vec<(u16, u16, &'a [u8])>),
+    Handle(u16),
+    Value(&'a [u8]),
+    HandleOffset { handle: u16, offset: u16 },
+    Handles(Vec<u16>),
+    Values(Vec<&'a [u8]>),
+    HandleValue { handle: u16, value: &'a [u8] },
+    HandleValues(Vec<(u16, &'a [u8])>),
+    PrepareWrite { handle: u16, offset: u16, value: &'a [u8] },
+    ExecuteWrite { flags: u8 },
+    SignedWrite { handle: u16, value: &'a [u8], signature: &'a [u8] },
+    None,
+    Unknown(&'a [u8]),
+}
+
 pub struct Pdu <'a> {
     opcode: OpCode,
-    param: &'a[u8],
+    param: Param<'a>,
 }
 
-impl Pdu <'_> {
-    pub fn parse(input: &'_[u8]) -> IResult<&[u8], Pdu> {
-        let (param, opcode) = le_u8(input)?;
-        let (rem, param) = take(param.len())(param)?;
+/// Split `i` into entries of `len` bytes each, failing unless there's at
+/// least one entry and nothing left over.
+fn entries<'a, T>(i: &'a [u8], len: usize, f: impl Fn(&'a [u8]) -> T) -> PResult<'a, Vec<T>> {
+    if len == 0 || i.is_empty() || !i.len().is_multiple_of(len) {
+        return error::fail(i, Kind::BadLength);
+    }
+    Ok((&i[i.len()..], i.chunks(len).map(f).collect()))
+}
+
+fn handle_at(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
 
-        Ok((rem, Pdu { opcode: OpCode::from(opcode), param }))
+fn uuid(i: &[u8]) -> PResult<'_, Uuid> {
+    match Uuid::from_le_bytes(i) {
+        Some(uuid) => Ok((&i[i.len()..], uuid)),
+        None => error::fail(i, Kind::BadLength),
+    }
+}
+
+fn information(i: &[u8]) -> PResult<'_, Param<'_>> {
+    let (i, format) = le_u8(i)?;
+    let len = match format {
+        0x01 => 4,
+        0x02 => 18,
+        _    => return error::fail(i, Kind::Unsupported),
+    };
+    let (i, list) = entries(i, len, |e| (handle_at(e, 0), Uuid::from_le_bytes(&e[2..]).unwrap()))?;
+
+    Ok((i, Param::Information(list)))
+}
+
+fn by_type(i: &[u8]) -> PResult<'_, Param<'_>> {
+    let (i, (start, end, attr_type)) = tuple((le_u16, le_u16, uuid))(i)?;
+    Ok((i, Param::ByType { start, end, attr_type }))
+}
+
+fn attribute_data(i: &[u8]) -> PResult<'_, Param<'_>> {
+    let (i, len) = le_u8(i)?;
+    if len < 2 {
+        return error::fail(i, Kind::BadLength);
+    }
+    let (i, list) = entries(i, len as usize, |e| (handle_at(e, 0), &e[2..]))?;
+
+    Ok((i, Param::AttributeData(list)))
+}
+
+fn group_data(i: &[u8]) -> PResult<'_, Param<'_>> {
+    let (i, len) = le_u8(i)?;
+    if len < 4 {
+        return error::fail(i, Kind::BadLength);
+    }
+    let (i, list) = entries(i, len as usize, |e| (handle_at(e, 0), handle_at(e, 2), &e[4..]))?;
+
+    Ok((i, Param::GroupData(list)))
+}
+
+fn handle_value(i: &[u8]) -> PResult<'_, Param<'_>> {
+    let (i, (handle, value)) = tuple((le_u16, rest))(i)?;
+    Ok((i, Param::HandleValue { handle, value }))
+}
+
+fn prepare_write(i: &[u8]) -> PResult<'_, Param<'_>> {
+    let (i, (handle, offset, value)) = tuple((le_u16, le_u16, rest))(i)?;
+    Ok((i, Param::PrepareWrite { handle, offset, value }))
+}
+
+fn signed_write(i: &[u8]) -> PResult<'_, Param<'_>> {
+    const SIGNATURE_LEN: usize = 12;
+
+    let (i, handle) = le_u16(i)?;
+    if i.len() < SIGNATURE_LEN {
+        return error::fail(i, Kind::BadLength);
+    }
+    let (value, signature) = i.split_at(i.len() - SIGNATURE_LEN);
+
+    Ok((&i[i.len()..], Param::SignedWrite { handle, value, signature }))
+}
+
+fn param(opcode: OpCode, i: &[u8]) -> PResult<'_, Param<'_>> {
+    use OpCode::*;
+
+    match opcode {
+        ErrorRsp => {
+            let (i, (request, handle, error)) = tuple((le_u8, le_u16, le_u8))(i)?;
+            Ok((i, Param::Error { request, handle, error }))
+        },
+        ExchangeMtuReq | ExchangeMtuRsp => {
+            let (i, mtu) = le_u16(i)?;
+            Ok((i, Param::Mtu(mtu)))
+        },
+        FindInformationReq => {
+            let (i, (start, end)) = tuple((le_u16, le_u16))(i)?;
+            Ok((i, Param::Range { start, end }))
+        },
+        FindInformationRsp => information(i),
+        FindByTypeValueReq => {
+            let (i, (start, end, attr_type, value)) = tuple((le_u16, le_u16, le_u16, rest))(i)?;
+            Ok((i, Param::FindByTypeValue { start, end, attr_type, value }))
+        },
+        FindByTypeValueRsp => {
+            let (i, list) = entries(i, 4, |e| (handle_at(e, 0), handle_at(e, 2)))?;
+            Ok((i, Param::HandlesInformation(list)))
+        },
+        ReadByTypeReq | ReadByGroupTypeReq => by_type(i),
+        ReadByTypeRsp => attribute_data(i),
+        ReadByGroupTypeRsp => group_data(i),
+        ReadReq => {
+            let (i, handle) = le_u16(i)?;
+            Ok((i, Param::Handle(handle)))
+        },
+        ReadBlobReq => {
+            let (i, (handle, offset)) = tuple((le_u16, le_u16))(i)?;
+            Ok((i, Param::HandleOffset { handle, offset }))
+        },
+        ReadRsp | ReadBlobRsp | ReadMultipleRsp => {
+            let (i, value) = rest(i)?;
+            Ok((i, Param::Value(value)))
+        },
+        ReadMultipleReq | ReadMultipleVariableReq => {
+            let (i, handles) = entries(i, 2, |e| handle_at(e, 0))?;
+            Ok((i, Param::Handles(handles)))
+        },
+        ReadMultipleVariableRsp => {
+            let (i, values) = many0(length_data(le_u16))(i)?;
+            Ok((i, Param::Values(values)))
+        },
+        MultipleHandleValueNtf => {
+            let (i, values) = many0(tuple((le_u16, length_data(le_u16))))(i)?;
+            Ok((i, Param::HandleValues(values)))
+        },
+        WriteReq | WriteCmd | HandleValueNtf | HandleValueInd => handle_value(i),
+        PrepareWriteReq | PrepareWriteRsp => prepare_write(i),
+        ExecuteWriteReq => {
+            let (i, flags) = le_u8(i)?;
+            Ok((i, Param::ExecuteWrite { flags }))
+        },
+        SignedWriteCmd => signed_write(i),
+        WriteRsp | ExecuteWriteRsp | HandleValueCfm => Ok((i, Param::None)),
+        Other(_) => Ok((&i[i.len()..], Param::Unknown(i))),
+    }
+}
+
+impl<'a> Pdu<'a> {
+    fn parser(input: &'a [u8]) -> PResult<'a, Pdu<'a>> {
+        let (i, opcode) = le_u8(input)?;
+        let opcode = OpCode::from(opcode);
+        let (rem, param) = all_consuming(|i| param(opcode, i))(i)?;
+
+        Ok((rem, Pdu { opcode, param }))
+    }
+
+    pub fn parse(input: &'a [u8]) -> error::Result<(&'a [u8], Pdu<'a>)> {
+        error::finish(Layer::Att, input, Pdu::parser(input))
+    }
+
+    pub fn opcode(&self) -> u8 {
+        u8::from(self.opcode)
+    }
+
+    pub fn param(&self) -> &Param<'a> {
+        &self.param
     }
 }
 
 impl fmt::Display for Pdu<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?} {:02x?}", self.opcode, self.param)
+        write!(f, "{}", self.opcode)?;
+
+        match &self.param {
+            Param::Error { request, handle, error } => {
+                field(f, "Request", OpCode::from(*request))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Error", format!("{} (0x{:02x})", error_str(*error), error))
+            },
+            Param::Mtu(mtu) => {
+                let name = match self.opcode {
+                    OpCode::ExchangeMtuReq => "Client RX MTU",
+                    _                      => "Server RX MTU",
+                };
+                field(f, name, mtu)
+            },
+            Param::Range { start, end } => {
+                field(f, "Handle range", format!("0x{:04x}-0x{:04x}", start, end))
+            },
+            Param::Information(list) => {
+                list.iter().try_for_each(|(handle, uuid)| field(f, &format!("Handle 0x{:04x}", handle), uuid))
+            },
+            Param::FindByTypeValue { start, end, attr_type, value } => {
+                field(f, "Handle range", format!("0x{:04x}-0x{:04x}", start, end))?;
+                field(f, "Attribute type", Uuid::Uuid16(*attr_type))?;
+                field(f, "Value", format!("{:02x?}", value))
+            },
+            Param::HandlesInformation(list) => {
+                list.iter().try_for_each(|(found, end)| {
+                    field(f, "Handle range", format!("0x{:04x}-0x{:04x}", found, end))
+                })
+            },
+            Param::ByType { start, end, attr_type } => {
+                let name = match self.opcode {
+                    OpCode::ReadByGroupTypeReq => "Attribute group type",
+                    _                          => "Attribute type",
+                };
+                field(f, "Handle range", format!("0x{:04x}-0x{:04x}", start, end))?;
+                field(f, name, attr_type)
+            },
+            Param::AttributeData(list) => {
+                // The response doesn't repeat the requested type, but the
+                // value of a characteristic always directly follows its
+                // declaration
+                let decls: Option<Vec<_>> = list.iter()
+                    .map(|(handle, value)| {
+                        gatt::characteristic(value).filter(|(_, value, _)| Some(*value) == handle.checked_add(1))
+                    })
+                    .collect();
+                match decls {
+                    Some(decls) => list.iter().zip(decls).try_for_each(|((handle, _), (properties, value, uuid))| {
+                        field(f, &format!("Handle 0x{:04x}", handle), format_args!(
+                            "Characteristic {}, value 0x{:04x}, properties {}", uuid, value, gatt::Properties(properties)))
+                    }),
+                    None => list.iter().try_for_each(|(handle, value)| {
+                        field(f, &format!("Handle 0x{:04x}", handle), format!("{:02x?}", value))
+                    }),
+                }
+            },
+            Param::GroupData(list) => {
+                list.iter().try_for_each(|(start, end, value)| {
+                    let name = format!("Handle range 0x{:04x}-0x{:04x}", start, end);
+                    match Uuid::from_le_bytes(value) {
+                        Some(uuid) => field(f, &name, uuid),
+                        None => field(f, &name, format!("{:02x?}", value)),
+                    }
+                })
+            },
+            Param::Handle(handle) => field(f, "Handle", format!("0x{:04x}", handle)),
+            Param::Value(value) => field(f, "Value", format!("{:02x?}", value)),
+            Param::HandleOffset { handle, offset } => {
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Offset", offset)
+            },
+            Param::Handles(handles) => {
+                handles.iter().try_for_each(|handle| field(f, "Handle", format!("0x{:04x}", handle)))
+            },
+            Param::Values(values) => {
+                values.iter().try_for_each(|value| field(f, "Value", format!("{:02x?}", value)))
+            },
+            Param::HandleValue { handle, value } => {
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Value", format!("{:02x?}", value))
+            },
+            Param::HandleValues(values) => {
+                values.iter().try_for_each(|(handle, value)| {
+                    field(f, "Handle", format!("0x{:04x}", handle))?;
+                    field(f, "Value", format!("{:02x?}", value))
+                })
+            },
+            Param::PrepareWrite { handle, offset, value } => {
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Offset", offset)?;
+                field(f, "Value", format!("{:02x?}", value))
+            },
+            Param::ExecuteWrite { flags } => {
+                let name = match flags {
+                    0x00 => "Cancel all prepared writes",
+                    0x01 => "Immediately write all pending prepared values",
+                    _    => "Reserved",
+                };
+                field(f, "Flags", format!("{} (0x{:02x})", name, flags))
+            },
+            Param::SignedWrite { handle, value, signature } => {
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Value", format!("{:02x?}", value))?;
+                field(f, "Signature", format!("{:02x?}", signature))
+            },
+            Param::None => Ok(()),
+            Param::Unknown(data) => write!(f, " {:02x?}", data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pdu, Param, Uuid};
+
+    #[test]
+    fn read_by_type_rsp() {
+        let (_, pdu) = Pdu::parse(&[0x09, 0x07, 0x02, 0x00, 0x20, 0x03, 0x00, 0x05, 0x2a]).unwrap();
+
+        assert_eq!(pdu.param(), &Param::AttributeData(vec![(0x0002, &[0x20, 0x03, 0x00, 0x05, 0x2a][..])]));
+        assert_eq!(pdu.to_string(), "Read By Type Response (0x09)\
+            \n        Handle 0x0002: Characteristic Service Changed (0x2a05), value 0x0003, properties 0x20 (Indicate)");
+
+        // Not a characteristic declaration, as the handles don't line up
+        let (_, pdu) = Pdu::parse(&[0x09, 0x07, 0x02, 0x00, 0x20, 0x07, 0x00, 0x05, 0x2a]).unwrap();
+        assert_eq!(pdu.to_string(), "Read By Type Response (0x09)\n        Handle 0x0002: [20, 07, 00, 05, 2a]");
+    }
+
+    #[test]
+    fn read_by_group_type_rsp() {
+        let (_, pdu) = Pdu::parse(&[0x11, 0x06, 0x01, 0x00, 0x08, 0x00, 0x01, 0x18]).unwrap();
+
+        assert_eq!(pdu.to_string(), "Read By Group Type Response (0x11)\
+            \n        Handle range 0x0001-0x0008: Generic Attribute (0x1801)");
+    }
+
+    #[test]
+    fn error_rsp() {
+        let (_, pdu) = Pdu::parse(&[0x01, 0x10, 0x22, 0x00, 0x0a]).unwrap();
+
+        assert_eq!(pdu.to_string(), "Error Response (0x01)\
+            \n        Request: Read By Group Type Request (0x10)\
+            \n        Handle: 0x0022\
+            \n        Error: Attribute Not Found (0x0a)");
+    }
+
+    #[test]
+    fn uuid128() {
+        let uuid = Uuid::from_le_bytes(&[
+            0xfb, 0x34, 0x9b, 0x5f, 0x80, 0x00, 0x00, 0x80, 0x00, 0x10, 0x00, 0x00, 0x0d, 0x18, 0x00, 0x00,
+        ]).unwrap();
+
+        assert_eq!(uuid.to_string(), "0000180d-0000-1000-8000-00805f9b34fb");
+        assert!(Pdu::parse(&[0x09, 0x07, 0x02, 0x00]).is_err());
     }
 }
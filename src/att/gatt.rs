@@ -0,0 +1,439 @@
+use std::{fmt, collections::{BTreeMap, HashMap}};
+use crate::hci::{bit_names, field};
+use crate::monitor::{BdAddr, Direction};
+use super::{OpCode, Param, Pdu, Uuid, value};
+
+const PRIMARY_SERVICE: Uuid = Uuid::Uuid16(0x2800);
+const SECONDARY_SERVICE: Uuid = Uuid::Uuid16(0x2801);
+const INCLUDE: Uuid = Uuid::Uuid16(0x2802);
+const CHARACTERISTIC: Uuid = Uuid::Uuid16(0x2803);
+
+/// One attribute of a reconstructed GATT database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Attribute {
+    Service { primary: bool, uuid: Uuid, end: u16 },
+    /// Included service, the UUID is only sent for 16-bit UUIDs
+    Include { start: u16, end: u16, uuid: Option<Uuid> },
+    Characteristic { properties: u8, value_handle: u16, uuid: Uuid },
+    Value { uuid: Uuid },
+    Descriptor { uuid: Uuid },
+}
+
+/// Characteristic properties bit field
+pub(super) struct Properties(pub u8);
+
+impl fmt::Display for Properties {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const BITS: [&str; 8] = [
+            "Broadcast", "Read", "Write Without Response", "Write",
+            "Notify", "Indicate", "Authenticated Signed Writes", "Extended Properties",
+        ];
+
+        f.write_str(&bit_names(self.0 as u16, 2, &BITS))
+    }
+}
+
+/// Properties, value handle and UUID from the value of a characteristic
+/// declaration
+pub(super) fn characteristic(value: &[u8]) -> Option<(u8, u16, Uuid)> {
+    let uuid = value.get(3..).and_then(Uuid::from_le_bytes)?;
+    Some((value[0], super::handle_at(value, 1), uuid))
+}
+
+/// GATT database of one server on one connection, as far as it could be
+/// reconstructed from the discovery procedures seen in the capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Database {
+    pub index: u16,
+    pub handle: u16,
+    pub peer: Option<BdAddr>,
+    /// Whether the server is the local device, i.e. requests were received
+    pub local: bool,
+    pub attributes: BTreeMap<u16, Attribute>,
+}
+
+impl Database {
+    fn new(index: u16, handle: u16, peer: Option<BdAddr>, local: bool) -> Self {
+        Database { index, handle, peer, local, attributes: BTreeMap::new() }
+    }
+
+    /// Characteristic declaration a value or descriptor handle belongs to
+    fn characteristic(&self, handle: u16) -> Option<(u16, &Attribute)> {
+        self.attributes.range(..handle).rev()
+            .find(|(_, a)| matches!(a, Attribute::Characteristic { .. }))
+            .map(|(h, a)| (*h, a))
+    }
+
+    /// Short description of the attribute at `handle`, if it's known
+    pub fn describe(&self, handle: u16) -> Option<String> {
+        let desc = match self.attributes.get(&handle)? {
+            Attribute::Service { primary: true, uuid, .. } => format!("Primary service {}", uuid),
+            Attribute::Service { primary: false, uuid, .. } => format!("Secondary service {}", uuid),
+            Attribute::Include { start, end, .. } => format!("Include 0x{:04x}-0x{:04x}", start, end),
+            Attribute::Characteristic { uuid, .. } => format!("Characteristic declaration {}", uuid),
+            Attribute::Value { uuid } => format!("Characteristic value {}", uuid),
+            Attribute::Descriptor { uuid } => match self.characteristic(handle) {
+                Some((_, Attribute::Characteristic { uuid: chr, .. })) => format!("Descriptor {} of {}", uuid, chr),
+                _ => format!("Descriptor {}", uuid),
+            },
+        };
+
+        Some(desc)
+    }
+
+    /// Description of the attribute at `handle` followed by the decoded
+    /// fields of `value`, if it's a value we know how to decode
+    fn annotate(&self, handle: u16, value: Option<&[u8]>) -> Option<String> {
+        let mut desc = self.describe(handle)?;
+        let uuid = match self.attributes.get(&handle)? {
+            Attribute::Value { uuid } | Attribute::Descriptor { uuid } => *uuid,
+            _ => return Some(desc),
+        };
+        if let (Uuid::Uuid16(uuid), Some(value)) = (uuid, value) {
+            append(&mut desc, value::fields(uuid, value));
+        }
+
+        Some(desc)
+    }
+
+    /// UUID of the characteristic whose value lives at `handle`
+    pub fn value_uuid(&self, handle: u16) -> Option<Uuid> {
+        match self.attributes.get(&handle)? {
+            Attribute::Value { uuid } => Some(*uuid),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, handle: u16, attr: Attribute) {
+        self.attributes.insert(handle, attr);
+    }
+
+    /// Add a handle from Find Information, unless it's already known as
+    /// something more specific than a descriptor
+    fn information(&mut self, handle: u16, uuid: Uuid) {
+        if [PRIMARY_SERVICE, SECONDARY_SERVICE, INCLUDE, CHARACTERISTIC].contains(&uuid) {
+            return;
+        }
+        self.attributes.entry(handle).or_insert(Attribute::Descriptor { uuid });
+    }
+}
+
+impl fmt::Display for Database {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} GATT database, handle 0x{:04x} (index {})",
+            if self.local { "Local" } else { "Remote" }, self.handle, self.index)?;
+        if let Some(peer) = self.peer {
+            write!(f, ": {}", peer)?;
+        }
+
+        for (handle, attr) in &self.attributes {
+            let name = format!("0x{:04x}", handle);
+            match attr {
+                Attribute::Service { primary, uuid, end } => {
+                    let kind = if *primary { "Primary" } else { "Secondary" };
+                    field(f, &name, format_args!("{} Service {} (end 0x{:04x})", kind, uuid, end))?;
+                },
+                Attribute::Include { start, end, uuid } => {
+                    write!(f, "\n        {}:   Include 0x{:04x}-0x{:04x}", name, start, end)?;
+                    if let Some(uuid) = uuid {
+                        write!(f, " {}", uuid)?;
+                    }
+                },
+                Attribute::Characteristic { properties, value_handle, uuid } => {
+                    write!(f, "\n        {}:   Characteristic {}, value 0x{:04x}, properties {}",
+                        name, uuid, value_handle, Properties(*properties))?;
+                },
+                Attribute::Value { .. } => (),
+                Attribute::Descriptor { uuid } => {
+                    write!(f, "\n        {}:     Descriptor {}", name, uuid)?;
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn append(desc: &mut String, fields: Option<value::Fields>) {
+    for (name, val) in fields.into_iter().flatten() {
+        desc.push_str(&format!("\n        {}: {}", name, val));
+    }
+}
+
+/// Outstanding request whose response carries discovery results or refers
+/// to a handle it doesn't repeat itself.
+#[derive(Debug, Clone)]
+enum Request {
+    Group(Uuid),
+    Type(Uuid),
+    FindByType(Option<Uuid>),
+    Information,
+    Read(u16),
+    ReadMultiple(Vec<u16>),
+}
+
+/// Key of one GATT server: controller index, connection handle and the
+/// direction requests travel in.
+type Server = (u16, u16, Direction);
+
+/// Key of one ATT bearer: a GATT server and the CID of the bearer, 0x0004
+/// or the local CID of an EATT channel. Each bearer has its own outstanding
+/// request.
+type Bearer = (u16, u16, u16, Direction);
+
+/// Reconstructs GATT databases from the ATT traffic of all connections.
+#[derive(Debug, Default)]
+pub struct Gatt {
+    open: HashMap<Server, Database>,
+    closed: Vec<Database>,
+    pending: HashMap<Bearer, Request>,
+}
+
+fn other(dir: Direction) -> Direction {
+    match dir {
+        Direction::Tx => Direction::Rx,
+        Direction::Rx => Direction::Tx,
+    }
+}
+
+impl Gatt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one ATT PDU sent in `dir` on the bearer with CID `bearer` of a
+    /// connection. Returns a description of each attribute it refers to
+    /// that's known.
+    pub fn pdu(&mut self, index: u16, handle: u16, bearer: u16, dir: Direction,
+               peer: Option<BdAddr>, pdu: &Pdu) -> Vec<String> {
+        use OpCode::*;
+
+        // Requests flow from the client towards the server, while responses,
+        // notifications and indications flow the other way.
+        let req_dir = match pdu.opcode {
+            ErrorRsp | ExchangeMtuRsp | FindInformationRsp | FindByTypeValueRsp | ReadByTypeRsp |
+            ReadRsp | ReadBlobRsp | ReadMultipleRsp | ReadByGroupTypeRsp | WriteRsp |
+            PrepareWriteRsp | ExecuteWriteRsp | ReadMultipleVariableRsp |
+            HandleValueNtf | HandleValueInd | MultipleHandleValueNtf => other(dir),
+            _ => dir,
+        };
+        let key = (index, handle, bearer, req_dir);
+        let db = self.open.entry((index, handle, req_dir))
+            .or_insert_with(|| Database::new(index, handle, peer, req_dir == Direction::Rx));
+
+        let desc = match (pdu.opcode, &pdu.param) {
+            (ReadByGroupTypeReq, Param::ByType { attr_type, .. }) => {
+                self.pending.insert(key, Request::Group(*attr_type));
+                None
+            },
+            (ReadByTypeReq, Param::ByType { attr_type, .. }) => {
+                self.pending.insert(key, Request::Type(*attr_type));
+                None
+            },
+            (FindByTypeValueReq, Param::FindByTypeValue { attr_type, value, .. }) => {
+                let uuid = if Uuid::Uuid16(*attr_type) == PRIMARY_SERVICE { Uuid::from_le_bytes(value) } else { None };
+                self.pending.insert(key, Request::FindByType(uuid));
+                None
+            },
+            (FindInformationReq, _) => {
+                self.pending.insert(key, Request::Information);
+                None
+            },
+            (ReadReq, Param::Handle(h)) | (ReadBlobReq, Param::HandleOffset { handle: h, .. }) => {
+                self.pending.insert(key, Request::Read(*h));
+                db.describe(*h)
+            },
+            (ReadByGroupTypeRsp, Param::GroupData(list)) => {
+                if let Some(Request::Group(group)) = self.pending.remove(&key) {
+                    for (start, end, value) in list {
+                        if let (Some(uuid), PRIMARY_SERVICE | SECONDARY_SERVICE) = (Uuid::from_le_bytes(value), group) {
+                            db.insert(*start, Attribute::Service { primary: group == PRIMARY_SERVICE, uuid, end: *end });
+                        }
+                    }
+                }
+                None
+            },
+            (FindByTypeValueRsp, Param::HandlesInformation(list)) => {
+                if let Some(Request::FindByType(Some(uuid))) = self.pending.remove(&key) {
+                    for (start, end) in list {
+                        db.insert(*start, Attribute::Service { primary: true, uuid, end: *end });
+                    }
+                }
+                None
+            },
+            (ReadByTypeRsp, Param::AttributeData(list)) => {
+                match self.pending.remove(&key) {
+                    Some(Request::Type(INCLUDE)) => {
+                        for (h, value) in list {
+                            if value.len() >= 4 {
+                                let (start, end) = (super::handle_at(value, 0), super::handle_at(value, 2));
+                                let uuid = Uuid::from_le_bytes(&value[4..]);
+                                db.insert(*h, Attribute::Include { start, end, uuid });
+                            }
+                        }
+                    },
+                    Some(Request::Type(CHARACTERISTIC)) => {
+                        for (h, value) in list {
+                            if let Some((properties, value_handle, uuid)) = characteristic(value) {
+                                db.insert(*h, Attribute::Characteristic { properties, value_handle, uuid });
+                                db.insert(value_handle, Attribute::Value { uuid });
+                            }
+                        }
+                    },
+                    Some(Request::Type(attr_type)) => {
+                        // Reading a characteristic by its type, e.g. the Database Hash
+                        let mut desc = attr_type.to_string();
+                        if let Uuid::Uuid16(uuid) = attr_type {
+                            list.iter().for_each(|(_, v)| append(&mut desc, value::fields(uuid, v)));
+                        }
+                        return vec![desc];
+                    },
+                    _ => (),
+                }
+                None
+            },
+            (FindInformationRsp, Param::Information(list)) => {
+                if let Some(Request::Information) = self.pending.remove(&key) {
+                    for (h, uuid) in list {
+                        db.information(*h, *uuid);
+                    }
+                }
+                None
+            },
+            (ReadRsp, Param::Value(value)) => {
+                match self.pending.remove(&key) {
+                    Some(Request::Read(h)) => db.annotate(h, Some(value)),
+                    _ => None,
+                }
+            },
+            (ReadBlobRsp, _) | (ErrorRsp, _) => {
+                match self.pending.remove(&key) {
+                    Some(Request::Read(h)) => db.describe(h),
+                    _ => None,
+                }
+            },
+            (ReadMultipleReq | ReadMultipleVariableReq, Param::Handles(handles)) => {
+                self.pending.insert(key, Request::ReadMultiple(handles.clone()));
+                return handles.iter().filter_map(|h| db.describe(*h)).collect();
+            },
+            (ReadMultipleRsp, _) => {
+                // Values of fixed length characteristics are simply
+                // concatenated, so there's no telling them apart
+                match self.pending.remove(&key) {
+                    Some(Request::ReadMultiple(handles)) => {
+                        return handles.iter().filter_map(|h| db.describe(*h)).collect();
+                    },
+                    _ => None,
+                }
+            },
+            (ReadMultipleVariableRsp, Param::Values(values)) => {
+                match self.pending.remove(&key) {
+                    Some(Request::ReadMultiple(handles)) => {
+                        return handles.iter().zip(values)
+                            .filter_map(|(h, v)| db.annotate(*h, Some(v)))
+                            .collect();
+                    },
+                    _ => None,
+                }
+            },
+            (_, Param::HandleValues(list)) => {
+                return list.iter().filter_map(|(h, v)| db.annotate(*h, Some(v))).collect();
+            },
+            (_, Param::HandleValue { handle: h, value }) |
+            (_, Param::SignedWrite { handle: h, value, .. }) => db.annotate(*h, Some(value)),
+            (_, Param::PrepareWrite { handle: h, .. }) => db.describe(*h),
+            _ => None,
+        };
+
+        desc.into_iter().collect()
+    }
+
+    /// Database of the server on a connection
+    pub fn get(&self, index: u16, handle: u16, server_is_local: bool) -> Option<&Database> {
+        let dir = if server_is_local { Direction::Rx } else { Direction::Tx };
+        self.open.get(&(index, handle, dir))
+    }
+
+    /// Retire the databases of a connection once it's gone, so that a later
+    /// connection reusing the handle starts from scratch
+    pub fn close(&mut self, index: u16, handle: u16) {
+        self.pending.retain(|(i, h, _, _), _| (*i, *h) != (index, handle));
+        for dir in [Direction::Tx, Direction::Rx] {
+            if let Some(db) = self.open.remove(&(index, handle, dir)) {
+                self.closed.push(db);
+            }
+        }
+    }
+
+    /// All databases with at least one discovered attribute
+    pub fn databases(&self) -> Vec<&Database> {
+        let mut all: Vec<_> = self.closed.iter().chain(self.open.values())
+            .filter(|db| !db.attributes.is_empty())
+            .collect();
+        all.sort_by_key(|db| (db.index, db.handle, !db.local));
+        all
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gatt, Attribute, Pdu, Uuid};
+    use crate::monitor::Direction;
+
+    fn feed(gatt: &mut Gatt, dir: Direction, data: &[u8]) -> Option<String> {
+        let (_, pdu) = Pdu::parse(data).unwrap();
+        gatt.pdu(0, 0x0040, 0x0004, dir, None, &pdu).into_iter().next()
+    }
+
+    #[test]
+    fn discover_and_annotate() {
+        let mut gatt = Gatt::new();
+
+        feed(&mut gatt, Direction::Tx, &[0x10, 0x01, 0x00, 0xff, 0xff, 0x00, 0x28]);
+        feed(&mut gatt, Direction::Rx, &[0x11, 0x06, 0x10, 0x00, 0x14, 0x00, 0x0d, 0x18]);
+        feed(&mut gatt, Direction::Tx, &[0x08, 0x10, 0x00, 0x14, 0x00, 0x03, 0x28]);
+        feed(&mut gatt, Direction::Rx, &[0x09, 0x07, 0x11, 0x00, 0x10, 0x12, 0x00, 0x37, 0x2a]);
+        feed(&mut gatt, Direction::Tx, &[0x04, 0x13, 0x00, 0x14, 0x00]);
+        feed(&mut gatt, Direction::Rx, &[0x05, 0x01, 0x13, 0x00, 0x02, 0x29]);
+
+        let db = gatt.get(0, 0x0040, false).unwrap();
+        assert_eq!(db.attributes.get(&0x0010), Some(&Attribute::Service { primary: true, uuid: Uuid::Uuid16(0x180d), end: 0x0014 }));
+        assert_eq!(db.value_uuid(0x0012), Some(Uuid::Uuid16(0x2a37)));
+
+        assert_eq!(feed(&mut gatt, Direction::Rx, &[0x1b, 0x12, 0x00, 0x00, 0x48]).as_deref(), Some("Characteristic value Heart Rate Measurement (0x2a37)\
+            \n        Flags: 0x00\
+            \n        Heart rate: 72 bpm\
+            \n        Sensor contact: Not supported"));
+        assert_eq!(feed(&mut gatt, Direction::Tx, &[0x12, 0x13, 0x00, 0x01, 0x00]).as_deref(), Some("Descriptor Client Characteristic Configuration (0x2902) of Heart Rate Measurement (0x2a37)\
+            \n        Configuration: 0x0001 (Notification)"));
+
+        gatt.close(0, 0x0040);
+        assert_eq!(gatt.databases().len(), 1);
+    }
+
+    #[test]
+    fn enhanced_bearers() {
+        let mut gatt = Gatt::new();
+        let mut feed = |bearer, dir, data: &[u8]| {
+            let (_, pdu) = Pdu::parse(data).unwrap();
+            gatt.pdu(0, 0x0040, bearer, dir, None, &pdu)
+        };
+
+        feed(0x0004, Direction::Tx, &[0x08, 0x01, 0x00, 0xff, 0xff, 0x03, 0x28]);
+        feed(0x0004, Direction::Rx, &[0x09, 0x07, 0x02, 0x00, 0x02, 0x03, 0x00, 0x00, 0x2a,
+                                      0x04, 0x00, 0x02, 0x05, 0x00, 0x19, 0x2a]);
+
+        // Requests outstanding on two EATT bearers at once
+        feed(0x0040, Direction::Tx, &[0x0a, 0x03, 0x00]);
+        assert_eq!(feed(0x0041, Direction::Tx, &[0x20, 0x05, 0x00, 0x03, 0x00]).len(), 2);
+        assert_eq!(feed(0x0041, Direction::Rx, &[0x21, 0x01, 0x00, 0x64, 0x02, 0x00, 0x41, 0x42]), vec![
+            "Characteristic value Battery Level (0x2a19)\n        Battery level: 100%".to_string(),
+            "Characteristic value Device Name (0x2a00)\n        Name: \"AB\"".to_string(),
+        ]);
+        assert_eq!(feed(0x0040, Direction::Rx, &[0x0b, 0x41]), vec![
+            "Characteristic value Device Name (0x2a00)\n        Name: \"A\"".to_string(),
+        ]);
+    }
+}
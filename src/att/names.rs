@@ -0,0 +1,440 @@
+fn service_str(uuid: u16) -> Option<&'static str> {
+    let name = match uuid {
+        0x1800 => "Generic Access",
+        0x1801 => "Generic Attribute",
+        0x1802 => "Immediate Alert",
+        0x1803 => "Link Loss",
+        0x1804 => "Tx Power",
+        0x1805 => "Current Time",
+        0x1806 => "Reference Time Update",
+        0x1807 => "Next DST Change",
+        0x1808 => "Glucose",
+        0x1809 => "Health Thermometer",
+        0x180a => "Device Information",
+        0x180d => "Heart Rate",
+        0x180e => "Phone Alert Status",
+        0x180f => "Battery",
+        0x1810 => "Blood Pressure",
+        0x1811 => "Alert Notification",
+        0x1812 => "Human Interface Device",
+        0x1813 => "Scan Parameters",
+        0x1814 => "Running Speed and Cadence",
+        0x1815 => "Automation IO",
+        0x1816 => "Cycling Speed and Cadence",
+        0x1818 => "Cycling Power",
+        0x1819 => "Location and Navigation",
+        0x181a => "Environmental Sensing",
+        0x181b => "Body Composition",
+        0x181c => "User Data",
+        0x181d => "Weight Scale",
+        0x181e => "Bond Management",
+        0x181f => "Continuous Glucose Monitoring",
+        0x1820 => "Internet Protocol Support",
+        0x1821 => "Indoor Positioning",
+        0x1822 => "Pulse Oximeter",
+        0x1823 => "HTTP Proxy",
+        0x1824 => "Transport Discovery",
+        0x1825 => "Object Transfer",
+        0x1826 => "Fitness Machine",
+        0x1827 => "Mesh Provisioning",
+        0x1828 => "Mesh Proxy",
+        0x1829 => "Reconnection Configuration",
+        0x183a => "Insulin Delivery",
+        0x183b => "Binary Sensor",
+        0x183c => "Emergency Configuration",
+        0x183d => "Authorization Control",
+        0x183e => "Physical Activity Monitor",
+        0x183f => "Elapsed Time",
+        0x1840 => "Generic Health Sensor",
+        0x1843 => "Audio Input Control",
+        0x1844 => "Volume Control",
+        0x1845 => "Volume Offset Control",
+        0x1846 => "Coordinated Set Identification",
+        0x1847 => "Device Time",
+        0x1848 => "Media Control",
+        0x1849 => "Generic Media Control",
+        0x184a => "Constant Tone Extension",
+        0x184b => "Telephone Bearer",
+        0x184c => "Generic Telephone Bearer",
+        0x184d => "Microphone Control",
+        0x184e => "Audio Stream Control",
+        0x184f => "Broadcast Audio Scan",
+        0x1850 => "Published Audio Capabilities",
+        0x1851 => "Basic Audio Announcement",
+        0x1852 => "Broadcast Audio Announcement",
+        0x1853 => "Common Audio",
+        0x1854 => "Hearing Access",
+        0x1855 => "Telephony and Media Audio",
+        0x1856 => "Public Broadcast Announcement",
+        0x1857 => "Electronic Shelf Label",
+        0x1858 => "Gaming Audio",
+        0x1859 => "Mesh Proxy Solicitation",
+        _      => return None,
+    };
+
+    Some(name)
+}
+
+fn unit_str(uuid: u16) -> Option<&'static str> {
+    let name = match uuid {
+        0x2700 => "unitless",
+        0x2701 => "length (metre)",
+        0x2702 => "mass (kilogram)",
+        0x2703 => "time (second)",
+        0x2704 => "electric current (ampere)",
+        0x2705 => "thermodynamic temperature (kelvin)",
+        0x2706 => "amount of substance (mole)",
+        0x2707 => "luminous intensity (candela)",
+        0x2710 => "area (square metres)",
+        0x2711 => "volume (cubic metres)",
+        0x2712 => "velocity (metres per second)",
+        0x2713 => "acceleration (metres per second squared)",
+        0x2714 => "wavenumber (reciprocal metre)",
+        0x2715 => "density (kilogram per cubic metre)",
+        0x2716 => "surface density (kilogram per square metre)",
+        0x2717 => "specific volume (cubic metre per kilogram)",
+        0x2718 => "current density (ampere per square metre)",
+        0x2719 => "magnetic field strength (ampere per metre)",
+        0x271a => "amount concentration (mole per cubic metre)",
+        0x271b => "mass concentration (kilogram per cubic metre)",
+        0x271c => "luminance (candela per square metre)",
+        0x271d => "refractive index",
+        0x271e => "relative permeability",
+        0x2720 => "plane angle (radian)",
+        0x2721 => "solid angle (steradian)",
+        0x2722 => "frequency (hertz)",
+        0x2723 => "force (newton)",
+        0x2724 => "pressure (pascal)",
+        0x2725 => "energy (joule)",
+        0x2726 => "power (watt)",
+        0x2727 => "electric charge (coulomb)",
+        0x2728 => "electric potential difference (volt)",
+        0x2729 => "capacitance (farad)",
+        0x272a => "electric resistance (ohm)",
+        0x272b => "electric conductance (siemens)",
+        0x272c => "magnetic flux (weber)",
+        0x272d => "magnetic flux density (tesla)",
+        0x272e => "inductance (henry)",
+        0x272f => "Celsius temperature (degree Celsius)",
+        0x2730 => "luminous flux (lumen)",
+        0x2731 => "illuminance (lux)",
+        0x2732 => "activity referred to a radionuclide (becquerel)",
+        0x2733 => "absorbed dose (gray)",
+        0x2734 => "dose equivalent (sievert)",
+        0x2735 => "catalytic activity (katal)",
+        0x2760 => "time (minute)",
+        0x2761 => "time (hour)",
+        0x2762 => "time (day)",
+        0x2763 => "plane angle (degree)",
+        0x2764 => "plane angle (minute)",
+        0x2765 => "plane angle (second)",
+        0x2766 => "area (hectare)",
+        0x2767 => "volume (litre)",
+        0x2768 => "mass (tonne)",
+        0x2780 => "pressure (bar)",
+        0x2781 => "pressure (millimetre of mercury)",
+        0x2782 => "length (ångström)",
+        0x2783 => "length (nautical mile)",
+        0x2784 => "area (barn)",
+        0x2785 => "velocity (knot)",
+        0x2786 => "logarithmic radio quantity (neper)",
+        0x2787 => "logarithmic radio quantity (bel)",
+        0x27a0 => "length (yard)",
+        0x27a1 => "length (parsec)",
+        0x27a2 => "length (inch)",
+        0x27a3 => "length (foot)",
+        0x27a4 => "length (mile)",
+        0x27a5 => "pressure (pound-force per square inch)",
+        0x27a6 => "velocity (kilometre per hour)",
+        0x27a7 => "velocity (mile per hour)",
+        0x27a8 => "angular velocity (revolution per minute)",
+        0x27a9 => "energy (gram calorie)",
+        0x27aa => "energy (kilogram calorie)",
+        0x27ab => "energy (kilowatt hour)",
+        0x27ac => "thermodynamic temperature (degree Fahrenheit)",
+        0x27ad => "percentage",
+        0x27ae => "per mille",
+        0x27af => "period (beats per minute)",
+        0x27b0 => "electric charge (ampere hours)",
+        0x27b1 => "mass density (milligram per decilitre)",
+        0x27b2 => "mass density (millimole per litre)",
+        0x27b3 => "time (year)",
+        0x27b4 => "time (month)",
+        0x27b5 => "concentration (count per cubic metre)",
+        0x27b6 => "irradiance (watt per square metre)",
+        0x27b7 => "milliliter (per kilogram per minute)",
+        0x27b8 => "mass (pound)",
+        0x27b9 => "metabolic equivalent",
+        0x27ba => "step (per minute)",
+        0x27bc => "stroke (per minute)",
+        0x27bd => "pace (kilometre per minute)",
+        0x27be => "luminous efficacy (lumen per watt)",
+        0x27bf => "luminous energy (lumen hour)",
+        0x27c0 => "luminous exposure (lux hour)",
+        0x27c1 => "mass flow (gram per second)",
+        0x27c2 => "volume flow (litre per second)",
+        0x27c3 => "sound pressure (decibel)",
+        0x27c4 => "parts per million",
+        0x27c5 => "parts per billion",
+        _      => return None,
+    };
+
+    Some(name)
+}
+
+fn declaration_str(uuid: u16) -> Option<&'static str> {
+    let name = match uuid {
+        0x2800 => "Primary Service",
+        0x2801 => "Secondary Service",
+        0x2802 => "Include",
+        0x2803 => "Characteristic",
+        _      => return None,
+    };
+
+    Some(name)
+}
+
+fn descriptor_str(uuid: u16) -> Option<&'static str> {
+    let name = match uuid {
+        0x2900 => "Characteristic Extended Properties",
+        0x2901 => "Characteristic User Description",
+        0x2902 => "Client Characteristic Configuration",
+        0x2903 => "Server Characteristic Configuration",
+        0x2904 => "Characteristic Presentation Format",
+        0x2905 => "Characteristic Aggregate Format",
+        0x2906 => "Valid Range",
+        0x2907 => "External Report Reference",
+        0x2908 => "Report Reference",
+        0x2909 => "Number of Digitals",
+        0x290a => "Value Trigger Setting",
+        0x290b => "Environmental Sensing Configuration",
+        0x290c => "Environmental Sensing Measurement",
+        0x290d => "Environmental Sensing Trigger Setting",
+        0x290e => "Time Trigger Setting",
+        0x290f => "Complete BR-EDR Transport Block Data",
+        0x2910 => "Observation Schedule",
+        0x2911 => "Valid Range and Accuracy",
+        _      => return None,
+    };
+
+    Some(name)
+}
+
+fn characteristic_str(uuid: u16) -> Option<&'static str> {
+    let name = match uuid {
+        0x2a00 => "Device Name",
+        0x2a01 => "Appearance",
+        0x2a02 => "Peripheral Privacy Flag",
+        0x2a03 => "Reconnection Address",
+        0x2a04 => "Peripheral Preferred Connection Parameters",
+        0x2a05 => "Service Changed",
+        0x2a06 => "Alert Level",
+        0x2a07 => "Tx Power Level",
+        0x2a08 => "Date Time",
+        0x2a09 => "Day of Week",
+        0x2a0a => "Day Date Time",
+        0x2a0c => "Exact Time 256",
+        0x2a0d => "DST Offset",
+        0x2a0e => "Time Zone",
+        0x2a0f => "Local Time Information",
+        0x2a11 => "Time with DST",
+        0x2a12 => "Time Accuracy",
+        0x2a13 => "Time Source",
+        0x2a14 => "Reference Time Information",
+        0x2a16 => "Time Update Control Point",
+        0x2a17 => "Time Update State",
+        0x2a18 => "Glucose Measurement",
+        0x2a19 => "Battery Level",
+        0x2a1c => "Temperature Measurement",
+        0x2a1d => "Temperature Type",
+        0x2a1e => "Intermediate Temperature",
+        0x2a21 => "Measurement Interval",
+        0x2a22 => "Boot Keyboard Input Report",
+        0x2a23 => "System ID",
+        0x2a24 => "Model Number String",
+        0x2a25 => "Serial Number String",
+        0x2a26 => "Firmware Revision String",
+        0x2a27 => "Hardware Revision String",
+        0x2a28 => "Software Revision String",
+        0x2a29 => "Manufacturer Name String",
+        0x2a2a => "IEEE 11073-20601 Regulatory Certification Data List",
+        0x2a2b => "Current Time",
+        0x2a2c => "Magnetic Declination",
+        0x2a31 => "Scan Refresh",
+        0x2a32 => "Boot Keyboard Output Report",
+        0x2a33 => "Boot Mouse Input Report",
+        0x2a34 => "Glucose Measurement Context",
+        0x2a35 => "Blood Pressure Measurement",
+        0x2a36 => "Intermediate Cuff Pressure",
+        0x2a37 => "Heart Rate Measurement",
+        0x2a38 => "Body Sensor Location",
+        0x2a39 => "Heart Rate Control Point",
+        0x2a3f => "Alert Status",
+        0x2a40 => "Ringer Control Point",
+        0x2a41 => "Ringer Setting",
+        0x2a42 => "Alert Category ID Bit Mask",
+        0x2a43 => "Alert Category ID",
+        0x2a44 => "Alert Notification Control Point",
+        0x2a45 => "Unread Alert Status",
+        0x2a46 => "New Alert",
+        0x2a47 => "Supported New Alert Category",
+        0x2a48 => "Supported Unread Alert Category",
+        0x2a49 => "Blood Pressure Feature",
+        0x2a4a => "HID Information",
+        0x2a4b => "Report Map",
+        0x2a4c => "HID Control Point",
+        0x2a4d => "Report",
+        0x2a4e => "Protocol Mode",
+        0x2a4f => "Scan Interval Window",
+        0x2a50 => "PnP ID",
+        0x2a51 => "Glucose Feature",
+        0x2a52 => "Record Access Control Point",
+        0x2a53 => "RSC Measurement",
+        0x2a54 => "RSC Feature",
+        0x2a55 => "SC Control Point",
+        0x2a5b => "CSC Measurement",
+        0x2a5c => "CSC Feature",
+        0x2a5d => "Sensor Location",
+        0x2a5e => "PLX Spot-Check Measurement",
+        0x2a5f => "PLX Continuous Measurement",
+        0x2a60 => "PLX Features",
+        0x2a63 => "Cycling Power Measurement",
+        0x2a64 => "Cycling Power Vector",
+        0x2a65 => "Cycling Power Feature",
+        0x2a66 => "Cycling Power Control Point",
+        0x2a67 => "Location and Speed",
+        0x2a68 => "Navigation",
+        0x2a69 => "Position Quality",
+        0x2a6a => "LN Feature",
+        0x2a6b => "LN Control Point",
+        0x2a6c => "Elevation",
+        0x2a6d => "Pressure",
+        0x2a6e => "Temperature",
+        0x2a6f => "Humidity",
+        0x2a70 => "True Wind Speed",
+        0x2a71 => "True Wind Direction",
+        0x2a72 => "Apparent Wind Speed",
+        0x2a73 => "Apparent Wind Direction",
+        0x2a74 => "Gust Factor",
+        0x2a75 => "Pollen Concentration",
+        0x2a76 => "UV Index",
+        0x2a77 => "Irradiance",
+        0x2a78 => "Rainfall",
+        0x2a79 => "Wind Chill",
+        0x2a7a => "Heat Index",
+        0x2a7b => "Dew Point",
+        0x2a7d => "Descriptor Value Changed",
+        0x2a80 => "Age",
+        0x2a85 => "Date of Birth",
+        0x2a87 => "Email Address",
+        0x2a8a => "First Name",
+        0x2a8c => "Gender",
+        0x2a8d => "Heart Rate Max",
+        0x2a8e => "Height",
+        0x2a90 => "Last Name",
+        0x2a98 => "Weight",
+        0x2a99 => "Database Change Increment",
+        0x2a9a => "User Index",
+        0x2a9b => "Body Composition Feature",
+        0x2a9c => "Body Composition Measurement",
+        0x2a9d => "Weight Measurement",
+        0x2a9e => "Weight Scale Feature",
+        0x2a9f => "User Control Point",
+        0x2aa2 => "Language",
+        0x2aa3 => "Barometric Pressure Trend",
+        0x2aa4 => "Bond Management Control Point",
+        0x2aa5 => "Bond Management Feature",
+        0x2aa6 => "Central Address Resolution",
+        0x2aa7 => "CGM Measurement",
+        0x2aa8 => "CGM Feature",
+        0x2aa9 => "CGM Status",
+        0x2aaa => "CGM Session Start Time",
+        0x2aab => "CGM Session Run Time",
+        0x2aac => "CGM Specific Ops Control Point",
+        0x2aad => "Indoor Positioning Configuration",
+        0x2ab3 => "Altitude",
+        0x2ab4 => "Uncertainty",
+        0x2ab5 => "Location Name",
+        0x2ab6 => "URI",
+        0x2ab7 => "HTTP Headers",
+        0x2ab8 => "HTTP Status Code",
+        0x2ab9 => "HTTP Entity Body",
+        0x2aba => "HTTP Control Point",
+        0x2abb => "HTTPS Security",
+        0x2abc => "TDS Control Point",
+        0x2abd => "OTS Feature",
+        0x2abe => "Object Name",
+        0x2abf => "Object Type",
+        0x2ac0 => "Object Size",
+        0x2ac1 => "Object First-Created",
+        0x2ac2 => "Object Last-Modified",
+        0x2ac3 => "Object ID",
+        0x2ac4 => "Object Properties",
+        0x2ac5 => "Object Action Control Point",
+        0x2ac6 => "Object List Control Point",
+        0x2ac7 => "Object List Filter",
+        0x2ac8 => "Object Changed",
+        0x2ac9 => "Resolvable Private Address Only",
+        0x2acc => "Fitness Machine Feature",
+        0x2acd => "Treadmill Data",
+        0x2ad2 => "Indoor Bike Data",
+        0x2ad9 => "Fitness Machine Control Point",
+        0x2ada => "Fitness Machine Status",
+        0x2adb => "Mesh Provisioning Data In",
+        0x2adc => "Mesh Provisioning Data Out",
+        0x2add => "Mesh Proxy Data In",
+        0x2ade => "Mesh Proxy Data Out",
+        0x2b29 => "Client Supported Features",
+        0x2b2a => "Database Hash",
+        0x2b3a => "Server Supported Features",
+        0x2b7d => "Volume State",
+        0x2b7e => "Volume Control Point",
+        0x2b7f => "Volume Flags",
+        0x2b84 => "Set Identity Resolving Key",
+        0x2b85 => "Coordinated Set Size",
+        0x2b86 => "Set Member Lock",
+        0x2b87 => "Set Member Rank",
+        0x2bc4 => "Sink ASE",
+        0x2bc5 => "Source ASE",
+        0x2bc6 => "ASE Control Point",
+        0x2bc7 => "Broadcast Audio Scan Control Point",
+        0x2bc8 => "Broadcast Receive State",
+        0x2bc9 => "Sink PAC",
+        0x2bca => "Sink Audio Locations",
+        0x2bcb => "Source PAC",
+        0x2bcc => "Source Audio Locations",
+        0x2bcd => "Available Audio Contexts",
+        0x2bce => "Supported Audio Contexts",
+        _      => return None,
+    };
+
+    Some(name)
+}
+
+/// Name of a 16-bit service, unit, declaration, descriptor or characteristic
+/// UUID from the Bluetooth SIG assigned numbers
+pub(crate) fn uuid16_str(uuid: u16) -> Option<&'static str> {
+    match uuid {
+        0x1800..=0x18ff => service_str(uuid),
+        0x2700..=0x27ff => unit_str(uuid),
+        0x2800..=0x28ff => declaration_str(uuid),
+        0x2900..=0x29ff => descriptor_str(uuid),
+        0x2a00..=0x2bff => characteristic_str(uuid),
+        _               => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::uuid16_str;
+
+    #[test]
+    fn lookup() {
+        assert_eq!(uuid16_str(0x180f), Some("Battery"));
+        assert_eq!(uuid16_str(0x2a19), Some("Battery Level"));
+        assert_eq!(uuid16_str(0x2902), Some("Client Characteristic Configuration"));
+        assert_eq!(uuid16_str(0x2800), Some("Primary Service"));
+        assert_eq!(uuid16_str(0x18ff), None);
+        assert_eq!(uuid16_str(0xfe2c), None);
+    }
+}
@@ -0,0 +1,184 @@
+use nom::{
+    bytes::complete::take,
+    combinator::{all_consuming, cond, fail, rest},
+    multi::many0,
+    number::complete::{le_u8, le_u16},
+    sequence::tuple,
+};
+use crate::error::PResult;
+use crate::hci::{bit_names, hex, ConnInterval, Timeout};
+
+/// Decoded fields of a characteristic or descriptor value
+pub(crate) type Fields = Vec<(&'static str, String)>;
+
+/// Hex value of a bit field followed by the names of the bits that are set
+fn heart_rate_measurement(i: &[u8]) -> PResult<'_, Fields> {
+    let (i, flags) = le_u8(i)?;
+    let (i, bpm) = if flags & 0x01 != 0 { le_u16(i)? } else { le_u8(i).map(|(i, v)| (i, v as u16))? };
+    let (i, energy) = cond(flags & 0x08 != 0, le_u16)(i)?;
+    let (i, rr) = if flags & 0x10 != 0 { many0(le_u16)(i)? } else { (i, Vec::new()) };
+
+    let contact = match (flags >> 1) & 0b11 {
+        0b10 => "Not detected",
+        0b11 => "Detected",
+        _    => "Not supported",
+    };
+
+    let mut fields = vec![
+        ("Flags", format!("0x{:02x}", flags)),
+        ("Heart rate", format!("{} bpm", bpm)),
+        ("Sensor contact", contact.to_string()),
+    ];
+    if let Some(energy) = energy {
+        fields.push(("Energy expended", format!("{} kJ", energy)));
+    }
+    for rr in rr {
+        fields.push(("RR interval", format!("{:.1} msec ({})", rr as f32 * 1000.0 / 1024.0, rr)));
+    }
+
+    Ok((i, fields))
+}
+
+fn appearance_str(category: u16) -> &'static str {
+    match category {
+        0x00 => "Unknown",
+        0x01 => "Phone",
+        0x02 => "Computer",
+        0x03 => "Watch",
+        0x04 => "Clock",
+        0x05 => "Display",
+        0x06 => "Remote Control",
+        0x07 => "Eye-glasses",
+        0x08 => "Tag",
+        0x09 => "Keyring",
+        0x0a => "Media Player",
+        0x0b => "Barcode Scanner",
+        0x0c => "Thermometer",
+        0x0d => "Heart Rate Sensor",
+        0x0e => "Blood Pressure",
+        0x0f => "Human Interface Device",
+        0x10 => "Glucose Meter",
+        0x11 => "Running Walking Sensor",
+        0x12 => "Cycling",
+        0x13 => "Control Device",
+        0x14 => "Network Device",
+        0x15 => "Sensor",
+        0x16 => "Light Fixtures",
+        0x17 => "Fan",
+        0x18 => "HVAC",
+        0x19 => "Air Conditioning",
+        0x1a => "Humidifier",
+        0x1b => "Heating",
+        0x1c => "Access Control",
+        0x1d => "Motorized Device",
+        0x1e => "Power Device",
+        0x1f => "Light Source",
+        0x20 => "Window Covering",
+        0x21 => "Audio Sink",
+        0x22 => "Audio Source",
+        0x23 => "Motorized Vehicle",
+        0x24 => "Domestic Appliance",
+        0x25 => "Wearable Audio Device",
+        0x26 => "Aircraft",
+        0x27 => "AV Equipment",
+        0x28 => "Display Equipment",
+        0x29 => "Hearing aid",
+        0x2a => "Gaming",
+        0x2b => "Signage",
+        0x31 => "Pulse Oximeter",
+        0x32 => "Weight Scale",
+        0x33 => "Personal Mobility Device",
+        0x34 => "Continuous Glucose Monitor",
+        0x35 => "Insulin Pump",
+        0x36 => "Medication Delivery",
+        0x37 => "Spirometer",
+        0x51 => "Outdoor Sports Activity",
+        _    => "Reserved",
+    }
+}
+
+/// Appearance value as category, subcategory and the raw value
+pub(crate) fn appearance(val: u16) -> String {
+    format!("{}, subcategory {} (0x{:04x})", appearance_str(val >> 6), val & 0x3f, val)
+}
+
+fn value(uuid: u16, i: &[u8]) -> PResult<'_, Fields> {
+    match uuid {
+        // Device Name
+        0x2a00 => {
+            let (i, name) = rest(i)?;
+            Ok((i, vec![("Name", format!("{:?}", String::from_utf8_lossy(name)))]))
+        },
+        // Appearance
+        0x2a01 => {
+            let (i, val) = le_u16(i)?;
+            Ok((i, vec![("Appearance", appearance(val))]))
+        },
+        // Peripheral Preferred Connection Parameters
+        0x2a04 => {
+            let (i, (min, max, latency, timeout)) = tuple((le_u16, le_u16, le_u16, le_u16))(i)?;
+            Ok((i, vec![
+                ("Min connection interval", ConnInterval(min).to_string()),
+                ("Max connection interval", ConnInterval(max).to_string()),
+                ("Connection latency", latency.to_string()),
+                ("Supervision timeout", Timeout(timeout).to_string()),
+            ]))
+        },
+        // Service Changed
+        0x2a05 => {
+            let (i, (start, end)) = tuple((le_u16, le_u16))(i)?;
+            Ok((i, vec![("Handle range", format!("0x{:04x}-0x{:04x}", start, end))]))
+        },
+        // Battery Level
+        0x2a19 => {
+            let (i, level) = le_u8(i)?;
+            Ok((i, vec![("Battery level", format!("{}%", level))]))
+        },
+        0x2a37 => heart_rate_measurement(i),
+        // Client Supported Features
+        0x2b29 => {
+            const BITS: [&str; 3] = ["Robust Caching", "Enhanced ATT Bearer", "Multiple Handle Value Notifications"];
+            let (i, (first, more)) = tuple((le_u8, rest))(i)?;
+            let mut fields = vec![("Features", bit_names(first as u16, 2, &BITS))];
+            if !more.is_empty() {
+                fields.push(("Reserved", format!("{:02x?}", more)));
+            }
+            Ok((i, fields))
+        },
+        // Database Hash
+        0x2b2a => {
+            let (i, hash) = take(16usize)(i)?;
+            Ok((i, vec![("Database hash", hex(hash))]))
+        },
+        // Client Characteristic Configuration
+        0x2902 => {
+            let (i, val) = le_u16(i)?;
+            Ok((i, vec![("Configuration", bit_names(val, 4, &["Notification", "Indication"]))]))
+        },
+        _ => fail(i),
+    }
+}
+
+/// Decode the value of a characteristic or descriptor with a 16-bit UUID,
+/// if it's one we know and it's well-formed
+pub(crate) fn fields(uuid: u16, data: &[u8]) -> Option<Fields> {
+    all_consuming(|i| value(uuid, i))(data).ok().map(|(_, fields)| fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fields;
+
+    #[test]
+    fn heart_rate_measurement() {
+        let f = fields(0x2a37, &[0x16, 0x48, 0x00, 0x04]).unwrap();
+
+        assert_eq!(f, vec![
+            ("Flags", "0x16".to_string()),
+            ("Heart rate", "72 bpm".to_string()),
+            ("Sensor contact", "Detected".to_string()),
+            ("RR interval", "1000.0 msec (1024)".to_string()),
+        ]);
+        assert!(fields(0x2a19, &[0x64, 0x00]).is_none());
+    }
+}
@@ -0,0 +1,201 @@
+use nom::{bytes::{self, complete::{tag, take}}, sequence::tuple, number::{streaming, complete::be_u32}};
+use num_enum::{FromPrimitive, IntoPrimitive};
+use std::io::{self, Write};
+use time::{Time, Date, Duration, OffsetDateTime};
+use crate::monitor;
+use crate::error::{self, Kind, Layer, PResult};
+
+const MAGIC: &[u8] = b"btsnoop\0";
+const VERSION: u32 = 1;
+
+// Microseconds between 0000-01-01 AD and the Unix epoch
+const EPOCH_DELTA: i64 = 0x00e0_3ab4_4a67_6000;
+const DAY_US: i64 = 86_400_000_000;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, FromPrimitive, IntoPrimitive)]
+pub enum Datalink {
+    Hci     = 1001,
+    H4      = 1002,
+    Bcsp    = 1003,
+    H5      = 1004,
+    Monitor = 2001,
+
+    #[num_enum(catch_all)]
+    Unknown(u32),
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Header {
+    pub version: u32,
+    pub datalink: Datalink,
+}
+
+fn header(input: &[u8]) -> PResult<'_, Header> {
+    let (rem, _) = tag(MAGIC)(input)?;
+    let (input, (version, datalink)) = tuple((be_u32, be_u32))(rem)?;
+    if version != VERSION {
+        return error::fail(rem, Kind::Unsupported);
+    }
+
+    Ok((input, Header { version, datalink: Datalink::from(datalink) }))
+}
+
+pub fn parse_header(input: &[u8]) -> error::Result<(&[u8], Header)> {
+    error::finish(Layer::Btsnoop, input, header(input))
+}
+
+fn ts_to_time(ts: i64) -> Time {
+    let us = (ts - EPOCH_DELTA).rem_euclid(DAY_US);
+    Time::MIDNIGHT + Duration::microseconds(us)
+}
+
+// Map the flags of the plain HCI datalinks onto a monitor opcode
+fn hci_opcode(flags: u32, h4_type: Option<u8>) -> Option<u16> {
+    let received = flags & 0x01 != 0;
+
+    match h4_type {
+        Some(0x01) => Some(2),
+        Some(0x02) => Some(if received { 5 } else { 4 }),
+        Some(0x03) => Some(if received { 7 } else { 6 }),
+        Some(0x04) => Some(3),
+        Some(0x05) => Some(if received { 19 } else { 18 }),
+        Some(_) => None,
+        None => match (flags & 0x02 != 0, received) {
+            (true, true) => Some(3),
+            (true, false) => Some(2),
+            (false, true) => Some(5),
+            (false, false) => Some(4),
+        },
+    }
+}
+
+fn record(datalink: Datalink, input: &[u8]) -> PResult<'_, monitor::Packet<'_>> {
+    let (input, (_orig_len, incl_len, flags, _drops)) =
+        tuple((streaming::be_u32, streaming::be_u32, streaming::be_u32, streaming::be_u32))(input)?;
+    let (input, ts) = streaming::be_i64(input)?;
+    let (input, data) = bytes::streaming::take(incl_len)(input)?;
+    let ts = ts_to_time(ts);
+
+    let (index, opcode, data) = match datalink {
+        Datalink::Monitor => ((flags >> 16) as u16, (flags & 0xffff) as u16, data),
+        Datalink::H4 => {
+            let (rem, h4) = take(1usize)(data)?;
+            match hci_opcode(flags, Some(h4[0])) {
+                Some(opcode) => (0, opcode, rem),
+                None => return error::fail(data, Kind::UnknownOpcode(h4[0] as u16)),
+            }
+        },
+        Datalink::Hci => (0, hci_opcode(flags, None).unwrap_or_default(), data),
+        _ => return error::fail(data, Kind::Unsupported),
+    };
+
+    let (_, pkt) = monitor::parse_monitor(ts, index, opcode, data)?;
+
+    Ok((input, pkt))
+}
+
+pub fn parse_record(datalink: Datalink, input: &[u8]) -> error::Result<(&[u8], monitor::Packet<'_>)> {
+    error::finish(Layer::Btsnoop, input, record(datalink, input))
+}
+
+pub struct Writer<W: Write> {
+    out: W,
+    base: i64,
+}
+
+impl<W: Write> Writer<W> {
+    /// Create a monitor (datalink 2001) BTSnoop writer. Packet timestamps are
+    /// only a time of day, so they get anchored to the current UTC date.
+    pub fn new(out: W) -> io::Result<Self> {
+        Self::with_date(out, OffsetDateTime::now_utc().date())
+    }
+
+    pub fn with_date(mut out: W, date: Date) -> io::Result<Self> {
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_be_bytes())?;
+        out.write_all(&u32::from(Datalink::Monitor).to_be_bytes())?;
+
+        let midnight = date.midnight().assume_utc().unix_timestamp() * 1_000_000;
+
+        Ok(Writer { out, base: midnight + EPOCH_DELTA })
+    }
+
+    pub fn write_packet(&mut self, pkt: &monitor::Packet) -> io::Result<()> {
+        let len = pkt.data.len() as u32;
+        let flags = ((pkt.index as u32) << 16) | pkt.op.opcode() as u32;
+        let ts = self.base + (pkt.ts - Time::MIDNIGHT).whole_microseconds() as i64;
+
+        self.out.write_all(&len.to_be_bytes())?;
+        self.out.write_all(&len.to_be_bytes())?;
+        self.out.write_all(&flags.to_be_bytes())?;
+        self.out.write_all(&0u32.to_be_bytes())?;
+        self.out.write_all(&ts.to_be_bytes())?;
+        self.out.write_all(&pkt.data)?;
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_header, parse_record, Writer, Datalink};
+    use crate::{tty, error::{Error, Layer}};
+    use time::{Date, Month};
+
+    #[test]
+    fn write_read_roundtrip() {
+        let mut data = &include_bytes!("xg24_peripheral_hr.btsnoop")[..];
+        let mut writer = Writer::with_date(Vec::new(), Date::from_calendar_date(2024, Month::January, 1).unwrap()).unwrap();
+        let mut written = Vec::new();
+
+        while let Ok((rem, pkt)) = tty::parse_data(data) {
+            writer.write_packet(&pkt).unwrap();
+            written.push(format!("{} {}", pkt.ts, pkt.op));
+            data = rem;
+        }
+
+        let file = writer.into_inner();
+        let (mut rec, hdr) = parse_header(&file).unwrap();
+        assert_eq!(hdr.datalink, Datalink::Monitor);
+
+        let mut read = Vec::new();
+        while !rec.is_empty() {
+            let (rem, pkt) = parse_record(hdr.datalink, rec).unwrap();
+            read.push(format!("{} {}", pkt.ts, pkt.op));
+            rec = rem;
+        }
+
+        assert!(!read.is_empty());
+        assert_eq!(written, read);
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut file = Writer::with_date(Vec::new(), Date::from_calendar_date(2024, Month::January, 1).unwrap())
+            .unwrap()
+            .into_inner();
+        assert_eq!(parse_header(&file).unwrap().1.version, 1);
+
+        file[11] = 2;
+        assert_eq!(parse_header(&file), Err(Error::Unsupported { layer: Layer::Btsnoop, offset: 8 }));
+    }
+
+    #[test]
+    fn h4_record() {
+        let rec = [
+            0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x04,
+            0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0xe0, 0x3a, 0xb4, 0x4a, 0x67, 0x60, 0x00,
+            0x01, 0x03, 0x0c, 0x00,
+        ];
+        let (rem, pkt) = parse_record(Datalink::H4, &rec).unwrap();
+
+        assert!(rem.is_empty());
+        assert_eq!(pkt.op.opcode(), 2);
+        assert_eq!(pkt.ts, time::Time::MIDNIGHT);
+    }
+}
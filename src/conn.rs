@@ -0,0 +1,340 @@
+use nom::{multi::length_count, number::complete::{le_u8, le_u16}, sequence::tuple};
+use std::{fmt, collections::HashMap};
+use time::Time;
+use crate::hci::{self, Command, Event, LeEvent, Op, StatusCode, ConnInterval, Timeout, Usec};
+use crate::monitor::{BdAddr, Direction};
+use crate::error::PResult;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Transport {
+    BrEdr,
+    Le,
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transport::BrEdr => write!(f, "BR/EDR"),
+            Transport::Le    => write!(f, "LE"),
+        }
+    }
+}
+
+/// LE connection parameters, in controller units
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConnParams {
+    pub interval: u16,
+    pub latency: u16,
+    pub timeout: u16,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DataLength {
+    pub max_tx_octets: u16,
+    pub max_tx_time: u16,
+    pub max_rx_octets: u16,
+    pub max_rx_time: u16,
+}
+
+/// Packet and byte counters for one direction
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Counters {
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Connection {
+    pub index: u16,
+    pub handle: u16,
+    pub transport: Transport,
+    pub peer: BdAddr,
+    /// Only known for LE connections
+    pub peer_addr_type: Option<u8>,
+    pub role: Option<u8>,
+    pub params: Option<ConnParams>,
+    /// TX and RX PHY of an LE connection
+    pub phy: Option<(u8, u8)>,
+    pub data_length: Option<DataLength>,
+    pub encrypted: bool,
+    pub connected: Time,
+    /// Time and reason of the disconnection
+    pub disconnected: Option<(Time, u8)>,
+    pub tx: Counters,
+    pub rx: Counters,
+}
+
+impl Connection {
+    fn new(ts: Time, index: u16, handle: u16, transport: Transport, peer: BdAddr) -> Self {
+        Connection {
+            index, handle, transport, peer,
+            peer_addr_type: None,
+            role: None,
+            params: None,
+            phy: None,
+            data_length: None,
+            encrypted: false,
+            connected: ts,
+            disconnected: None,
+            tx: Counters::default(),
+            rx: Counters::default(),
+        }
+    }
+}
+
+impl fmt::Display for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} connection handle 0x{:04x} (index {}): {}", self.transport, self.handle, self.index, self.peer)?;
+        if let Some(t) = self.peer_addr_type {
+            hci::field(f, "Peer address type", format_args!("{} (0x{:02x})", hci::addr_type_str(t), t))?;
+        }
+        if let Some(role) = self.role {
+            hci::field(f, "Role", format_args!("{} (0x{:02x})", hci::role_str(role), role))?;
+        }
+        if let Some(p) = self.params {
+            hci::field(f, "Connection interval", ConnInterval(p.interval))?;
+            hci::field(f, "Connection latency", p.latency)?;
+            hci::field(f, "Supervision timeout", Timeout(p.timeout))?;
+        }
+        if let Some((tx, rx)) = self.phy {
+            hci::field(f, "PHY", format_args!("TX {} RX {}", hci::phy_str(tx), hci::phy_str(rx)))?;
+        }
+        if let Some(d) = self.data_length {
+            hci::field(f, "Data length", format_args!("TX {} octets {}, RX {} octets {}",
+                d.max_tx_octets, Usec(d.max_tx_time as u32), d.max_rx_octets, Usec(d.max_rx_time as u32)))?;
+        }
+        hci::field(f, "Encryption", if self.encrypted { "Enabled" } else { "Disabled" })?;
+        hci::field(f, "Connected", self.connected)?;
+        if let Some((ts, reason)) = self.disconnected {
+            hci::field(f, "Disconnected", format_args!("{} reason {}", ts, StatusCode(reason)))?;
+        }
+        hci::field(f, "TX", format_args!("{} packets, {} bytes", self.tx.packets, self.tx.bytes))?;
+        hci::field(f, "RX", format_args!("{} packets, {} bytes", self.rx.packets, self.rx.bytes))
+    }
+}
+
+fn conn_complete(i: &[u8]) -> PResult<'_, (u8, u16, BdAddr, u8, u8)> {
+    tuple((le_u8, le_u16, BdAddr::parse, le_u8, le_u8))(i)
+}
+
+/// Status, handle and one more octet, e.g. an enable flag
+fn status_handle_u8(i: &[u8]) -> PResult<'_, (u8, u16, u8)> {
+    tuple((le_u8, le_u16, le_u8))(i)
+}
+
+/// CIS and ACL handle pairs of LE Create CIS
+fn create_cis(i: &[u8]) -> PResult<'_, Vec<(u16, u16)>> {
+    length_count(le_u8, tuple((le_u16, le_u16)))(i)
+}
+
+fn role_change(i: &[u8]) -> PResult<'_, (u8, BdAddr, u8)> {
+    tuple((le_u8, BdAddr::parse, le_u8))(i)
+}
+
+/// Table of the connections seen in a capture, fed from HCI events.
+#[derive(Debug, Default)]
+pub struct Connections {
+    open: HashMap<(u16, u16), Connection>,
+    closed: Vec<Connection>,
+    /// CIS handle to the handle of the ACL connection it belongs to
+    cis: HashMap<(u16, u16), u16>,
+}
+
+impl Connections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, index: u16, handle: u16) -> Option<&Connection> {
+        self.open.get(&(index, handle))
+    }
+
+    /// Peer of an ACL connection or of the ACL connection a CIS belongs to
+    pub fn peer(&self, index: u16, handle: u16) -> Option<BdAddr> {
+        let handle = self.cis.get(&(index, handle)).copied().unwrap_or(handle);
+        self.get(index, handle).map(|c| c.peer)
+    }
+
+    /// Account one data packet to its connection
+    pub fn data(&mut self, index: u16, handle: u16, dir: Direction, len: usize) {
+        if let Some(conn) = self.open.get_mut(&(index, handle)) {
+            let c = match dir {
+                Direction::Tx => &mut conn.tx,
+                Direction::Rx => &mut conn.rx,
+            };
+            c.packets += 1;
+            c.bytes += len as u64;
+        }
+    }
+
+    /// All connections in the order they were established
+    pub fn all(&self) -> Vec<&Connection> {
+        let mut all: Vec<_> = self.closed.iter().chain(self.open.values()).collect();
+        all.sort_by_key(|c| (c.connected, c.index, c.handle));
+        all
+    }
+
+    /// Update the table from an HCI command. Only LE Create CIS matters, to
+    /// know the ACL connection of the CISes a central establishes.
+    pub fn command(&mut self, index: u16, cmd: &Command) {
+        if cmd.op() == Op::LeCreateCis {
+            if let Ok((_, pairs)) = create_cis(cmd.param()) {
+                for (cis_handle, acl_handle) in pairs {
+                    self.cis.insert((index, cis_handle), acl_handle);
+                }
+            }
+        }
+    }
+
+    /// Update the table from an HCI event. Returns the connection that was
+    /// closed by it, if any.
+    pub fn event(&mut self, ts: Time, index: u16, ev: &Event) -> Option<&Connection> {
+        let param = ev.param();
+
+        match ev.code() {
+            // Connection Complete
+            0x03 => {
+                if let Ok((_, (0, handle, peer, 0x01, encrypted))) =
+                    conn_complete(param) {
+                    let mut conn = Connection::new(ts, index, handle, Transport::BrEdr, peer);
+                    conn.encrypted = encrypted != 0;
+                    self.open.insert((index, handle), conn);
+                }
+            },
+            // Disconnect Complete
+            0x05 => {
+                if let Some((0, handle, reason)) = ev.disconnect_complete() {
+                    self.cis.retain(|(i, cis), acl| *i != index || (*cis != handle && *acl != handle));
+                    if let Some(mut conn) = self.open.remove(&(index, handle)) {
+                        conn.disconnected = Some((ts, reason));
+                        self.closed.push(conn);
+                        return self.closed.last();
+                    }
+                }
+            },
+            // Encryption Change v1 and v2
+            0x08 | 0x59 => {
+                if let Ok((_, (0, handle, enabled))) = status_handle_u8(param) {
+                    if let Some(conn) = self.open.get_mut(&(index, handle)) {
+                        conn.encrypted = enabled != 0;
+                    }
+                }
+            },
+            // Role Change
+            0x12 => {
+                if let Ok((_, (0, peer, role))) = role_change(param) {
+                    self.open.values_mut()
+                        .filter(|c| c.index == index && c.transport == Transport::BrEdr && c.peer == peer)
+                        .for_each(|c| c.role = Some(role));
+                }
+            },
+            0x3e => {
+                if let Some(le) = ev.le_event() {
+                    self.le_event(ts, index, le);
+                }
+            },
+            _ => (),
+        }
+
+        None
+    }
+
+    fn le_event(&mut self, ts: Time, index: u16, ev: LeEvent) {
+        match ev {
+            LeEvent::ConnComplete { status: 0, handle, role, peer_addr_type, peer_addr, interval, latency, timeout, .. } |
+            LeEvent::EnhConnComplete { status: 0, handle, role, peer_addr_type, peer_addr, interval, latency, timeout, .. } => {
+                let mut conn = Connection::new(ts, index, handle, Transport::Le, peer_addr);
+                conn.peer_addr_type = Some(peer_addr_type);
+                conn.role = Some(role);
+                conn.params = Some(ConnParams { interval, latency, timeout });
+                conn.phy = Some((0x01, 0x01));
+                self.open.insert((index, handle), conn);
+            },
+            LeEvent::ConnUpdateComplete { status: 0, handle, interval, latency, timeout } => {
+                if let Some(conn) = self.open.get_mut(&(index, handle)) {
+                    conn.params = Some(ConnParams { interval, latency, timeout });
+                }
+            },
+            LeEvent::PhyUpdateComplete { status: 0, handle, tx_phy, rx_phy } => {
+                if let Some(conn) = self.open.get_mut(&(index, handle)) {
+                    conn.phy = Some((tx_phy, rx_phy));
+                }
+            },
+            LeEvent::DataLengthChange { handle, max_tx_octets, max_tx_time, max_rx_octets, max_rx_time } => {
+                if let Some(conn) = self.open.get_mut(&(index, handle)) {
+                    conn.data_length = Some(DataLength { max_tx_octets, max_tx_time, max_rx_octets, max_rx_time });
+                }
+            },
+            LeEvent::CisRequest { acl_handle, cis_handle, .. } => {
+                self.cis.insert((index, cis_handle), acl_handle);
+            },
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Connections, Transport};
+    use crate::hci::{Command, Event};
+    use crate::monitor::Direction;
+    use time::Time;
+
+    #[test]
+    fn le_connection_lifecycle() {
+        let mut conns = Connections::new();
+        let ts = Time::MIDNIGHT;
+
+        let (_, ev) = Event::parse(&[
+            0x3e, 0x13, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11,
+            0x18, 0x00, 0x00, 0x00, 0xc8, 0x00, 0x00,
+        ]).unwrap();
+        assert!(conns.event(ts, 0, &ev).is_none());
+
+        let (_, ev) = Event::parse(&[0x3e, 0x06, 0x0c, 0x00, 0x01, 0x00, 0x02, 0x02]).unwrap();
+        conns.event(ts, 0, &ev);
+        conns.data(0, 0x0001, Direction::Rx, 9);
+
+        let conn = conns.get(0, 0x0001).unwrap();
+        assert_eq!(conn.transport, Transport::Le);
+        assert_eq!(conn.peer.to_string(), "11:22:33:44:55:66");
+        assert_eq!(conn.phy, Some((0x02, 0x02)));
+
+        let (_, ev) = Event::parse(&[0x05, 0x04, 0x00, 0x01, 0x00, 0x13]).unwrap();
+        let conn = conns.event(ts, 0, &ev).unwrap();
+        assert_eq!(conn.disconnected, Some((ts, 0x13)));
+        assert_eq!(conn.rx.bytes, 9);
+        assert!(conns.get(0, 0x0001).is_none());
+        assert_eq!(conns.all().len(), 1);
+    }
+
+    #[test]
+    fn cis_peer_from_create_cis() {
+        let mut conns = Connections::new();
+        let ts = Time::MIDNIGHT;
+
+        let (_, ev) = Event::parse(&[
+            0x3e, 0x13, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11,
+            0x18, 0x00, 0x00, 0x00, 0xc8, 0x00, 0x00,
+        ]).unwrap();
+        conns.event(ts, 0, &ev);
+
+        let (_, cmd) = Command::parse(&[0x64, 0x20, 0x05, 0x01, 0x60, 0x00, 0x01, 0x00]).unwrap();
+        assert_eq!(cmd.to_string(), "LE Create Connected Isochronous Stream\
+            \n        Num CIS: 1\
+            \n        CIS handle: 0x0060\
+            \n        ACL handle: 0x0001");
+        conns.command(0, &cmd);
+        assert_eq!(conns.peer(0, 0x0060).unwrap().to_string(), "11:22:33:44:55:66");
+        assert!(conns.peer(1, 0x0060).is_none());
+
+        let (_, ev) = Event::parse(&[0x05, 0x04, 0x00, 0x60, 0x00, 0x13]).unwrap();
+        conns.event(ts, 0, &ev);
+        assert!(conns.peer(0, 0x0060).is_none());
+    }
+}
@@ -0,0 +1,277 @@
+use std::{io, fmt::Write};
+use time::Time;
+use crate::monitor::{AclPkt, Direction, Op, Packet};
+use crate::l2cap::{self, sig, Channels, Psm, Reassembler, Sdu, PSM_EATT, PSM_IPSP, PSM_OTS};
+use crate::conn::Connections;
+use crate::att::{self, Gatt};
+use crate::iso::{self, IsoPkt, Streams};
+use crate::sco::{self, ScoPkt, SyncConns};
+use crate::export::Export;
+
+const SIG_CID: u16 = 0x0001;
+const ATT_CID: u16 = 0x0004;
+const LE_SIG_CID: u16 = 0x0005;
+
+/// 6LoWPAN dispatch type at the start of an IPSP SDU
+fn lowpan_str(dispatch: u8) -> &'static str {
+    match dispatch {
+        0x41        => "IPv6",
+        0x60..=0x7f => "IPHC",
+        0x80..=0xbf => "Mesh",
+        0xc0..=0xc7 => "FRAG1",
+        0xe0..=0xe7 => "FRAGN",
+        _           => "Unknown",
+    }
+}
+
+/// Note a failure to export payloads on the line of the packet
+fn export_result(res: io::Result<()>, s: &mut String) {
+    if let Err(e) = res {
+        let _ = write!(s, " <export failed: {}>", e);
+    }
+}
+
+/// Stateful decoder turning a sequence of packets into text, carrying the
+/// state needed across packets such as partially reassembled L2CAP frames.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    acl: Reassembler,
+    conns: Connections,
+    chans: Channels,
+    gatt: Gatt,
+    streams: Streams,
+    sync: SyncConns,
+    export: Option<Export>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connections(&self) -> &Connections {
+        &self.conns
+    }
+
+    pub fn channels(&self) -> &Channels {
+        &self.chans
+    }
+
+    pub fn gatt(&self) -> &Gatt {
+        &self.gatt
+    }
+
+    pub fn streams(&self) -> &Streams {
+        &self.streams
+    }
+
+    pub fn sync_connections(&self) -> &SyncConns {
+        &self.sync
+    }
+
+    /// Export SCO and ISO payloads while decoding
+    pub fn set_export(&mut self, export: Export) {
+        self.export = Some(export);
+    }
+
+    /// Complete the files of the export, if any
+    pub fn finish(&mut self) -> io::Result<()> {
+        match &mut self.export {
+            Some(export) => export.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Decode one packet into a line of text, without the timestamp
+    pub fn decode(&mut self, pkt: &Packet) -> String {
+        match &pkt.op {
+            Op::EventPkt(e) => {
+                self.streams.event(pkt.index, e);
+                self.sync.event(pkt.ts, pkt.index, e);
+                if let Some(conn) = self.conns.event(pkt.ts, pkt.index, e) {
+                    self.acl.reset(conn.index, conn.handle);
+                    self.chans.close(conn.index, conn.handle);
+                    self.gatt.close(conn.index, conn.handle);
+                }
+                let mut s = pkt.op.to_string();
+                if let Some(export) = &mut self.export {
+                    let (sync, streams) = (&self.sync, &self.streams);
+                    let res = export.retain(|index, handle| {
+                        sync.get(index, handle).is_some() || streams.get(index, handle).is_some()
+                    });
+                    export_result(res, &mut s);
+                }
+                s
+            },
+            Op::CommandPkt(c) => {
+                self.conns.command(pkt.index, c);
+                self.streams.command(pkt.index, c);
+                pkt.op.to_string()
+            },
+            Op::AclTxPkt(acl) => self.acl(pkt.index, Direction::Tx, acl),
+            Op::AclRxPkt(acl) => self.acl(pkt.index, Direction::Rx, acl),
+            Op::ScoTxPkt(data) => self.sco(pkt.ts, pkt.index, Direction::Tx, data),
+            Op::ScoRxPkt(data) => self.sco(pkt.ts, pkt.index, Direction::Rx, data),
+            Op::IsoTxPkt(data) => self.iso(pkt.index, Direction::Tx, data),
+            Op::IsoRxPkt(data) => self.iso(pkt.index, Direction::Rx, data),
+            op => op.to_string(),
+        }
+    }
+
+    /// Connection handle, followed by the peer address when it's known
+    fn handle(&self, index: u16, handle: u16) -> String {
+        match self.conns.peer(index, handle) {
+            Some(peer) => format!("handle 0x{:04x} ({})", handle, peer),
+            None => format!("handle 0x{:04x}", handle),
+        }
+    }
+
+    fn acl(&mut self, index: u16, dir: Direction, acl: &AclPkt) -> String {
+        let mut s = format!("ACL {}:       {} pb {:02b} bc {:02b}: ",
+                            dir, self.handle(index, acl.handle()), acl.pb(), acl.bc());
+        self.conns.data(index, acl.handle(), dir, acl.data().len());
+        let res = self.acl.push(index, dir, acl);
+
+        if let Some(err) = res.error {
+            let _ = write!(s, "<{}> ", err);
+        }
+
+        match res.frame {
+            Some(frame) => match l2cap::Frame::parse(&frame) {
+                Ok((_, frame)) => {
+                    if let SIG_CID | LE_SIG_CID = frame.cid() {
+                        let mut i = frame.data();
+                        while let Ok((rem, cmd)) = sig::Command::parse(i) {
+                            self.chans.signal(index, acl.handle(), dir, &cmd);
+                            i = rem;
+                        }
+                    }
+                    match self.channel(index, acl.handle(), dir, &frame) {
+                        Some(line) => s.push_str(&line),
+                        None => { let _ = write!(s, "{}", frame); },
+                    }
+                    if frame.cid() == ATT_CID {
+                        self.attribute(index, acl.handle(), ATT_CID, dir, frame.data(), &mut s);
+                    }
+                },
+                Err(_) => { let _ = write!(s, "{:02x?}", frame); },
+            },
+            None => match self.acl.pending(index, acl.handle(), dir) {
+                Some((len, Some(missing))) => {
+                    let _ = write!(s, "L2CAP fragment, {} bytes buffered, {} missing", len, missing);
+                },
+                Some((len, None)) => {
+                    let _ = write!(s, "L2CAP fragment, {} bytes buffered", len);
+                },
+                None => { let _ = write!(s, "{:02x?}", acl.data()); },
+            },
+        }
+
+        s
+    }
+
+    /// Track GATT discovery on an ATT PDU from the given bearer and describe
+    /// the attributes it refers to
+    fn attribute(&mut self, index: u16, handle: u16, bearer: u16, dir: Direction, data: &[u8], s: &mut String) {
+        let Ok((_, pdu)) = att::Pdu::parse(data) else { return };
+        let peer = self.conns.peer(index, handle);
+
+        for desc in self.gatt.pdu(index, handle, bearer, dir, peer, &pdu) {
+            let _ = write!(s, "\n        Attribute: {}", desc);
+        }
+    }
+
+    /// Follow credits and SDUs of a frame on a credit based channel, and
+    /// decode the SDU according to the channel's PSM
+    fn channel(&mut self, index: u16, handle: u16, dir: Direction, frame: &l2cap::Frame) -> Option<String> {
+        let (chan, sdu) = self.chans.frame(index, handle, dir, frame.cid(), frame.data())?;
+        let credits = match dir {
+            Direction::Tx => chan.remote.credits,
+            Direction::Rx => chan.local.credits,
+        };
+        let (psm, bearer) = (chan.psm, chan.local.cid);
+        let mut s = format!("Channel 0x{:04x} PSM {} credits {}: ", frame.cid(), Psm(psm), credits);
+
+        let _ = match sdu {
+            Sdu::Complete(data) => match (psm, data.first()) {
+                (PSM_EATT, _) => match att::Pdu::parse(&data) {
+                    Ok((_, pdu)) => {
+                        let _ = write!(s, "ATT: {}", pdu);
+                        self.attribute(index, handle, bearer, dir, &data, &mut s);
+                        Ok(())
+                    },
+                    Err(_) => write!(s, "{:02x?}", data),
+                },
+                (PSM_OTS, _) => write!(s, "Object data, {} bytes", data.len()),
+                (PSM_IPSP, Some(dispatch)) => {
+                    write!(s, "6LoWPAN {} (0x{:02x}), {} bytes", lowpan_str(*dispatch), dispatch, data.len())
+                },
+                _ => write!(s, "{:02x?}", data),
+            },
+            Sdu::Partial { len, total } => write!(s, "SDU fragment, {} of {} bytes", len, total),
+            Sdu::Truncated => write!(s, "<K-frame without SDU length> {:02x?}", frame.data()),
+            Sdu::Overflow { extra } => write!(s, "<SDU overflows by {} bytes>", extra),
+        };
+
+        Some(s)
+    }
+
+    /// Account a SCO data packet to its synchronous connection
+    fn sco(&mut self, ts: Time, index: u16, dir: Direction, data: &[u8]) -> String {
+        let pkt = match ScoPkt::parse(data) {
+            Ok((_, pkt)) => pkt,
+            Err(_) => return format!("SCO {}:       {:02x?}", dir, data),
+        };
+        let conn = self.sync.packet(ts, index, dir, &pkt);
+        let (peer, air_mode) = (conn.peer, conn.params.map(|p| p.air_mode));
+        let mut s = match peer {
+            Some(peer) => format!("SCO {}:       handle 0x{:04x} ({})", dir, pkt.handle(), peer),
+            None => format!("SCO {}:       handle 0x{:04x}", dir, pkt.handle()),
+        };
+
+        let _ = write!(s, ": {} bytes", pkt.data().len());
+        if pkt.status() != 0 {
+            let _ = write!(s, " <{}>", sco::status_str(pkt.status()));
+        }
+        if let Some(export) = &mut self.export {
+            export_result(export.sco(index, pkt.handle(), peer, air_mode, dir, pkt.data()), &mut s);
+        }
+
+        s
+    }
+
+    /// Reassemble an ISO data packet and account its SDU to the stream
+    fn iso(&mut self, index: u16, dir: Direction, data: &[u8]) -> String {
+        let pkt = match IsoPkt::parse(data) {
+            Ok((_, pkt)) => pkt,
+            Err(_) => return format!("ISO {}:       {:02x?}", dir, data),
+        };
+        let mut s = format!("ISO {}:       {}", dir, self.handle(index, pkt.handle()));
+        if let Some(ts) = pkt.timestamp() {
+            let _ = write!(s, " ts {} us", ts);
+        }
+
+        let _ = match self.streams.packet(index, dir, &pkt) {
+            iso::Sdu::Complete { header, data, missing, .. } => {
+                let _ = write!(s, ": SDU seq {}, {} bytes", header.seq, data.len());
+                if let Some(export) = &mut self.export {
+                    let peer = self.conns.peer(index, pkt.handle());
+                    export_result(export.iso(index, pkt.handle(), peer, dir, &data), &mut s);
+                }
+                if header.status != 0 {
+                    let _ = write!(s, " <{}>", iso::status_str(header.status));
+                }
+                match missing {
+                    0 => Ok(()),
+                    1 => write!(s, " <1 SDU missing>"),
+                    n => write!(s, " <{} SDUs missing>", n),
+                }
+            },
+            iso::Sdu::Partial { len, total } => write!(s, ": SDU fragment, {} of {} bytes", len, total),
+            iso::Sdu::Orphaned => write!(s, ": <fragment without start> {:02x?}", pkt.data()),
+            iso::Sdu::Overflow { extra } => write!(s, ": <SDU overflows by {} bytes>", extra),
+        };
+
+        s
+    }
+}
@@ -0,0 +1,174 @@
+use std::{fmt, io};
+use nom::{IResult, error::{ErrorKind, ParseError}};
+
+/// Protocol layer at which a parse error was detected
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Layer {
+    Tty,
+    Btsnoop,
+    Monitor,
+    Hci,
+    L2cap,
+    Att,
+    Smp,
+    Iso,
+    Sco,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Layer::*;
+        match self {
+            Tty     => write!(f, "TTY"),
+            Btsnoop => write!(f, "BTSnoop"),
+            Monitor => write!(f, "Monitor"),
+            Hci     => write!(f, "HCI"),
+            L2cap   => write!(f, "L2CAP"),
+            Att     => write!(f, "ATT"),
+            Smp     => write!(f, "SMP"),
+            Iso     => write!(f, "ISO"),
+            Sco     => write!(f, "SCO"),
+        }
+    }
+}
+
+/// Errors returned by the public parsing API. The offset is the byte
+/// position, relative to the start of the input that was handed to the
+/// failing public function, at which the problem was detected.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    Truncated { layer: Layer, offset: usize },
+    BadLength { layer: Layer, offset: usize },
+    BadMagic { layer: Layer, offset: usize },
+    InvalidUtf8 { layer: Layer, offset: usize },
+    UnknownOpcode { layer: Layer, offset: usize, opcode: u16 },
+    Unsupported { layer: Layer, offset: usize },
+    Malformed { layer: Layer, offset: usize },
+    Io { layer: Layer, offset: usize, kind: io::ErrorKind },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn layer(&self) -> Layer {
+        use Error::*;
+        match self {
+            Truncated { layer, .. } | BadLength { layer, .. } | BadMagic { layer, .. } |
+            InvalidUtf8 { layer, .. } | UnknownOpcode { layer, .. } | Unsupported { layer, .. } |
+            Malformed { layer, .. } | Io { layer, .. } => *layer,
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        use Error::*;
+        match self {
+            Truncated { offset, .. } | BadLength { offset, .. } | BadMagic { offset, .. } |
+            InvalidUtf8 { offset, .. } | UnknownOpcode { offset, .. } | Unsupported { offset, .. } |
+            Malformed { offset, .. } | Io { offset, .. } => *offset,
+        }
+    }
+
+    /// Shift the offset by `base`, for errors found inside a larger stream
+    pub(crate) fn at(mut self, base: usize) -> Error {
+        use Error::*;
+        match &mut self {
+            Truncated { offset, .. } | BadLength { offset, .. } | BadMagic { offset, .. } |
+            InvalidUtf8 { offset, .. } | UnknownOpcode { offset, .. } | Unsupported { offset, .. } |
+            Malformed { offset, .. } | Io { offset, .. } => *offset += base,
+        }
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Error::*;
+        match self {
+            Truncated { .. }     => write!(f, "Truncated frame")?,
+            BadLength { .. }     => write!(f, "Bad length")?,
+            BadMagic { .. }      => write!(f, "Bad magic")?,
+            InvalidUtf8 { .. }   => write!(f, "Invalid UTF-8")?,
+            UnknownOpcode { opcode, .. } => write!(f, "Unknown opcode 0x{:04x}", opcode)?,
+            Unsupported { .. }   => write!(f, "Unsupported format")?,
+            Malformed { .. }     => write!(f, "Malformed data")?,
+            Io { kind, .. }      => write!(f, "I/O error: {}", kind)?,
+        }
+
+        write!(f, " ({} at offset {})", self.layer(), self.offset())
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Kind {
+    Nom(ErrorKind),
+    BadLength,
+    InvalidUtf8,
+    UnknownOpcode(u16),
+    Unsupported,
+}
+
+/// Error type threaded through the internal nom parsers. It remembers where
+/// in the input the problem happened so that the public API can turn it into
+/// an `Error` with an offset.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct NomError<'a> {
+    input: &'a [u8],
+    kind: Kind,
+    layer: Option<Layer>,
+}
+
+pub(crate) type PResult<'a, T> = IResult<&'a [u8], T, NomError<'a>>;
+
+impl<'a> ParseError<&'a [u8]> for NomError<'a> {
+    fn from_error_kind(input: &'a [u8], kind: ErrorKind) -> Self {
+        NomError { input, kind: Kind::Nom(kind), layer: None }
+    }
+
+    fn append(_: &'a [u8], _: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl NomError<'_> {
+    fn into_error(self, layer: Layer, base: &[u8]) -> Error {
+        let start = base.as_ptr() as usize;
+        let pos = self.input.as_ptr() as usize;
+        let offset = if pos >= start && pos <= start + base.len() { pos - start } else { 0 };
+        let layer = self.layer.unwrap_or(layer);
+
+        match self.kind {
+            Kind::Nom(ErrorKind::Eof) => Error::Truncated { layer, offset },
+            Kind::Nom(ErrorKind::Tag) => Error::BadMagic { layer, offset },
+            Kind::Nom(_) => Error::Malformed { layer, offset },
+            Kind::BadLength => Error::BadLength { layer, offset },
+            Kind::InvalidUtf8 => Error::InvalidUtf8 { layer, offset },
+            Kind::UnknownOpcode(opcode) => Error::UnknownOpcode { layer, offset, opcode },
+            Kind::Unsupported => Error::Unsupported { layer, offset },
+        }
+    }
+}
+
+pub(crate) fn fail<T>(input: &[u8], kind: Kind) -> PResult<'_, T> {
+    Err(nom::Err::Failure(NomError { input, kind, layer: None }))
+}
+
+/// Tag any error coming out of `res` with `layer`, unless a lower layer has
+/// already claimed it.
+pub(crate) fn in_layer<T>(layer: Layer, res: PResult<'_, T>) -> PResult<'_, T> {
+    res.map_err(|e| e.map(|mut e| {
+        e.layer.get_or_insert(layer);
+        e
+    }))
+}
+
+pub(crate) fn finish<'a, T>(layer: Layer, input: &'a [u8], res: PResult<'a, T>) -> Result<(&'a [u8], T)> {
+    match res {
+        Ok(v) => Ok(v),
+        Err(nom::Err::Incomplete(_)) => Err(Error::Truncated { layer, offset: input.len() }),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(e.into_error(layer, input)),
+    }
+}
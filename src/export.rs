@@ -0,0 +1,233 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+};
+use crate::monitor::{BdAddr, Direction};
+use crate::wav;
+
+/// Air mode of a transparent synchronous connection
+const AIR_MODE_TRANSPARENT: u8 = 0x03;
+
+/// Sample rate of the PCM data the controller exchanges over HCI for CVSD,
+/// u-law and A-law connections
+const SCO_PCM_RATE: u32 = 8000;
+
+enum Sink {
+    Raw(BufWriter<File>),
+    Wav(wav::Writer<BufWriter<File>>),
+}
+
+impl Sink {
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        match self {
+            Sink::Raw(w) => w.write_all(data),
+            Sink::Wav(w) => w.write_samples(data),
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Sink::Raw(mut w) => w.flush(),
+            Sink::Wav(w) => w.finish().map(drop),
+        }
+    }
+}
+
+/// Dumps the payloads of SCO and ISO connections into one file per handle
+/// and direction, named after the handle and the peer address.
+///
+/// SCO data is written as 16-bit mono PCM into WAV files, except for
+/// transparent connections whose frames, typically mSBC, are written as is
+/// unless a PCM sample rate is given. ISO SDUs, typically LC3 frames, are
+/// always written as is.
+pub struct Export {
+    dir: PathBuf,
+    pcm_rate: Option<u32>,
+    sinks: HashMap<(u16, u16, Direction), Sink>,
+    names: HashSet<String>,
+}
+
+impl std::fmt::Debug for Export {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Export")
+            .field("dir", &self.dir)
+            .field("pcm_rate", &self.pcm_rate)
+            .field("names", &self.names)
+            .finish()
+    }
+}
+
+impl Export {
+    /// Export into `dir`, creating it if needed. With a `pcm_rate`, data of
+    /// transparent SCO connections is taken as 16-bit mono PCM at that rate.
+    pub fn new(dir: PathBuf, pcm_rate: Option<u32>) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        Ok(Export { dir, pcm_rate, sinks: HashMap::new(), names: HashSet::new() })
+    }
+
+    /// Path of a file not used yet in this export, as handles get reused
+    fn path(&mut self, kind: &str, handle: u16, peer: Option<BdAddr>, dir: Direction, ext: &str) -> PathBuf {
+        let mut base = format!("{}-0x{:04x}", kind, handle);
+        if let Some(peer) = peer {
+            base.push('-');
+            base.push_str(&peer.to_string().replace(':', "-"));
+        }
+        base.push_str(match dir {
+            Direction::Tx => "-tx",
+            Direction::Rx => "-rx",
+        });
+
+        let mut name = format!("{}.{}", base, ext);
+        let mut n = 1;
+        while self.names.contains(&name) {
+            n += 1;
+            name = format!("{}-{}.{}", base, n, ext);
+        }
+        let path = self.dir.join(&name);
+        self.names.insert(name);
+
+        path
+    }
+
+    fn sink(&mut self, index: u16, handle: u16, dir: Direction,
+            open: impl FnOnce(&mut Self) -> io::Result<Sink>) -> io::Result<&mut Sink> {
+        let key = (index, handle, dir);
+        if !self.sinks.contains_key(&key) {
+            let sink = open(self)?;
+            self.sinks.insert(key, sink);
+        }
+
+        Ok(self.sinks.get_mut(&key).expect("sink was just inserted"))
+    }
+
+    /// Append the payload of a SCO data packet. The air mode is unknown for
+    /// connections established before the capture started.
+    pub fn sco(&mut self, index: u16, handle: u16, peer: Option<BdAddr>, air_mode: Option<u8>,
+               dir: Direction, data: &[u8]) -> io::Result<()> {
+        let rate = match (air_mode, self.pcm_rate) {
+            (Some(AIR_MODE_TRANSPARENT), rate) => rate,
+            (Some(_), _) => Some(SCO_PCM_RATE),
+            (None, _) => None,
+        };
+        let ext = match (air_mode, rate) {
+            (_, Some(_)) => "wav",
+            (Some(_), None) => "msbc",
+            (None, None) => "raw",
+        };
+
+        self.sink(index, handle, dir, |e| {
+            let file = BufWriter::new(File::create(e.path("sco", handle, peer, dir, ext))?);
+            match rate {
+                Some(rate) => Ok(Sink::Wav(wav::Writer::new(file, rate, 1, 16)?)),
+                None => Ok(Sink::Raw(file)),
+            }
+        })?.write(data)
+    }
+
+    /// Append a complete ISO SDU
+    pub fn iso(&mut self, index: u16, handle: u16, peer: Option<BdAddr>, dir: Direction,
+               sdu: &[u8]) -> io::Result<()> {
+        self.sink(index, handle, dir, |e| {
+            let file = File::create(e.path("iso", handle, peer, dir, "lc3"))?;
+            Ok(Sink::Raw(BufWriter::new(file)))
+        })?.write(sdu)
+    }
+
+    /// Finish the files of the handles for which `keep` is false, so that a
+    /// new connection reusing a handle gets a file of its own
+    pub fn retain(&mut self, keep: impl Fn(u16, u16) -> bool) -> io::Result<()> {
+        let done: Vec<_> = self.sinks.keys().filter(|(i, h, _)| !keep(*i, *h)).copied().collect();
+        done.into_iter().try_for_each(|key| match self.sinks.remove(&key) {
+            Some(sink) => sink.finish(),
+            None => Ok(()),
+        })
+    }
+
+    /// Finish all files
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.retain(|_, _| false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Export;
+    use crate::monitor::{BdAddr, Direction};
+    use std::{fs, path::{Path, PathBuf}};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("btmon-export-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn files(dir: &Path) -> Vec<(String, Vec<u8>)> {
+        let mut files: Vec<_> = fs::read_dir(dir).unwrap()
+            .map(|e| e.unwrap())
+            .map(|e| (e.file_name().into_string().unwrap(), fs::read(e.path()).unwrap()))
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn naming_and_formats() {
+        let dir = temp_dir("naming");
+        let peer = Some(BdAddr::from([0x66, 0x55, 0x44, 0x33, 0x22, 0x11]));
+        let mut e = Export::new(dir.clone(), None).unwrap();
+
+        // CVSD, transparent and unknown air modes
+        e.sco(0, 0x0006, peer, Some(0x02), Direction::Rx, &[0x01, 0x02]).unwrap();
+        e.sco(0, 0x0007, None, Some(0x03), Direction::Tx, &[0xad, 0x00]).unwrap();
+        e.sco(0, 0x0008, None, None, Direction::Tx, &[0x03]).unwrap();
+        e.iso(0, 0x0060, peer, Direction::Tx, &[0x04, 0x05]).unwrap();
+
+        // Only the connection that went away gets its file finished
+        e.retain(|_, handle| handle != 0x0006).unwrap();
+        let wav = fs::read(dir.join("sco-0x0006-11-22-33-44-55-66-rx.wav")).unwrap();
+        assert_eq!(&wav[40..], &[0x02, 0x00, 0x00, 0x00, 0x01, 0x02]);
+
+        // A new connection reusing the handle
+        e.sco(0, 0x0006, peer, Some(0x02), Direction::Rx, &[0x03, 0x04]).unwrap();
+        e.sco(0, 0x0006, peer, Some(0x02), Direction::Rx, &[0x05, 0x06]).unwrap();
+        e.finish().unwrap();
+
+        let files = files(&dir);
+        let names: Vec<_> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, [
+            "iso-0x0060-11-22-33-44-55-66-tx.lc3",
+            "sco-0x0006-11-22-33-44-55-66-rx-2.wav",
+            "sco-0x0006-11-22-33-44-55-66-rx.wav",
+            "sco-0x0007-tx.msbc",
+            "sco-0x0008-tx.raw",
+        ]);
+        assert_eq!(files[0].1, [0x04, 0x05]);
+        assert_eq!(&files[1].1[24..28], &8000u32.to_le_bytes());
+        assert_eq!(&files[1].1[40..], &[0x04, 0x00, 0x00, 0x00, 0x03, 0x04, 0x05, 0x06]);
+        assert_eq!(files[3].1, [0xad, 0x00]);
+        assert_eq!(files[4].1, [0x03]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pcm_rate() {
+        let dir = temp_dir("pcm-rate");
+        let mut e = Export::new(dir.clone(), Some(16000)).unwrap();
+
+        e.sco(1, 0x0007, None, Some(0x03), Direction::Tx, &[0x01, 0x02]).unwrap();
+        e.sco(1, 0x0008, None, None, Direction::Rx, &[0x03]).unwrap();
+        e.finish().unwrap();
+
+        let files = files(&dir);
+        assert_eq!(files[0].0, "sco-0x0007-tx.wav");
+        assert_eq!(&files[0].1[24..28], &16000u32.to_le_bytes());
+        assert_eq!(&files[0].1[44..], &[0x01, 0x02]);
+        assert_eq!(files[1], ("sco-0x0008-rx.raw".to_string(), vec![0x03]));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,11 +1,22 @@
 use nom::{IResult, multi::length_data, number::complete::{le_u16, le_u8}, sequence::tuple};
 use num_enum::FromPrimitive;
-use std::fmt;
+use std::{fmt, borrow::Cow};
+use crate::error::{self, Layer, PResult};
 
-#[derive(Debug, Eq, PartialEq)]
+mod ad;
+mod cmd;
+mod company;
+mod le_meta;
+mod param;
+mod ret;
+
+pub use le_meta::LeEvent;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Event<'a> {
     code: u8,
-    param: &'a [u8],
+    param: Cow<'a, [u8]>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -14,6 +25,139 @@ pub struct LeMeta <'a> {
     param: &'a[u8],
 }
 
+/// Write one decoded parameter on its own indented line
+pub(crate) fn field(f: &mut fmt::Formatter, name: &str, val: impl fmt::Display) -> fmt::Result {
+    write!(f, "\n        {}: {}", name, val)
+}
+
+/// Names of the bits set in a bit field, "Reserved" for bits without a name
+pub(crate) fn set_bits(val: u16, names: &[&'static str]) -> Vec<&'static str> {
+    (0..16)
+        .filter(|bit| val & (1 << bit) != 0)
+        .map(|bit| *names.get(bit).unwrap_or(&"Reserved"))
+        .collect()
+}
+
+/// Bit field as its value followed by the names of the bits set, if any
+pub(crate) fn bit_names(val: u16, width: usize, names: &[&'static str]) -> String {
+    let set = set_bits(val, names);
+
+    match set.is_empty() {
+        true  => format!("0x{:0w$x}", val, w = width),
+        false => format!("0x{:0w$x} ({})", val, set.join(", "), w = width),
+    }
+}
+
+/// Bytes as one continuous hex string, e.g. for keys and hashes
+pub(crate) fn hex(data: &[u8]) -> String {
+    use fmt::Write;
+
+    data.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{:02x}", b);
+        s
+    })
+}
+
+pub(crate) struct StatusCode(pub u8);
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (0x{:02x})", status_str(self.0), self.0)
+    }
+}
+
+/// Connection or ISO interval in units of 1.25 ms
+pub(crate) struct ConnInterval(pub u16);
+
+impl fmt::Display for ConnInterval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.2} msec (0x{:04x})", self.0 as f32 * 1.25, self.0)
+    }
+}
+
+/// Advertising or scanning interval/window in units of 0.625 ms
+pub(crate) struct AdvInterval(pub u32);
+
+impl fmt::Display for AdvInterval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.3} msec (0x{:04x})", self.0 as f32 * 0.625, self.0)
+    }
+}
+
+/// Times and latencies in microseconds, such as data lengths and ISO delays
+pub(crate) struct Usec(pub u32);
+
+impl fmt::Display for Usec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} us (0x{:04x})", self.0, self.0)
+    }
+}
+
+pub(crate) struct Phy(pub u8);
+
+impl fmt::Display for Phy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (0x{:02x})", phy_str(self.0), self.0)
+    }
+}
+
+pub(crate) struct Rssi(pub i8);
+
+impl fmt::Display for Rssi {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            127 => write!(f, "not available (0x7f)"),
+            v   => write!(f, "{} dBm (0x{:02x})", v, v as u8),
+        }
+    }
+}
+
+/// Supervision timeout in units of 10 ms
+pub(crate) struct Timeout(pub u16);
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} msec (0x{:04x})", self.0 as u32 * 10, self.0)
+    }
+}
+
+pub(crate) fn addr_type_str(addr_type: u8) -> &'static str {
+    match addr_type {
+        0x00 => "Public",
+        0x01 => "Random",
+        0x02 => "Public Identity",
+        0x03 => "Random Identity",
+        0xff => "Anonymous",
+        _    => "Unknown",
+    }
+}
+
+pub(crate) fn phy_str(phy: u8) -> &'static str {
+    match phy {
+        0x01 => "LE 1M",
+        0x02 => "LE 2M",
+        0x03 => "LE Coded",
+        0x04 => "LE Coded (S=2)",
+        _    => "Unknown",
+    }
+}
+
+pub(crate) fn role_str(role: u8) -> &'static str {
+    match role {
+        0x00 => "Central",
+        0x01 => "Peripheral",
+        _    => "Unknown",
+    }
+}
+
+pub(crate) fn link_type_str(link_type: u8) -> &'static str {
+    param::LINK_TYPES.get(link_type as usize).copied().unwrap_or("Reserved")
+}
+
+pub(crate) fn air_mode_str(air_mode: u8) -> &'static str {
+    param::AIR_MODES.get(air_mode as usize).copied().unwrap_or("Reserved")
+}
+
 fn status_str(status: u8) -> &'static str {
     match status {
         0x00 => "Success",
@@ -169,15 +313,128 @@ fn le_meta(param: &[u8]) -> IResult<&[u8], u8> {
     le_u8(param)
 }
 
+fn event_str(code: u8) -> Option<&'static str> {
+    let name = match code {
+        0x01 => "Inquiry Complete",
+        0x02 => "Inquiry Result",
+        0x03 => "Connection Complete",
+        0x04 => "Connection Request",
+        0x05 => "Disconnect Complete",
+        0x06 => "Authentication Complete",
+        0x07 => "Remote Name Request Complete",
+        0x08 => "Encryption Change",
+        0x09 => "Change Connection Link Key Complete",
+        0x0a => "Master Link Key Complete",
+        0x0b => "Read Remote Supported Features Complete",
+        0x0c => "Read Remote Version Information Complete",
+        0x0d => "QoS Setup Complete",
+        0x0e => "Command Complete",
+        0x0f => "Command Status",
+        0x10 => "Hardware Error",
+        0x11 => "Flush Occurred",
+        0x12 => "Role Change",
+        0x13 => "Number Of Completed Packets",
+        0x14 => "Mode Change",
+        0x15 => "Return Link Keys",
+        0x16 => "PIN Code Request",
+        0x17 => "Link Key Request",
+        0x18 => "Link Key Notification",
+        0x19 => "Loopback Command",
+        0x1a => "Data Buffer Overflow",
+        0x1b => "Max Slots Change",
+        0x1c => "Read Clock Offset Complete",
+        0x1d => "Connection Packet Type Changed",
+        0x1e => "QoS Violation",
+        0x1f => "Page Scan Mode Change",
+        0x20 => "Page Scan Repetition Mode Change",
+        0x21 => "Flow Specification Complete",
+        0x22 => "Inquiry Result with RSSI",
+        0x23 => "Read Remote Extended Features Complete",
+        0x2c => "Synchronous Connection Complete",
+        0x2d => "Synchronous Connection Changed",
+        0x2e => "Sniff Subrating",
+        0x2f => "Extended Inquiry Result",
+        0x30 => "Encryption Key Refresh Complete",
+        0x31 => "IO Capability Request",
+        0x32 => "IO Capability Response",
+        0x33 => "User Confirmation Request",
+        0x34 => "User Passkey Request",
+        0x35 => "Remote OOB Data Request",
+        0x36 => "Simple Pairing Complete",
+        0x38 => "Link Supervision Timeout Changed",
+        0x39 => "Enhanced Flush Complete",
+        0x3b => "User Passkey Notification",
+        0x3c => "Keypress Notification",
+        0x3d => "Remote Host Supported Features Notification",
+        0x3e => "LE Meta Event",
+        0x40 => "Physical Link Complete",
+        0x41 => "Channel Selected",
+        0x42 => "Disconnection Physical Link Complete",
+        0x43 => "Physical Link Loss Early Warning",
+        0x44 => "Physical Link Recovery",
+        0x45 => "Logical Link Complete",
+        0x46 => "Disconnection Logical Link Complete",
+        0x47 => "Flow Spec Modify Complete",
+        0x48 => "Number Of Completed Data Blocks",
+        0x4c => "Short Range Mode Change Complete",
+        0x4d => "AMP Status Change",
+        0x4e => "AMP Start Test",
+        0x4f => "AMP Test End",
+        0x50 => "AMP Receiver Report",
+        0x51 => "Short Range Mode Change Complete",
+        0x52 => "AMP Status Change",
+        _    => return None,
+    };
+
+    Some(name)
+}
+
+const REMOTE_VERSION: param::Layout = &[
+    ("Status", param::Param::Status),
+    ("Handle", param::Param::Hex16),
+    ("LMP version", param::Param::Named(param::VERSIONS)),
+    ("Manufacturer", param::Param::Company),
+    ("LMP subversion", param::Param::Hex16),
+];
+
+const SYNC_CONN_COMPLETE: param::Layout = &[
+    ("Status", param::Param::Status),
+    ("Handle", param::Param::Hex16),
+    ("Address", param::Param::Addr),
+    ("Link type", param::Param::Named(param::LINK_TYPES)),
+    ("Transmission interval (slots)", param::Param::U8),
+    ("Retransmission window (slots)", param::Param::U8),
+    ("RX packet length", param::Param::U16),
+    ("TX packet length", param::Param::U16),
+    ("Air mode", param::Param::Named(param::AIR_MODES)),
+];
+
+const SYNC_CONN_CHANGED: param::Layout = &[
+    ("Status", param::Param::Status),
+    ("Handle", param::Param::Hex16),
+    ("Transmission interval (slots)", param::Param::U8),
+    ("Retransmission window (slots)", param::Param::U8),
+    ("RX packet length", param::Param::U16),
+    ("TX packet length", param::Param::U16),
+];
+
 impl fmt::Display for Event<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.code {
-            0x01 => write!(f, "Inquiry Complete"),
-            0x02 => write!(f, "Inquiry Result"),
-            0x03 => write!(f, "Connection Complete"),
-            0x04 => write!(f, "Connection Request"),
+            0x0c | 0x2c | 0x2d => {
+                let (name, layout) = match self.code {
+                    0x0c => ("Read Remote Version Information Complete", REMOTE_VERSION),
+                    0x2c => ("Synchronous Connection Complete", SYNC_CONN_COMPLETE),
+                    _    => ("Synchronous Connection Changed", SYNC_CONN_CHANGED),
+                };
+                write!(f, "{}", name)?;
+                match param::fields(layout, &self.param) {
+                    Some(fields) => fields.into_iter().try_for_each(|(name, val)| field(f, name, val)),
+                    None => write!(f, " param {:02x?}", self.param),
+                }
+            },
             0x05 => {
-                match disconnect_complete(self.param) {
+                match disconnect_complete(&self.param) {
                     Ok((_, (status, handle, reason))) => {
                         write!(f, "Disconnect Complete: {} (0x{:02x}) handle 0x{:04x} reason 0x{:02x}",
                             status_str(status), status, handle, reason)
@@ -185,110 +442,94 @@ impl fmt::Display for Event<'_> {
                     Err(e) => write!(f, "Disconnect Complete failed to parse: {:?}", e),
                 }
             },
-            0x06 => write!(f, "Authentication Complete"),
-            0x07 => write!(f, "Remote Name Request Complete"),
-            0x08 => write!(f, "Encryption Change"),
-            0x09 => write!(f, "Change Connection Link Key Complete"),
-            0x0a => write!(f, "Master Link Key Complete"),
-            0x0b => write!(f, "Read Remote Supported Features Complete"),
-            0x0c => write!(f, "Read Remote Version Information Complete"),
-            0x0d => write!(f, "QoS Setup Complete"),
             0x0e => {
-                match command_complete(self.param) {
+                match command_complete(&self.param) {
                     Ok((rem, (ncmd, op))) => {
-                        write!(f, "Command Complete: ncmd {} {} param {:02x?}", ncmd, Op::from(op), rem)
+                        let op = Op::from(op);
+
+                        write!(f, "Command Complete: ncmd {} {}", ncmd, op)?;
+                        match ret::fields(op, rem) {
+                            Some(fields) => fields.into_iter().try_for_each(|(name, val)| field(f, name, val)),
+                            None => write!(f, " param {:02x?}", rem),
+                        }
                     },
                     Err(e) => write!(f, "Command Complete failed to parse: {:?}", e),
                 }
             },
             0x0f => {
-                match command_status(self.param) {
+                match command_status(&self.param) {
                     Ok((_, (status, ncmd, op))) => {
                         write!(f, "Command Status: {} (0x{:02x}) ncmd {} {}", status_str(status), status, ncmd, Op::from(op))
                     },
                     Err(e) => write!(f, "Command Status failed to parse: {:?}", e),
                 }
             },
-            0x10 => write!(f, "Hardware Error"),
-            0x11 => write!(f, "Flush Occurred"),
-            0x12 => write!(f, "Role Change"),
             0x13 => {
-                match num_completed_pkts(self.param) {
+                match num_completed_pkts(&self.param) {
                     Ok((_, (_n_handles, handle, pkts))) => {
                         write!(f, "Number Of Completed Packets: handle 0x{:04x} pkts {}", handle, pkts)
                     },
                     Err(e) => write!(f, "Number Of Completed Packets failed to parse: {:?}", e),
                 }
             },
-            0x14 => write!(f, "Mode Change"),
-            0x15 => write!(f, "Return Link Keys"),
-            0x16 => write!(f, "PIN Code Request"),
-            0x17 => write!(f, "Link Key Request"),
-            0x18 => write!(f, "Link Key Notification"),
-            0x19 => write!(f, "Loopback Command"),
-            0x1a => write!(f, "Data Buffer Overflow"),
-            0x1b => write!(f, "Max Slots Change"),
-            0x1c => write!(f, "Read Clock Offset Complete"),
-            0x1d => write!(f, "Connection Packet Type Changed"),
-            0x1e => write!(f, "QoS Violation"),
-            0x1f => write!(f, "Page Scan Mode Change"),
-            0x20 => write!(f, "Page Scan Repetition Mode Change"),
-            0x21 => write!(f, "Flow Specification Complete"),
-            0x22 => write!(f, "Inquiry Result with RSSI"),
-            0x23 => write!(f, "Read Remote Extended Features Complete"),
-            0x2c => write!(f, "Synchronous Connection Complete"),
-            0x2d => write!(f, "Synchronous Connection Changed"),
-            0x2e => write!(f, "Sniff Subrating"),
-            0x2f => write!(f, "Extended Inquiry Result"),
-            0x30 => write!(f, "Encryption Key Refresh Complete"),
-            0x31 => write!(f, "IO Capability Request"),
-            0x32 => write!(f, "IO Capability Response"),
-            0x33 => write!(f, "User Confirmation Request"),
-            0x34 => write!(f, "User Passkey Request"),
-            0x35 => write!(f, "Remote OOB Data Request"),
-            0x36 => write!(f, "Simple Pairing Complete"),
-            0x38 => write!(f, "Link Supervision Timeout Changed"),
-            0x39 => write!(f, "Enhanced Flush Complete"),
-            0x3b => write!(f, "User Passkey Notification"),
-            0x3c => write!(f, "Keypress Notification"),
-            0x3d => write!(f, "Remote Host Supported Features Notification"),
             0x3e => {
-                match le_meta(self.param) {
+                match le_meta(&self.param) {
                     Ok((rem, sub)) => {
-                        write!(f, "{} (0x{:02x}) param {:02x?}", le_sub_str(sub), sub, rem)
+                        write!(f, "{} (0x{:02x})", le_sub_str(sub), sub)?;
+                        match LeEvent::parse(sub, rem) {
+                            Ok(ev) => write!(f, "{}", ev),
+                            Err(_) => write!(f, " param {:02x?}", rem),
+                        }
                     },
                     Err(e) => write!(f, "LE Meta failed to parse: {:?}", e),
                 }
             },
-            0x40 => write!(f, "Physical Link Complete"),
-            0x41 => write!(f, "Channel Selected"),
-            0x42 => write!(f, "Disconnection Physical Link Complete"),
-            0x43 => write!(f, "Physical Link Loss Early Warning"),
-            0x44 => write!(f, "Physical Link Recovery"),
-            0x45 => write!(f, "Logical Link Complete"),
-            0x46 => write!(f, "Disconnection Logical Link Complete"),
-            0x47 => write!(f, "Flow Spec Modify Complete"),
-            0x48 => write!(f, "Number Of Completed Data Blocks"),
-            0x4c => write!(f, "Short Range Mode Change Complete"),
-            0x4d => write!(f, "AMP Status Change"),
-            0x4e => write!(f, "AMP Start Test"),
-            0x4f => write!(f, "AMP Test End"),
-            0x50 => write!(f, "AMP Receiver Report"),
-            0x51 => write!(f, "Short Range Mode Change Complete"),
-            0x52 => write!(f, "AMP Status Change"),
-            _ => {
-                write!(f, "0x{:02x} (Unknown): {:02x?}", self.code, self.param)
+            code => match event_str(code) {
+                Some(name) => write!(f, "{}", name),
+                None => write!(f, "0x{:02x} (Unknown): {:02x?}", code, self.param),
             },
         }
     }
 }
 
 impl Event <'_> {
-    pub fn parse(data: &'_[u8]) -> IResult<&[u8], Event> {
+    pub(crate) fn parser(data: &[u8]) -> PResult<'_, Event<'_>> {
         let (data, code) = le_u8(data)?;
         let (data, param) = length_data(le_u8)(data)?;
 
-        Ok((data, Event { code, param }))
+        Ok((data, Event { code, param: Cow::Borrowed(param) }))
+    }
+
+    pub fn code(&self) -> u8 {
+        self.code
+    }
+
+    pub fn param(&self) -> &[u8] {
+        &self.param
+    }
+
+    /// Status, handle and reason, if this is a well-formed Disconnect Complete
+    pub fn disconnect_complete(&self) -> Option<(u8, u16, u8)> {
+        match (self.code, disconnect_complete(&self.param)) {
+            (0x05, Ok((_, v))) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Decoded subevent, if this is a well-formed LE Meta event
+    pub fn le_event(&self) -> Option<LeEvent<'_>> {
+        match (self.code, le_meta(&self.param)) {
+            (0x3e, Ok((rem, sub))) => LeEvent::parse(sub, rem).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn into_owned(self) -> Event<'static> {
+        Event { code: self.code, param: Cow::Owned(self.param.into_owned()) }
+    }
+
+    pub fn parse(data: &[u8]) -> error::Result<(&[u8], Event<'_>)> {
+        error::finish(Layer::Hci, data, Event::parser(data))
     }
 }
 
@@ -328,7 +569,8 @@ macro_rules! ocf {
 use Ogf::*;
 
 #[repr(u16)]
-#[derive(Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Op {
     // Link Control commands
     Inquiry                       = op!(LinkControl, 0x0001),
@@ -601,6 +843,9 @@ pub enum Op {
     LeWriteRfPathCompensation     = op!(Le,          0x004d),
     LeSetPrivacyMode              = op!(Le,          0x004e),
     LeSetPeriodicAdvParamV2       = op!(Le,          0x0086),
+    LeCreateCis                   = op!(Le,          0x0064),
+    LeCreateBig                   = op!(Le,          0x0068),
+    LeBigCreateSync               = op!(Le,          0x006b),
 
     #[num_enum(catch_all)]
     Unknown(u16),
@@ -865,6 +1110,9 @@ impl fmt::Display for Op {
             LeClearAdvSets                => write!(f, "LE Clear Advertising Sets"),
             LeSetPeriodicAdvParamV1       => write!(f, "LE Set Periodic Advertising Parameters [v1]"),
             LeSetPeriodicAdvParamV2       => write!(f, "LE Set Periodic Advertising Parameters [v2]"),
+            LeCreateCis                   => write!(f, "LE Create Connected Isochronous Stream"),
+            LeCreateBig                   => write!(f, "LE Create BIG"),
+            LeBigCreateSync               => write!(f, "LE BIG Create Sync"),
             LeSetPeriodicAdvData          => write!(f, "LE Set Periodic Advertising Data"),
             LeSetPeriodicAdvEnable        => write!(f, "LE Set Periodic Advertising Enable"),
             LeSetExtScanParam             => write!(f, "LE Set Extended Scan Parameters"),
@@ -890,21 +1138,47 @@ impl fmt::Display for Op {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Command <'a> {
     op: Op,
-    param: &'a[u8],
+    param: Cow<'a, [u8]>,
 }
 
 impl Command <'_> {
-    pub fn parse(data: &'_[u8]) -> IResult<&[u8], Command> {
+    pub(crate) fn parser(data: &[u8]) -> PResult<'_, Command<'_>> {
         let (data, (op_raw, param)) = tuple((le_u16, length_data(le_u8)))(data)?;
-        Ok((data, Command { op: Op::from(op_raw), param }))
+        Ok((data, Command { op: Op::from(op_raw), param: Cow::Borrowed(param) }))
+    }
+
+    pub fn into_owned(self) -> Command<'static> {
+        Command { op: self.op, param: Cow::Owned(self.param.into_owned()) }
+    }
+
+    pub fn parse(data: &[u8]) -> error::Result<(&[u8], Command<'_>)> {
+        error::finish(Layer::Hci, data, Command::parser(data))
+    }
+
+    pub fn op(&self) -> Op {
+        self.op
+    }
+
+    pub fn param(&self) -> &[u8] {
+        &self.param
     }
 }
 
 impl fmt::Display for Command<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}: {:02x?}", self.op, self.param)
+        match cmd::layout(self.op).and_then(|layout| param::fields(layout, &self.param)) {
+            Some(fields) => {
+                write!(f, "{}", self.op)?;
+                for (name, val) in fields {
+                    field(f, name, val)?;
+                }
+                Ok(())
+            },
+            None => write!(f, "{}: {:02x?}", self.op, self.param),
+        }
     }
 }
@@ -0,0 +1,211 @@
+use std::fmt;
+use crate::att::{appearance, Uuid};
+use super::company::company_str;
+use super::{bit_names, hex, AdvInterval};
+
+fn ad_type_str(ad_type: u8) -> &'static str {
+    match ad_type {
+        0x01 => "Flags",
+        0x02 => "16-bit Service UUIDs (incomplete)",
+        0x03 => "16-bit Service UUIDs (complete)",
+        0x04 => "32-bit Service UUIDs (incomplete)",
+        0x05 => "32-bit Service UUIDs (complete)",
+        0x06 => "128-bit Service UUIDs (incomplete)",
+        0x07 => "128-bit Service UUIDs (complete)",
+        0x08 => "Name (short)",
+        0x09 => "Name (complete)",
+        0x0a => "TX power",
+        0x0d => "Class of Device",
+        0x10 => "Device ID",
+        0x11 => "Security Manager OOB Flags",
+        0x12 => "Peripheral Connection Interval Range",
+        0x14 => "16-bit Service Solicitation UUIDs",
+        0x15 => "128-bit Service Solicitation UUIDs",
+        0x16 => "Service Data (16-bit UUID)",
+        0x17 => "Public Target Address",
+        0x18 => "Random Target Address",
+        0x19 => "Appearance",
+        0x1a => "Advertising Interval",
+        0x1b => "LE Bluetooth Device Address",
+        0x1c => "LE Role",
+        0x1f => "32-bit Service Solicitation UUIDs",
+        0x20 => "Service Data (32-bit UUID)",
+        0x21 => "Service Data (128-bit UUID)",
+        0x24 => "URI",
+        0x27 => "LE Supported Features",
+        0x29 => "PB-ADV",
+        0x2a => "Mesh Message",
+        0x2b => "Mesh Beacon",
+        0x2c => "BIGInfo",
+        0x2d => "Broadcast Code",
+        0x2e => "Resolvable Set Identifier",
+        0x2f => "Advertising Interval (long)",
+        0x30 => "Broadcast Name",
+        0x31 => "Encrypted Advertising Data",
+        0x3d => "3D Information Data",
+        0xff => "Manufacturer Specific Data",
+        _    => "Unknown",
+    }
+}
+
+fn le_role_str(role: u8) -> &'static str {
+    match role {
+        0x00 => "Only Peripheral Role supported",
+        0x01 => "Only Central Role supported",
+        0x02 => "Peripheral and Central Role supported, Peripheral Role preferred",
+        0x03 => "Peripheral and Central Role supported, Central Role preferred",
+        _    => "Reserved",
+    }
+}
+
+/// URI scheme name abbreviated to a single code point by the Assigned Numbers
+fn uri_scheme_str(code: char) -> Option<&'static str> {
+    match code {
+        '\u{01}' => Some(""),
+        '\u{02}' => Some("aaa:"),
+        '\u{03}' => Some("aaas:"),
+        '\u{0f}' => Some("ftp:"),
+        '\u{12}' => Some("geo:"),
+        '\u{16}' => Some("http:"),
+        '\u{17}' => Some("https:"),
+        '\u{1c}' => Some("mailto:"),
+        '\u{2e}' => Some("tel:"),
+        _        => None,
+    }
+}
+
+fn uuids(data: &[u8], len: usize) -> Option<String> {
+    if data.is_empty() || !data.len().is_multiple_of(len) {
+        return None;
+    }
+    let list: Vec<_> = data.chunks(len).map(|c| match Uuid::from_le_bytes(c) {
+        Some(uuid) => uuid.to_string(),
+        None => format!("0x{:08x}", u32::from_le_bytes([c[0], c[1], c[2], c[3]])),
+    }).collect();
+
+    Some(list.join(", "))
+}
+
+fn text(data: &[u8]) -> String {
+    format!("{:?}", String::from_utf8_lossy(data))
+}
+
+fn with_data(desc: String, data: &[u8]) -> String {
+    match data.is_empty() {
+        true  => desc,
+        false => format!("{} {:02x?}", desc, data),
+    }
+}
+
+/// Value of one AD structure, or `None` if its length doesn't fit its type
+fn value(ad_type: u8, data: &[u8]) -> Option<String> {
+    const FLAGS: [&str; 5] = [
+        "LE Limited Discoverable Mode",
+        "LE General Discoverable Mode",
+        "BR/EDR Not Supported",
+        "Simultaneous LE and BR/EDR (Controller)",
+        "Simultaneous LE and BR/EDR (Host)",
+    ];
+
+    let val = match (ad_type, data) {
+        (0x01, [flags]) => bit_names(*flags as u16, 2, &FLAGS),
+        (0x02 | 0x03 | 0x14, data) => uuids(data, 2)?,
+        (0x04 | 0x05 | 0x1f, data) => uuids(data, 4)?,
+        (0x06 | 0x07 | 0x15, data) => uuids(data, 16)?,
+        (0x08 | 0x09 | 0x30, data) => text(data),
+        (0x0a, [power]) => format!("{} dBm", *power as i8),
+        (0x16, [lo, hi, rest @ ..]) => {
+            with_data(Uuid::Uuid16(u16::from_le_bytes([*lo, *hi])).to_string(), rest)
+        },
+        (0x20, [b0, b1, b2, b3, rest @ ..]) => {
+            with_data(format!("0x{:08x}", u32::from_le_bytes([*b0, *b1, *b2, *b3])), rest)
+        },
+        (0x21, data) if data.len() >= 16 => {
+            with_data(Uuid::from_le_bytes(&data[..16])?.to_string(), &data[16..])
+        },
+        (0x19, [lo, hi]) => appearance(u16::from_le_bytes([*lo, *hi])),
+        (0x1a, [lo, hi]) => AdvInterval(u16::from_le_bytes([*lo, *hi]) as u32).to_string(),
+        (0x2f, [b0, b1, b2]) => AdvInterval(u32::from_le_bytes([*b0, *b1, *b2, 0])).to_string(),
+        (0x2f, [b0, b1, b2, b3]) => AdvInterval(u32::from_le_bytes([*b0, *b1, *b2, *b3])).to_string(),
+        (0x1c, [role]) => format!("{} (0x{:02x})", le_role_str(*role), role),
+        (0x24, data) => {
+            let uri = String::from_utf8_lossy(data);
+            let mut chars = uri.chars();
+            match chars.next().and_then(uri_scheme_str) {
+                Some(scheme) => format!("{:?}", format!("{}{}", scheme, chars.as_str())),
+                None => format!("{:?}", uri),
+            }
+        },
+        (0xff, [lo, hi, rest @ ..]) => {
+            let company = u16::from_le_bytes([*lo, *hi]);
+            with_data(format!("{} (0x{:04x})", company_str(company), company), rest)
+        },
+        (0x2d, data) => hex(data),
+        (_, data) => format!("{:02x?}", data),
+    };
+
+    Some(val)
+}
+
+/// Advertising or scan response data, printed as its length followed by one
+/// line per AD structure
+pub(crate) struct AdData<'a>(pub &'a [u8]);
+
+impl fmt::Display for AdData<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes", self.0.len())?;
+
+        let mut i = self.0;
+        while let [len, rest @ ..] = i {
+            let len = *len as usize;
+            if len == 0 {
+                // Zero length marks the end of significant data
+                if rest.iter().any(|b| *b != 0) {
+                    write!(f, "\n          Non-significant data: {:02x?}", rest)?;
+                }
+                break;
+            }
+            let Some((entry, rem)) = (len <= rest.len()).then(|| rest.split_at(len)) else {
+                return write!(f, "\n          Truncated AD structure: {:02x?}", i);
+            };
+            let (ad_type, data) = (entry[0], &entry[1..]);
+            let name = match ad_type_str(ad_type) {
+                "Unknown" => format!("Unknown type 0x{:02x}", ad_type),
+                name => name.to_string(),
+            };
+            match value(ad_type, data) {
+                Some(val) => write!(f, "\n          {}: {}", name, val)?,
+                None => write!(f, "\n          {} (bad length): {:02x?}", name, data)?,
+            }
+            i = rem;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdData;
+
+    #[test]
+    fn ad_structures() {
+        let data = [
+            0x02, 0x01, 0x06,
+            0x03, 0x03, 0x0d, 0x18,
+            0x05, 0x09, 0x78, 0x67, 0x32, 0x34,
+            0x05, 0xff, 0xff, 0x02, 0x01, 0x02,
+            0x06, 0x24, 0x17, 0x2f, 0x2f, 0x61, 0x62,
+            0x00, 0x00,
+        ];
+
+        assert_eq!(AdData(&data).to_string(), "28 bytes\
+            \n          Flags: 0x06 (LE General Discoverable Mode, BR/EDR Not Supported)\
+            \n          16-bit Service UUIDs (complete): Heart Rate (0x180d)\
+            \n          Name (complete): \"xg24\"\
+            \n          Manufacturer Specific Data: Silicon Laboratories (0x02ff) [01, 02]\
+            \n          URI: \"https://ab\"");
+        assert_eq!(AdData(&[0x03, 0x19, 0x00]).to_string(), "3 bytes\
+            \n          Truncated AD structure: [03, 19, 00]");
+    }
+}
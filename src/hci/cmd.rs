@@ -0,0 +1,814 @@
+use super::Op;
+use super::param::{Layout, Param::*, ENABLE, OWN_ADDR_TYPE, PHYS};
+
+const ADV_TYPE: &[&str] = &[
+    "Connectable undirected - ADV_IND",
+    "Connectable directed - ADV_DIRECT_IND (high duty cycle)",
+    "Scannable undirected - ADV_SCAN_IND",
+    "Non connectable undirected - ADV_NONCONN_IND",
+    "Connectable directed - ADV_DIRECT_IND (low duty cycle)",
+];
+
+const ADV_PROPS: &[&str] = &[
+    "Connectable",
+    "Scannable",
+    "Directed",
+    "High Duty Cycle Directed Connectable",
+    "Use legacy advertising PDUs",
+    "Anonymous advertising",
+    "Include TxPower",
+];
+
+const ADV_FILTER: &[&str] = &[
+    "Allow Scan Request from Any, Allow Connect Request from Any",
+    "Allow Scan Request from Accept List Only, Allow Connect Request from Any",
+    "Allow Scan Request from Any, Allow Connect Request from Accept List Only",
+    "Allow Scan Request from Accept List Only, Allow Connect Request from Accept List Only",
+];
+
+const CHANNEL_MAP: &[&str] = &["37", "38", "39"];
+
+const SCAN_TYPE: &[&str] = &["Passive", "Active"];
+
+const SCAN_FILTER: &[&str] = &[
+    "Accept all advertisement",
+    "Ignore not in accept list",
+    "Accept all advertisement, inc. directed unresolved RPA",
+    "Ignore not in accept list, exc. directed unresolved RPA",
+];
+
+const INIT_FILTER: &[&str] = &["Accept list is not used", "Accept list is used"];
+
+const ALL_PHYS: &[&str] = &["No TX PHY preference", "No RX PHY preference"];
+
+const DATA_OP: &[&str] = &[
+    "Intermediate fragment", "First fragment", "Last fragment", "Complete data", "Unchanged data",
+];
+
+const PERIODIC_ENABLE: &[&str] = &["Enable", "Include ADI"];
+
+const PRIVACY_MODE: &[&str] = &["Network Privacy Mode", "Device Privacy Mode"];
+
+pub(super) const SCAN_ENABLE: &[&str] = &["No Scans", "Inquiry Scan", "Page Scan", "Inquiry Scan + Page Scan"];
+
+const IO_CAP: &[&str] = &["DisplayOnly", "DisplayYesNo", "KeyboardOnly", "NoInputNoOutput"];
+
+pub(super) const LINK_POLICY: &[&str] = &["Enable Role Switch", "Enable Hold Mode", "Enable Sniff Mode", "Enable Park State"];
+
+const PIN_TYPE: &[&str] = &["Variable", "Fixed"];
+
+pub(super) const INQUIRY_MODE: &[&str] = &[
+    "Standard Inquiry Result", "Inquiry Result with RSSI", "Inquiry Result with RSSI or Extended Inquiry Result",
+];
+
+const SCAN_KIND: &[&str] = &["Standard Scan", "Interlaced Scan"];
+
+const FLOW_CONTROL: &[&str] = &["Off", "ACL on, SCO off", "ACL off, SCO on", "ACL on, SCO on"];
+
+const POWER_TYPE: &[&str] = &["Current Transmit Power Level", "Maximum Transmit Power Level"];
+
+const WHICH_CLOCK: &[&str] = &["Local", "Piconet"];
+
+const PACKING: &[&str] = &["Sequential", "Interleaved"];
+
+const FRAMING: &[&str] = &["Unframed", "Framed"];
+
+const CODING_FORMAT: &[&str] = &["u-law log", "A-law log", "CVSD", "Transparent", "Linear PCM", "mSBC", "LC3", "G.729A"];
+
+const PCM_FORMAT: &[&str] = &["N/A", "1's complement", "2's complement", "Sign-magnitude", "Unsigned"];
+
+const ENH_SETUP_SYNC_CONN: Layout = &[
+    ("Handle", Hex16),
+    ("Transmit bandwidth", U32),
+    ("Receive bandwidth", U32),
+    ("Transmit coding format", Named(CODING_FORMAT)),
+    ("Transmit coding company", Company),
+    ("Transmit vendor codec", Hex16),
+    ("Receive coding format", Named(CODING_FORMAT)),
+    ("Receive coding company", Company),
+    ("Receive vendor codec", Hex16),
+    ("Transmit codec frame size", U16),
+    ("Receive codec frame size", U16),
+    ("Input bandwidth", U32),
+    ("Output bandwidth", U32),
+    ("Input coding format", Named(CODING_FORMAT)),
+    ("Input coding company", Company),
+    ("Input vendor codec", Hex16),
+    ("Output coding format", Named(CODING_FORMAT)),
+    ("Output coding company", Company),
+    ("Output vendor codec", Hex16),
+    ("Input coded data size", U16),
+    ("Output coded data size", U16),
+    ("Input PCM data format", Named(PCM_FORMAT)),
+    ("Output PCM data format", Named(PCM_FORMAT)),
+    ("Input PCM sample MSB position", U8),
+    ("Output PCM sample MSB position", U8),
+    ("Input data path", Hex8),
+    ("Output data path", Hex8),
+    ("Input transport unit size", U8),
+    ("Output transport unit size", U8),
+    ("Max latency", U16),
+    ("Packet type", Hex16),
+    ("Retransmission effort", Hex8),
+];
+
+const ENH_ACCEPT_SYNC_CONN: Layout = &[
+    ("Address", Addr),
+    ("Transmit bandwidth", U32),
+    ("Receive bandwidth", U32),
+    ("Transmit coding format", Named(CODING_FORMAT)),
+    ("Transmit coding company", Company),
+    ("Transmit vendor codec", Hex16),
+    ("Receive coding format", Named(CODING_FORMAT)),
+    ("Receive coding company", Company),
+    ("Receive vendor codec", Hex16),
+    ("Transmit codec frame size", U16),
+    ("Receive codec frame size", U16),
+    ("Input bandwidth", U32),
+    ("Output bandwidth", U32),
+    ("Input coding format", Named(CODING_FORMAT)),
+    ("Input coding company", Company),
+    ("Input vendor codec", Hex16),
+    ("Output coding format", Named(CODING_FORMAT)),
+    ("Output coding company", Company),
+    ("Output vendor codec", Hex16),
+    ("Input coded data size", U16),
+    ("Output coded data size", U16),
+    ("Input PCM data format", Named(PCM_FORMAT)),
+    ("Output PCM data format", Named(PCM_FORMAT)),
+    ("Input PCM sample MSB position", U8),
+    ("Output PCM sample MSB position", U8),
+    ("Input data path", Hex8),
+    ("Output data path", Hex8),
+    ("Input transport unit size", U8),
+    ("Output transport unit size", U8),
+    ("Max latency", U16),
+    ("Packet type", Hex16),
+    ("Retransmission effort", Hex8),
+];
+
+const HANDLE: Layout = &[("Handle", Hex16)];
+
+const ADDR: Layout = &[("Address", Addr)];
+
+const ADV_HANDLE: Layout = &[("Advertising handle", Hex8)];
+
+const TYPED_ADDR: Layout = &[("Address type", AddrType), ("Address", Addr)];
+
+const EXT_ADV_PARAMS: Layout = &[
+    ("Handle", Hex8),
+    ("Properties", Flags16(ADV_PROPS)),
+    ("Min advertising interval", AdvInterval24),
+    ("Max advertising interval", AdvInterval24),
+    ("Channel map", Flags8(CHANNEL_MAP)),
+    ("Own address type", Named(OWN_ADDR_TYPE)),
+    ("Peer address type", AddrType),
+    ("Peer address", Addr),
+    ("Filter policy", Named(ADV_FILTER)),
+    ("TX power", TxPower),
+    ("Primary PHY", Phy),
+    ("Secondary max skip", U8),
+    ("Secondary PHY", Phy),
+    ("SID", Hex8),
+    ("Scan request notifications", Named(ENABLE)),
+];
+
+const EXT_ADV_DATA: Layout = &[
+    ("Handle", Hex8),
+    ("Operation", Named(DATA_OP)),
+    ("Fragment preference", Hex8),
+    ("Data", ExtAdvData),
+];
+
+const EXT_CREATE_CONN: Layout = &[
+    ("Filter policy", Named(INIT_FILTER)),
+    ("Own address type", Named(OWN_ADDR_TYPE)),
+    ("Peer address type", AddrType),
+    ("Peer address", Addr),
+    ("Initiating PHYs", PerPhy(&[
+        ("Scan interval", AdvInterval),
+        ("Scan window", AdvInterval),
+        ("Min connection interval", ConnInterval),
+        ("Max connection interval", ConnInterval),
+        ("Connection latency", U16),
+        ("Supervision timeout", Timeout),
+        ("Min connection length", AdvInterval),
+        ("Max connection length", AdvInterval),
+    ])),
+];
+
+const RECEIVER_TEST: Layout = &[
+    ("RX channel", RfChannel),
+    ("PHY", Phy),
+    ("Modulation index", Hex8),
+];
+
+const TRANSMITTER_TEST: Layout = &[
+    ("TX channel", RfChannel),
+    ("Test data length", U8),
+    ("Packet payload", Hex8),
+    ("PHY", Phy),
+];
+
+/// Parameter layout of the command `op`, if known
+pub(crate) fn layout(op: Op) -> Option<Layout> {
+    use Op::*;
+
+    let layout: Layout = match op {
+        // Link Control commands
+        Inquiry => &[("LAP", Hex24), ("Length", U8), ("Num responses", U8)],
+        PeriodicInquiryMode => &[
+            ("Max period", U16),
+            ("Min period", U16),
+            ("LAP", Hex24),
+            ("Length", U8),
+            ("Num responses", U8),
+        ],
+        CreateConn => &[
+            ("Address", Addr),
+            ("Packet type", Hex16),
+            ("Page scan repetition mode", Hex8),
+            ("Page scan mode", Hex8),
+            ("Clock offset", Hex16),
+            ("Role switch", Hex8),
+        ],
+        Disconnect => &[("Handle", Hex16), ("Reason", Status)],
+        AddScoConn | ChangeConnPacketType => &[("Handle", Hex16), ("Packet type", Hex16)],
+        AcceptConnReq | SwitchRole => &[("Address", Addr), ("Role", Role)],
+        RejectConnReq | RejectSyncConnReq | IoCapReqNegReply => &[("Address", Addr), ("Reason", Status)],
+        LinkKeyReqReply => &[("Address", Addr), ("Link key", Key(16))],
+        PinCodeReqReply => &[("Address", Addr), ("PIN length", U8), ("PIN code", Bytes(16))],
+        CreateConnCancel | LinkKeyReqNegReply | PinCodeReqNegReply | RemoteNameReqCancel |
+        UserConfirmReqReply | UserConfirmReqNegReply | UserPasskeyReqNegReply |
+        RemoteOobDataReqNegReply | TruncatedPageCancel => ADDR,
+        AuthRequested | ChangeConnLinkKey | ReadRemoteSuppFeatures | ReadRemoteVerInfo |
+        ReadClockOffset | ReadLmpHandle => HANDLE,
+        SetConnEncryption => &[("Handle", Hex16), ("Encryption", Named(ENABLE))],
+        MasterLinkKey => &[("Key flag", Hex8)],
+        RemoteNameReq => &[
+            ("Address", Addr),
+            ("Page scan repetition mode", Hex8),
+            ("Page scan mode", Hex8),
+            ("Clock offset", Hex16),
+        ],
+        ReadRemoteExtFeatures => &[("Handle", Hex16), ("Page", U8)],
+        SetupSyncConn => &[
+            ("Handle", Hex16),
+            ("Transmit bandwidth", U32),
+            ("Receive bandwidth", U32),
+            ("Max latency", U16),
+            ("Voice setting", Hex16),
+            ("Retransmission effort", Hex8),
+            ("Packet type", Hex16),
+        ],
+        AcceptSyncConnReq => &[
+            ("Address", Addr),
+            ("Transmit bandwidth", U32),
+            ("Receive bandwidth", U32),
+            ("Max latency", U16),
+            ("Voice setting", Hex16),
+            ("Retransmission effort", Hex8),
+            ("Packet type", Hex16),
+        ],
+        IoCapReqReply => &[
+            ("Address", Addr),
+            ("IO capability", Named(IO_CAP)),
+            ("OOB data", Hex8),
+            ("Authentication", Hex8),
+        ],
+        UserPasskeyReqReply => &[("Address", Addr), ("Passkey", U32)],
+        RemoteOobDataReqReply => &[("Address", Addr), ("Hash C", Key(16)), ("Randomizer R", Key(16))],
+        CreatePhyLink | AcceptPhyLink => &[
+            ("Physical handle", Hex8),
+            ("Key length", U8),
+            ("Key type", Hex8),
+            ("Key", Rest),
+        ],
+        DisconnectPhyLink => &[("Physical handle", Hex8), ("Reason", Status)],
+        CreateLogicalLink | AcceptLogicalLink => &[
+            ("Physical handle", Hex8),
+            ("TX flow spec", Bytes(16)),
+            ("RX flow spec", Bytes(16)),
+        ],
+        DisconnectLogicalLink => &[("Logical handle", Hex16)],
+        LogicalLinkCancel => &[("Physical handle", Hex8), ("TX flow spec ID", Hex8)],
+        FlowSpecModify => &[("Handle", Hex16), ("TX flow spec", Bytes(16)), ("RX flow spec", Bytes(16))],
+        EnhancedSetupSyncConn => ENH_SETUP_SYNC_CONN,
+        EnhancedAcceptSyncConnReq => ENH_ACCEPT_SYNC_CONN,
+        TruncatedPage => &[("Address", Addr), ("Page scan repetition mode", Hex8), ("Clock offset", Hex16)],
+        SetConnlessSlaveBroadcast => &[
+            ("Enable", Named(ENABLE)),
+            ("LT_ADDR", U8),
+            ("LPO allowed", Hex8),
+            ("Packet type", Hex16),
+            ("Min interval", AdvInterval),
+            ("Max interval", AdvInterval),
+            ("Supervision timeout", AdvInterval),
+        ],
+        SetConnlessSlaveBroadcastRecv => &[
+            ("Enable", Named(ENABLE)),
+            ("Address", Addr),
+            ("LT_ADDR", U8),
+            ("Interval", AdvInterval),
+            ("Clock offset", Hex32),
+            ("Next broadcast clock", Hex32),
+            ("Supervision timeout", AdvInterval),
+            ("Remote timing accuracy", U8),
+            ("Skip", U8),
+            ("Packet type", Hex16),
+            ("AFH channel map", Bytes(10)),
+        ],
+        RecvSyncTrain => &[
+            ("Address", Addr),
+            ("Timeout", AdvInterval),
+            ("Window", AdvInterval),
+            ("Interval", AdvInterval),
+        ],
+
+        // Link Policy commands
+        HoldMode | ParkState => &[
+            ("Handle", Hex16),
+            ("Max interval", AdvInterval),
+            ("Min interval", AdvInterval),
+        ],
+        SniffMode => &[
+            ("Handle", Hex16),
+            ("Max interval", AdvInterval),
+            ("Min interval", AdvInterval),
+            ("Attempt", AdvInterval),
+            ("Timeout", AdvInterval),
+        ],
+        ExitSniffMode | ExitParkState | RoleDiscovery | ReadLinkPolicySettings => HANDLE,
+        QoSSetup => &[
+            ("Handle", Hex16),
+            ("Flags", Hex8),
+            ("Service type", Hex8),
+            ("Token rate", U32),
+            ("Peak bandwidth", U32),
+            ("Latency", U32),
+            ("Delay variation", U32),
+        ],
+        WriteLinkPolicySettings => &[("Handle", Hex16), ("Link policy", Flags16(LINK_POLICY))],
+        WriteDefaultLinkPolicySettings => &[("Link policy", Flags16(LINK_POLICY))],
+        FlowSpec => &[
+            ("Handle", Hex16),
+            ("Flags", Hex8),
+            ("Flow direction", Hex8),
+            ("Service type", Hex8),
+            ("Token rate", U32),
+            ("Token bucket size", U32),
+            ("Peak bandwidth", U32),
+            ("Access latency", U32),
+        ],
+        SniffSubrating => &[
+            ("Handle", Hex16),
+            ("Max latency", AdvInterval),
+            ("Min remote timeout", AdvInterval),
+            ("Min local timeout", AdvInterval),
+        ],
+
+        // Controller & Baseband commands
+        SetEvtMask => &[("Mask", EventMask)],
+        SetEventFilter => &[("Type", Hex8), ("Condition", Rest)],
+        Flush | ReadAutoFlushTimeout | ReadLinkSupervisionTimeout | RefreshEncryptionKey => HANDLE,
+        WritePinType => &[("PIN type", Named(PIN_TYPE))],
+        ReadStoredLinkKey => &[("Address", Addr), ("Read all", Hex8)],
+        WriteStoredLinkKey => &[("Num keys", U8), ("Keys", Rest)],
+        DeleteStoredLinkKey => &[("Address", Addr), ("Delete all", Hex8)],
+        WriteLocalName => &[("Name", Name)],
+        WriteConnAcceptTimeout | WritePageTimeout => &[("Timeout", AdvInterval)],
+        WriteScanEnable => &[("Scan enable", Named(SCAN_ENABLE))],
+        WritePageScanActivity | WriteInquiryScanActivity => &[
+            ("Interval", AdvInterval),
+            ("Window", AdvInterval),
+        ],
+        WriteAuthEnable => &[("Enable", Named(ENABLE))],
+        WriteEncryptMode => &[("Mode", Hex8)],
+        WriteClassOfDevice => &[("Class", Hex24)],
+        WriteVoiceSetting => &[("Setting", Hex16)],
+        WriteAutoFlushTimeout => &[("Handle", Hex16), ("Timeout", AdvInterval)],
+        WriteNumBroadcastRetrans => &[("Num retransmissions", U8)],
+        WriteHoldModeActivity => &[("Activity", Hex8)],
+        ReadTransmitPowerLevel => &[("Handle", Hex16), ("Type", Named(POWER_TYPE))],
+        WriteSynchronousFlowControlEnable => &[("Flow control", Named(ENABLE))],
+        SetControllerToHostFlowControl => &[("Flow control", Named(FLOW_CONTROL))],
+        HostBufferSize => &[
+            ("ACL MTU", U16),
+            ("SCO MTU", U8),
+            ("ACL max packets", U16),
+            ("SCO max packets", U16),
+        ],
+        HostNumberOfCompletedPackets => &[("Num handles", List(&[
+            ("Handle", Hex16),
+            ("Count", U16),
+        ]))],
+        WriteLinkSupervisionTimeout => &[("Handle", Hex16), ("Timeout", AdvInterval)],
+        WriteCurrentIacLap => &[("Num current IAC", List(&[("Access code", Hex24)]))],
+        WritePageScanPeriodMode | WritePageScanMode => &[("Mode", Hex8)],
+        SetAFHHostChannelClassification => &[("Map", Bytes(10))],
+        WriteInquiryScanType | WritePageScanType => &[("Type", Named(SCAN_KIND))],
+        WriteInquiryMode => &[("Mode", Named(INQUIRY_MODE))],
+        WriteAFHChannelAssessmentMode => &[("Mode", Named(ENABLE))],
+        WriteExtendedInquiryResponse => &[("FEC", Hex8), ("Data", Bytes(240))],
+        WriteSimplePairingMode => &[("Mode", Named(ENABLE))],
+        WriteInquiryTransmitPowerLevel => &[("TX power", I8)],
+        WriteAnonMode => &[("Anonymity mode", Named(ENABLE))],
+        WriteAliasAuthEnable => &[("Alias authentication", Named(ENABLE))],
+        WriteAnonAddrChangeParams => &[("Address change interval", U32), ("Address change timeout", AdvInterval)],
+        WriteDefaultErrDataReporting => &[("Mode", Named(ENABLE))],
+        EnhanSetupSyncConn => ENH_SETUP_SYNC_CONN,
+        AcceptExtSyncConnReq => ENH_ACCEPT_SYNC_CONN,
+
+        // Informational parameters
+        ReadLocalExtendedFeatures => &[("Page", U8)],
+
+        // Status parameters
+        ReadFailedContactCounter | ResetFailedContactCounter | ReadLinkQuality | ReadRssi |
+        ReadAfhChannelMap | ReadEncryptionKeySize => HANDLE,
+        ReadClock => &[("Handle", Hex16), ("Which clock", Named(WHICH_CLOCK))],
+        ReadLocalAmpAssoc => &[
+            ("Physical handle", Hex8),
+            ("Length so far", U16),
+            ("Max length", U16),
+        ],
+        WriteRemoteAmpAssoc => &[
+            ("Physical handle", Hex8),
+            ("Length so far", U16),
+            ("Remaining length", U16),
+            ("Fragment", Rest),
+        ],
+        SetTriggeredClockCapture => &[
+            ("Handle", Hex16),
+            ("Enable", Named(ENABLE)),
+            ("Which clock", Named(WHICH_CLOCK)),
+            ("LPO allowed", Hex8),
+            ("Num captures to filter", U8),
+        ],
+
+        // Testing commands
+        WriteLoopbackMode => &[("Mode", Hex8)],
+        WriteSimplePairingDebugMode => &[("Debug mode", Named(ENABLE))],
+        EnableAmpReceiverReports => &[("Enable", Named(ENABLE)), ("Interval", U8)],
+        AmpTest => &[("Parameters", Rest)],
+        WriteSecureConnectionsTestMode => &[
+            ("Handle", Hex16),
+            ("DM1 ACL-U mode", Named(ENABLE)),
+            ("eSCO loopback mode", Named(ENABLE)),
+        ],
+
+        // LE Controller commands
+        LeSetEvtMask => &[("Mask", LeEventMask)],
+        LeSetRandomAddress => ADDR,
+        LeSetAdvPAram => &[
+            ("Min advertising interval", AdvInterval),
+            ("Max advertising interval", AdvInterval),
+            ("Type", Named(ADV_TYPE)),
+            ("Own address type", Named(OWN_ADDR_TYPE)),
+            ("Direct address type", AddrType),
+            ("Direct address", Addr),
+            ("Channel map", Flags8(CHANNEL_MAP)),
+            ("Filter policy", Named(ADV_FILTER)),
+        ],
+        LeSetAdvData | LeSetScanRespData => &[("Data", AdvData)],
+        LeSetAdvEnable => &[("Advertising", Named(ENABLE))],
+        LeSetScanParam => &[
+            ("Type", Named(SCAN_TYPE)),
+            ("Interval", AdvInterval),
+            ("Window", AdvInterval),
+            ("Own address type", Named(OWN_ADDR_TYPE)),
+            ("Filter policy", Named(SCAN_FILTER)),
+        ],
+        LeSetScanEnable => &[("Scanning", Named(ENABLE)), ("Filter duplicates", Named(ENABLE))],
+        LeCreateConn => &[
+            ("Scan interval", AdvInterval),
+            ("Scan window", AdvInterval),
+            ("Filter policy", Named(INIT_FILTER)),
+            ("Peer address type", AddrType),
+            ("Peer address", Addr),
+            ("Own address type", Named(OWN_ADDR_TYPE)),
+            ("Min connection interval", ConnInterval),
+            ("Max connection interval", ConnInterval),
+            ("Connection latency", U16),
+            ("Supervision timeout", Timeout),
+            ("Min connection length", AdvInterval),
+            ("Max connection length", AdvInterval),
+        ],
+        LeAddDevToFilterAcceptList | LeRemDevFromFilterAcceptList | LeRemDevFromResolvList |
+        LeReadPeerResolvAddress | LeReadLocalResolvAddress => TYPED_ADDR,
+        LeConnUpdate => &[
+            ("Handle", Hex16),
+            ("Min connection interval", ConnInterval),
+            ("Max connection interval", ConnInterval),
+            ("Connection latency", U16),
+            ("Supervision timeout", Timeout),
+            ("Min connection length", AdvInterval),
+            ("Max connection length", AdvInterval),
+        ],
+        LeSetHostChanClass => &[("Channel map", Bytes(5))],
+        LeReadChanMAp | LeReadRemFeatPage0 | LeLtkReqNegReply | LeReadPhy => HANDLE,
+        LeEncrypt => &[("Key", Key(16)), ("Plaintext data", Key(16))],
+        LeEnableEncrypt => &[
+            ("Handle", Hex16),
+            ("Random number", Hex64),
+            ("Encrypted diversifier", Hex16),
+            ("Long term key", Key(16)),
+        ],
+        LeLtkReqReply => &[("Handle", Hex16), ("Long term key", Key(16))],
+        LeReceiverTestV1 => &[("RX channel", RfChannel)],
+        LeReceiverTestV2 => RECEIVER_TEST,
+        LeReceiverTestV3 => &[
+            ("RX channel", RfChannel),
+            ("PHY", Phy),
+            ("Modulation index", Hex8),
+            ("Expected CTE length", U8),
+            ("Expected CTE type", Hex8),
+            ("Slot durations", Hex8),
+            ("Antenna IDs", Data),
+        ],
+        LeTransmitterTestV1 => &[
+            ("TX channel", RfChannel),
+            ("Test data length", U8),
+            ("Packet payload", Hex8),
+        ],
+        LeTransmitterTestV2 => TRANSMITTER_TEST,
+        LeTransmitterTestV3 => &[
+            ("TX channel", RfChannel),
+            ("Test data length", U8),
+            ("Packet payload", Hex8),
+            ("PHY", Phy),
+            ("CTE length", U8),
+            ("CTE type", Hex8),
+            ("Antenna IDs", Data),
+        ],
+        LeTransmitterTestV4 => &[
+            ("TX channel", RfChannel),
+            ("Test data length", U8),
+            ("Packet payload", Hex8),
+            ("PHY", Phy),
+            ("CTE length", U8),
+            ("CTE type", Hex8),
+            ("Antenna IDs", Data),
+            ("TX power", TxPower),
+        ],
+        LeRemoteConnParamReqReply => &[
+            ("Handle", Hex16),
+            ("Min connection interval", ConnInterval),
+            ("Max connection interval", ConnInterval),
+            ("Connection latency", U16),
+            ("Supervision timeout", Timeout),
+            ("Min connection length", AdvInterval),
+            ("Max connection length", AdvInterval),
+        ],
+        LeRemoteConnParamReqNegReply => &[("Handle", Hex16), ("Reason", Status)],
+        LeSetDataLength => &[("Handle", Hex16), ("TX octets", U16), ("TX time", Usec)],
+        LeWriteSuggDefDataLength => &[("TX octets", U16), ("TX time", Usec)],
+        LeGenerateDHKeyV1 => &[("Remote P-256 public key", Key(64))],
+        LeGenerateDHKeyV2 => &[("Remote P-256 public key", Key(64)), ("Key type", Hex8)],
+        LeAddDevToResolvList => &[
+            ("Address type", AddrType),
+            ("Address", Addr),
+            ("Peer identity resolving key", Key(16)),
+            ("Local identity resolving key", Key(16)),
+        ],
+        LeSetAddressResolutionEnable => &[("Address resolution", Named(ENABLE))],
+        LeSetResolvPrivAddrTimeout => &[("Timeout", Seconds)],
+        LeSetDefaultPhy => &[
+            ("All PHYs preference", Flags8(ALL_PHYS)),
+            ("TX PHYs preference", Flags8(PHYS)),
+            ("RX PHYs preference", Flags8(PHYS)),
+        ],
+        LeSetPhy => &[
+            ("Handle", Hex16),
+            ("All PHYs preference", Flags8(ALL_PHYS)),
+            ("TX PHYs preference", Flags8(PHYS)),
+            ("RX PHYs preference", Flags8(PHYS)),
+            ("PHY options", Hex16),
+        ],
+        LeSetAdvSetRandAddr => &[("Advertising handle", Hex8), ("Address", Addr)],
+        LeSetExtAdvParamV1 => EXT_ADV_PARAMS,
+        LeSetExtAdvParamV2 => &[
+            ("Handle", Hex8),
+            ("Properties", Flags16(ADV_PROPS)),
+            ("Min advertising interval", AdvInterval24),
+            ("Max advertising interval", AdvInterval24),
+            ("Channel map", Flags8(CHANNEL_MAP)),
+            ("Own address type", Named(OWN_ADDR_TYPE)),
+            ("Peer address type", AddrType),
+            ("Peer address", Addr),
+            ("Filter policy", Named(ADV_FILTER)),
+            ("TX power", TxPower),
+            ("Primary PHY", Phy),
+            ("Secondary max skip", U8),
+            ("Secondary PHY", Phy),
+            ("SID", Hex8),
+            ("Scan request notifications", Named(ENABLE)),
+            ("Primary PHY options", Hex8),
+            ("Secondary PHY options", Hex8),
+        ],
+        LeSetExtAdvData | LeSetExtScanRespData => EXT_ADV_DATA,
+        LeSetExtAdvEnable => &[
+            ("Extended advertising", Named(ENABLE)),
+            ("Number of sets", List(&[
+                ("Handle", Hex8),
+                ("Duration", Timeout),
+                ("Max ext adv events", U8),
+            ])),
+        ],
+        LeRemoveAdvSet => ADV_HANDLE,
+        LeSetPeriodicAdvParamV1 => &[
+            ("Handle", Hex8),
+            ("Min interval", ConnInterval),
+            ("Max interval", ConnInterval),
+            ("Properties", Flags16(ADV_PROPS)),
+        ],
+        LeSetPeriodicAdvParamV2 => &[
+            ("Handle", Hex8),
+            ("Min interval", ConnInterval),
+            ("Max interval", ConnInterval),
+            ("Properties", Flags16(ADV_PROPS)),
+            ("Num subevents", U8),
+            ("Subevent interval", U8),
+            ("Response slot delay", U8),
+            ("Response slot spacing", U8),
+            ("Num response slots", U8),
+        ],
+        LeSetPeriodicAdvData => &[("Handle", Hex8), ("Operation", Named(DATA_OP)), ("Data", Data)],
+        LeSetPeriodicAdvEnable => &[("Enable", Flags8(PERIODIC_ENABLE)), ("Handle", Hex8)],
+        LeSetExtScanParam => &[
+            ("Own address type", Named(OWN_ADDR_TYPE)),
+            ("Filter policy", Named(SCAN_FILTER)),
+            ("PHYs", PerPhy(&[
+                ("Type", Named(SCAN_TYPE)),
+                ("Interval", AdvInterval),
+                ("Window", AdvInterval),
+            ])),
+        ],
+        LeSetExtScanEnable => &[
+            ("Extended scan", Named(ENABLE)),
+            ("Filter duplicates", Hex8),
+            ("Duration", Timeout),
+            ("Period", Hex16),
+        ],
+        LeExtCreateConnV1 => EXT_CREATE_CONN,
+        LeExtCreateConnV2 => &[
+            ("Advertising handle", Hex8),
+            ("Subevent", U8),
+            ("Filter policy", Named(INIT_FILTER)),
+            ("Own address type", Named(OWN_ADDR_TYPE)),
+            ("Peer address type", AddrType),
+            ("Peer address", Addr),
+            ("Initiating PHYs", PerPhy(&[
+                ("Scan interval", AdvInterval),
+                ("Scan window", AdvInterval),
+                ("Min connection interval", ConnInterval),
+                ("Max connection interval", ConnInterval),
+                ("Connection latency", U16),
+                ("Supervision timeout", Timeout),
+                ("Min connection length", AdvInterval),
+                ("Max connection length", AdvInterval),
+            ])),
+        ],
+        LePeriodicAdvCreateSync => &[
+            ("Options", Hex8),
+            ("SID", Hex8),
+            ("Advertiser address type", AddrType),
+            ("Advertiser address", Addr),
+            ("Skip", U16),
+            ("Sync timeout", Timeout),
+            ("Sync CTE type", Hex8),
+        ],
+        LePeriodicAdvTerminateSync => &[("Sync handle", Hex16)],
+        LeAddDevToPeriodicAdvList | LeRemDevFromPeriodicAdvList => &[
+            ("Address type", AddrType),
+            ("Address", Addr),
+            ("SID", Hex8),
+        ],
+        LeWriteRfPathCompensation => &[("TX path compensation", Db10), ("RX path compensation", Db10)],
+        LeSetPrivacyMode => &[("Address type", AddrType), ("Address", Addr), ("Mode", Named(PRIVACY_MODE))],
+        LeCreateCis => &[("Num CIS", List(&[("CIS handle", Hex16), ("ACL handle", Hex16)]))],
+        LeCreateBig => &[
+            ("BIG handle", Hex8),
+            ("Advertising handle", Hex8),
+            ("Num BIS", U8),
+            ("SDU interval", Usec24),
+            ("Max SDU", U16),
+            ("Max transport latency", U16),
+            ("RTN", U8),
+            ("PHY", Flags8(PHYS)),
+            ("Packing", Named(PACKING)),
+            ("Framing", Named(FRAMING)),
+            ("Encryption", Named(ENABLE)),
+            ("Broadcast code", Key(16)),
+        ],
+        LeBigCreateSync => &[
+            ("BIG handle", Hex8),
+            ("Sync handle", Hex16),
+            ("Encryption", Named(ENABLE)),
+            ("Broadcast code", Key(16)),
+            ("MSE", U8),
+            ("Sync timeout", Timeout),
+            ("Num BIS", List(&[("BIS", U8)])),
+        ],
+
+        // Commands without parameters
+        InquiryCancel | ExitPeriodicInquiryMode | StartSyncTrain | ReadDefaultLinkPolicySettings |
+        Reset | ReadPinType | CreateNewUnitKey | ReadLocalName | ReadConnAcceptTimeout |
+        ReadPageTimeout | ReadScanEnable | ReadPageScanActivity | ReadInquiryScanActivity |
+        ReadAuthEnable | ReadEncryptMode | ReadClassOfDevice | ReadVoiceSetting |
+        ReadNumBroadcastRetrans | ReadHoldModeActivity | ReadSynchronousFlowControlEnable |
+        ReadNumberOfSupportedIac | ReadCurrentIacLap | ReadPageScanPeriodMode | ReadPageScanMode |
+        ReadInquiryScanType | ReadInquiryMode | ReadPageScanType | ReadAFHChannelAssessmentMode |
+        ReadExtendedInquiryResponse | ReadSimplePairingMode | ReadLocalOOBData |
+        ReadInquiryResponseTransmitPowerLevel | ReadDefaultErrDataReporting |
+        ReadLocalVerInfo | ReadLocalSupportedCommands | ReadLocalSupportedFeatures | ReadBufSize |
+        ReadBdAddr | ReadLocalAmpInfo | GetMwsTransportLayerConfig | ReadLoopbackMode |
+        EnableDeviceUnderTestMode | AmpTestEnd | LeReadBufSizeV1 | LeReadBufSizeV2 |
+        LeReadLocalFeaturesPage0 | LeReadAdvPhyChanTxPower | LeCreateConnCancel |
+        LeReadFilterAcceptListSize | LeClearFilterAcceptList | LeRand | LeReadSupportedStates |
+        LeTestEnd | LeReadSuggDefDataLength | LeReadLocalP256PubKey | LeClearResolvList |
+        LeReadResolvListSize | LeReadMaxDataLength | LeReadMaxAdvDataLength | LeReadNumSuppAdvSets |
+        LeClearAdvSets | LePeriodicAdvCreateSyncCancel | LeClearPeriodicAdvList |
+        LeReadPeriodicAdvListSize | LeReadTransmitPower | LeReadRfPathCompensation => &[],
+
+        _ => return None,
+    };
+
+    Some(layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hci::Command;
+
+    #[test]
+    fn le_set_adv_params() {
+        let data = [
+            0x06, 0x20, 0x0f, 0xa0, 0x00, 0xa0, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00,
+        ];
+        let (_, cmd) = Command::parse(&data).unwrap();
+
+        assert_eq!(cmd.to_string(), "LE Set Advertising Parameters\
+            \n        Min advertising interval: 100.000 msec (0x00a0)\
+            \n        Max advertising interval: 100.000 msec (0x00a0)\
+            \n        Type: Connectable undirected - ADV_IND (0x00)\
+            \n        Own address type: Public (0x00)\
+            \n        Direct address type: Public (0x00)\
+            \n        Direct address: 00:00:00:00:00:00\
+            \n        Channel map: 37, 38, 39 (0x07)\
+            \n        Filter policy: Allow Scan Request from Any, Allow Connect Request from Any (0x00)");
+    }
+
+    #[test]
+    fn set_event_mask_bits() {
+        let data = [0x01, 0x0c, 0x08, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20];
+        let (_, cmd) = Command::parse(&data).unwrap();
+        let text = cmd.to_string();
+
+        assert!(text.contains("Mask: 0x2000000000000010"));
+        assert!(text.contains("\n          Disconnect Complete"));
+        assert!(text.contains("\n          LE Meta Event"));
+    }
+
+    #[test]
+    fn le_set_default_phy() {
+        let (_, cmd) = Command::parse(&[0x31, 0x20, 0x03, 0x00, 0x03, 0x02]).unwrap();
+
+        assert_eq!(cmd.to_string(), "LE Set Default PHY\
+            \n        All PHYs preference: 0x00\
+            \n        TX PHYs preference: LE 1M, LE 2M (0x03)\
+            \n        RX PHYs preference: LE 2M (0x02)");
+    }
+
+    #[test]
+    fn length_mismatch_falls_back_to_hex() {
+        let data = [0x06, 0x04, 0x02, 0x40, 0x00];
+        let (_, cmd) = Command::parse(&data).unwrap();
+
+        assert_eq!(cmd.to_string(), "Disconnect: [40, 00]");
+    }
+
+    #[test]
+    fn enhanced_setup_sync_conn() {
+        let mut data = vec![0x3d, 0x04, 0x3b, 0x06, 0x00, 0x40, 0x1f, 0x00, 0x00, 0x40, 0x1f, 0x00, 0x00];
+        data.extend([0x05, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x3c, 0x00, 0x3c, 0x00]);
+        data.extend([0x00, 0x7d, 0x00, 0x00, 0x00, 0x7d, 0x00, 0x00]);
+        data.extend([0x04, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x10, 0x00]);
+        data.extend([0x02, 0x02, 0x00, 0x00, 0x01, 0x01, 0x00, 0x00, 0x0d, 0x00, 0x80, 0x03, 0x02]);
+        let (_, cmd) = Command::parse(&data).unwrap();
+        let text = cmd.to_string();
+
+        assert!(text.starts_with("Enhanced Setup Synchronous Connection\
+            \n        Handle: 0x0006\
+            \n        Transmit bandwidth: 8000\
+            \n        Receive bandwidth: 8000\
+            \n        Transmit coding format: mSBC (0x05)"));
+        assert!(text.contains("\n        Input PCM data format: 2's complement (0x02)"));
+        assert!(text.ends_with("\n        Max latency: 13\
+            \n        Packet type: 0x0380\
+            \n        Retransmission effort: 0x02"));
+
+        let (_, cmd) = Command::parse(&[0x4b, 0x0c, 0x01, 0x01]).unwrap();
+        assert!(cmd.to_string().ends_with("\n        Anonymity mode: Enabled (0x01)"));
+    }
+}
@@ -0,0 +1,28 @@
+/// Bluetooth SIG company identifiers, sorted by identifier
+const COMPANIES: &[(u16, &str)] = include!("company_ids.rs");
+
+/// Name of the company with identifier `id`, as used in version information
+/// and manufacturer specific data
+pub(crate) fn company_str(id: u16) -> &'static str {
+    if id == 0xffff {
+        return "Internal use";
+    }
+    match COMPANIES.binary_search_by_key(&id, |(id, _)| *id) {
+        Ok(pos) => COMPANIES[pos].1,
+        Err(_)  => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{company_str, COMPANIES};
+
+    #[test]
+    fn lookup() {
+        assert!(COMPANIES.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(company_str(0x02ff), "Silicon Laboratories");
+        assert_eq!(company_str(0x004c), "Apple, Inc.");
+        assert_eq!(company_str(0x7fff), "Unknown");
+        assert_eq!(company_str(0xffff), "Internal use");
+    }
+}
@@ -0,0 +1,1035 @@
+use nom::{
+    bytes::complete::take,
+    multi::{count, length_data},
+    number::complete::{le_i8, le_u8, le_u16, le_u24, le_u64},
+    sequence::tuple,
+};
+use std::fmt;
+use crate::error::{self, Layer, PResult};
+use crate::monitor::BdAddr;
+use super::ad::AdData;
+use super::param::le_features;
+use super::{field, StatusCode, ConnInterval, Timeout, Usec, Phy, Rssi, addr_type_str, role_str};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AdvReport<'a> {
+    pub event_type: u8,
+    pub addr_type: u8,
+    pub addr: BdAddr,
+    pub data: &'a [u8],
+    pub rssi: i8,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DirectedAdvReport {
+    pub event_type: u8,
+    pub addr_type: u8,
+    pub addr: BdAddr,
+    pub direct_addr_type: u8,
+    pub direct_addr: BdAddr,
+    pub rssi: i8,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExtAdvReport<'a> {
+    pub event_type: u16,
+    pub addr_type: u8,
+    pub addr: BdAddr,
+    pub primary_phy: u8,
+    pub secondary_phy: u8,
+    pub sid: u8,
+    pub tx_power: i8,
+    pub rssi: i8,
+    pub interval: u16,
+    pub direct_addr_type: u8,
+    pub direct_addr: BdAddr,
+    pub data: &'a [u8],
+}
+
+/// Periodic Advertising with Responses timing, added by the v2 events
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PawrTiming {
+    pub num_subevents: u8,
+    /// Subevent interval and response slot delay in units of 1.25 ms
+    pub subevent_interval: u8,
+    pub response_slot_delay: u8,
+    /// Response slot spacing in units of 0.125 ms
+    pub response_slot_spacing: u8,
+}
+
+/// Sub-interval and SDU parameters of a CIS, added by LE CIS Established v2
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CisSdu {
+    /// Time between the start of consecutive subevents, in microseconds
+    pub sub_interval: u32,
+    pub max_sdu_c_to_p: u16,
+    pub max_sdu_p_to_c: u16,
+    pub sdu_interval_c_to_p: u32,
+    pub sdu_interval_p_to_c: u32,
+    pub framing: u8,
+}
+
+/// Decoded LE Meta event parameters.
+///
+/// Unlike commands, return parameters and other events, which are printed
+/// straight from a `param::Layout`, LE Meta events are decoded into typed
+/// fields because connection and ISO stream tracking consume them. Their
+/// values are printed with the same helpers as the layouts use.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LeEvent<'a> {
+    ConnComplete {
+        status: u8,
+        handle: u16,
+        role: u8,
+        peer_addr_type: u8,
+        peer_addr: BdAddr,
+        interval: u16,
+        latency: u16,
+        timeout: u16,
+        clock_accuracy: u8,
+    },
+    AdvReport(Vec<AdvReport<'a>>),
+    ConnUpdateComplete {
+        status: u8,
+        handle: u16,
+        interval: u16,
+        latency: u16,
+        timeout: u16,
+    },
+    RemoteFeaturesComplete {
+        status: u8,
+        handle: u16,
+        features: u64,
+    },
+    LtkRequest {
+        handle: u16,
+        rand: u64,
+        ediv: u16,
+    },
+    RemoteConnParamRequest {
+        handle: u16,
+        interval_min: u16,
+        interval_max: u16,
+        latency: u16,
+        timeout: u16,
+    },
+    DataLengthChange {
+        handle: u16,
+        max_tx_octets: u16,
+        max_tx_time: u16,
+        max_rx_octets: u16,
+        max_rx_time: u16,
+    },
+    P256KeyComplete {
+        status: u8,
+        key: &'a [u8],
+    },
+    DhKeyComplete {
+        status: u8,
+        key: &'a [u8],
+    },
+    EnhConnComplete {
+        status: u8,
+        handle: u16,
+        role: u8,
+        peer_addr_type: u8,
+        peer_addr: BdAddr,
+        local_rpa: BdAddr,
+        peer_rpa: BdAddr,
+        interval: u16,
+        latency: u16,
+        timeout: u16,
+        clock_accuracy: u8,
+        // Only present in version 2 of the event
+        adv_handle: Option<(u8, u16)>,
+    },
+    DirectedAdvReport(Vec<DirectedAdvReport>),
+    PhyUpdateComplete {
+        status: u8,
+        handle: u16,
+        tx_phy: u8,
+        rx_phy: u8,
+    },
+    ExtAdvReport(Vec<ExtAdvReport<'a>>),
+    PaSyncEstablished {
+        status: u8,
+        sync_handle: u16,
+        sid: u8,
+        addr_type: u8,
+        addr: BdAddr,
+        phy: u8,
+        interval: u16,
+        clock_accuracy: u8,
+        // Only present in version 2
+        pawr: Option<PawrTiming>,
+    },
+    PaReport {
+        sync_handle: u16,
+        tx_power: i8,
+        rssi: i8,
+        cte_type: u8,
+        // Event counter and subevent, only present in version 2
+        subevent: Option<(u16, u8)>,
+        data_status: u8,
+        data: &'a [u8],
+    },
+    PaSyncLost {
+        sync_handle: u16,
+    },
+    ScanTimeout,
+    AdvSetTerminated {
+        status: u8,
+        adv_handle: u8,
+        handle: u16,
+        num_events: u8,
+    },
+    ScanRequestReceived {
+        adv_handle: u8,
+        addr_type: u8,
+        addr: BdAddr,
+    },
+    ChannelSelection {
+        handle: u16,
+        algorithm: u8,
+    },
+    PastReceived {
+        status: u8,
+        handle: u16,
+        service_data: u16,
+        sync_handle: u16,
+        sid: u8,
+        addr_type: u8,
+        addr: BdAddr,
+        phy: u8,
+        interval: u16,
+        clock_accuracy: u8,
+        // Only present in version 2
+        pawr: Option<PawrTiming>,
+    },
+    CisEstablished {
+        status: u8,
+        handle: u16,
+        cig_sync_delay: u32,
+        cis_sync_delay: u32,
+        latency_c_to_p: u32,
+        latency_p_to_c: u32,
+        phy_c_to_p: u8,
+        phy_p_to_c: u8,
+        nse: u8,
+        bn_c_to_p: u8,
+        bn_p_to_c: u8,
+        ft_c_to_p: u8,
+        ft_p_to_c: u8,
+        max_pdu_c_to_p: u16,
+        max_pdu_p_to_c: u16,
+        iso_interval: u16,
+        // Only present in version 2
+        sdu: Option<CisSdu>,
+    },
+    CisRequest {
+        acl_handle: u16,
+        cis_handle: u16,
+        cig_id: u8,
+        cis_id: u8,
+    },
+    BigComplete {
+        status: u8,
+        big_handle: u8,
+        sync_delay: u32,
+        latency: u32,
+        phy: u8,
+        nse: u8,
+        bn: u8,
+        pto: u8,
+        irc: u8,
+        max_pdu: u16,
+        iso_interval: u16,
+        bis_handles: Vec<u16>,
+    },
+    BigTerminated {
+        big_handle: u8,
+        reason: u8,
+    },
+    BigSyncEstablished {
+        status: u8,
+        big_handle: u8,
+        latency: u32,
+        nse: u8,
+        bn: u8,
+        pto: u8,
+        irc: u8,
+        max_pdu: u16,
+        iso_interval: u16,
+        bis_handles: Vec<u16>,
+    },
+    BigSyncLost {
+        big_handle: u8,
+        reason: u8,
+    },
+    PeerScaComplete {
+        status: u8,
+        handle: u16,
+        sca: u8,
+    },
+    PathLossThreshold {
+        handle: u16,
+        path_loss: u8,
+        zone: u8,
+    },
+    TxPowerReporting {
+        status: u8,
+        handle: u16,
+        reason: u8,
+        phy: u8,
+        level: i8,
+        flags: u8,
+        delta: i8,
+    },
+    BigInfoReport {
+        sync_handle: u16,
+        num_bis: u8,
+        nse: u8,
+        iso_interval: u16,
+        bn: u8,
+        pto: u8,
+        irc: u8,
+        max_pdu: u16,
+        sdu_interval: u32,
+        max_sdu: u16,
+        phy: u8,
+        framing: u8,
+        encryption: u8,
+    },
+    SubrateChange {
+        status: u8,
+        handle: u16,
+        factor: u16,
+        latency: u16,
+        continuation: u16,
+        timeout: u16,
+    },
+    Unknown(&'a [u8]),
+}
+
+fn clock_accuracy_str(acc: u8) -> &'static str {
+    match acc {
+        0x00 => "500 ppm",
+        0x01 => "250 ppm",
+        0x02 => "150 ppm",
+        0x03 => "100 ppm",
+        0x04 => "75 ppm",
+        0x05 => "50 ppm",
+        0x06 => "30 ppm",
+        0x07 => "20 ppm",
+        _    => "Unknown",
+    }
+}
+
+fn adv_type_str(event_type: u8) -> &'static str {
+    match event_type {
+        0x00 => "Connectable undirected - ADV_IND",
+        0x01 => "Connectable directed - ADV_DIRECT_IND",
+        0x02 => "Scannable undirected - ADV_SCAN_IND",
+        0x03 => "Non connectable undirected - ADV_NONCONN_IND",
+        0x04 => "Scan response - SCAN_RSP",
+        _    => "Unknown",
+    }
+}
+
+struct ExtAdvType(u16);
+
+impl fmt::Display for ExtAdvType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const BITS: [&str; 5] = ["Connectable", "Scannable", "Directed", "Scan response", "Legacy"];
+
+        write!(f, "0x{:04x} (", self.0)?;
+        let mut sep = "";
+        for (bit, name) in BITS.iter().enumerate() {
+            if self.0 & (1 << bit) != 0 {
+                write!(f, "{}{}", sep, name)?;
+                sep = ", ";
+            }
+        }
+        match (self.0 >> 5) & 0b11 {
+            0 => write!(f, "{}Complete)", sep),
+            1 => write!(f, "{}Incomplete, more data)", sep),
+            2 => write!(f, "{}Incomplete, truncated)", sep),
+            _ => write!(f, "{}Reserved)", sep),
+        }
+    }
+}
+
+fn data_status_str(status: u8) -> &'static str {
+    match status {
+        0x00 => "Complete",
+        0x01 => "Incomplete, more data to come",
+        0x02 => "Incomplete, truncated",
+        0xff => "Failed to receive",
+        _    => "Reserved",
+    }
+}
+
+struct Addr(u8, BdAddr);
+
+impl fmt::Display for Addr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.1, addr_type_str(self.0))
+    }
+}
+
+impl PawrTiming {
+    /// Print as fields following those of the v1 event
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        field(f, "Number of subevents", self.num_subevents)?;
+        field(f, "Subevent interval", ConnInterval(self.subevent_interval as u16))?;
+        field(f, "Response slot delay", ConnInterval(self.response_slot_delay as u16))?;
+        field(f, "Response slot spacing", format!("{:.3} msec (0x{:02x})",
+            self.response_slot_spacing as f32 * 0.125, self.response_slot_spacing))
+    }
+}
+
+fn adv_report(i: &[u8]) -> PResult<'_, AdvReport<'_>> {
+    let (i, (event_type, addr_type, addr, data, rssi)) =
+        tuple((le_u8, le_u8, BdAddr::parse, length_data(le_u8), le_i8))(i)?;
+
+    Ok((i, AdvReport { event_type, addr_type, addr, data, rssi }))
+}
+
+fn directed_adv_report(i: &[u8]) -> PResult<'_, DirectedAdvReport> {
+    let (i, (event_type, addr_type, addr, direct_addr_type, direct_addr, rssi)) =
+        tuple((le_u8, le_u8, BdAddr::parse, le_u8, BdAddr::parse, le_i8))(i)?;
+
+    Ok((i, DirectedAdvReport { event_type, addr_type, addr, direct_addr_type, direct_addr, rssi }))
+}
+
+fn ext_adv_report(i: &[u8]) -> PResult<'_, ExtAdvReport<'_>> {
+    let (i, (event_type, addr_type, addr, primary_phy, secondary_phy, sid, tx_power, rssi)) =
+        tuple((le_u16, le_u8, BdAddr::parse, le_u8, le_u8, le_u8, le_i8, le_i8))(i)?;
+    let (i, (interval, direct_addr_type, direct_addr, data)) =
+        tuple((le_u16, le_u8, BdAddr::parse, length_data(le_u8)))(i)?;
+
+    Ok((i, ExtAdvReport {
+        event_type, addr_type, addr, primary_phy, secondary_phy, sid, tx_power, rssi,
+        interval, direct_addr_type, direct_addr, data,
+    }))
+}
+
+fn bis_handles(i: &[u8]) -> PResult<'_, Vec<u16>> {
+    let (i, num_bis) = le_u8(i)?;
+    count(le_u16, num_bis as usize)(i)
+}
+
+fn conn_complete(i: &[u8]) -> PResult<'_, LeEvent<'_>> {
+    let (i, (status, handle, role, peer_addr_type, peer_addr, interval, latency, timeout, clock_accuracy)) =
+        tuple((le_u8, le_u16, le_u8, le_u8, BdAddr::parse, le_u16, le_u16, le_u16, le_u8))(i)?;
+
+    Ok((i, LeEvent::ConnComplete {
+        status, handle, role, peer_addr_type, peer_addr, interval, latency, timeout, clock_accuracy,
+    }))
+}
+
+fn enh_conn_complete(v2: bool, i: &[u8]) -> PResult<'_, LeEvent<'_>> {
+    let (i, (status, handle, role, peer_addr_type, peer_addr, local_rpa, peer_rpa)) =
+        tuple((le_u8, le_u16, le_u8, le_u8, BdAddr::parse, BdAddr::parse, BdAddr::parse))(i)?;
+    let (i, (interval, latency, timeout, clock_accuracy)) = tuple((le_u16, le_u16, le_u16, le_u8))(i)?;
+    let (i, adv_handle) = if v2 {
+        let (i, v) = tuple((le_u8, le_u16))(i)?;
+        (i, Some(v))
+    } else {
+        (i, None)
+    };
+
+    Ok((i, LeEvent::EnhConnComplete {
+        status, handle, role, peer_addr_type, peer_addr, local_rpa, peer_rpa,
+        interval, latency, timeout, clock_accuracy, adv_handle,
+    }))
+}
+
+fn pawr_timing(v2: bool, i: &[u8]) -> PResult<'_, Option<PawrTiming>> {
+    if !v2 {
+        return Ok((i, None));
+    }
+    let (i, (num_subevents, subevent_interval, response_slot_delay, response_slot_spacing)) =
+        tuple((le_u8, le_u8, le_u8, le_u8))(i)?;
+
+    Ok((i, Some(PawrTiming { num_subevents, subevent_interval, response_slot_delay, response_slot_spacing })))
+}
+
+fn pa_sync_established(v2: bool, i: &[u8]) -> PResult<'_, LeEvent<'_>> {
+    let (i, (status, sync_handle, sid, addr_type, addr, phy, interval, clock_accuracy)) =
+        tuple((le_u8, le_u16, le_u8, le_u8, BdAddr::parse, le_u8, le_u16, le_u8))(i)?;
+    let (i, pawr) = pawr_timing(v2, i)?;
+
+    Ok((i, LeEvent::PaSyncEstablished { status, sync_handle, sid, addr_type, addr, phy, interval, clock_accuracy, pawr }))
+}
+
+fn pa_report(v2: bool, i: &[u8]) -> PResult<'_, LeEvent<'_>> {
+    let (i, (sync_handle, tx_power, rssi, cte_type)) = tuple((le_u16, le_i8, le_i8, le_u8))(i)?;
+    let (i, subevent) = if v2 {
+        let (i, v) = tuple((le_u16, le_u8))(i)?;
+        (i, Some(v))
+    } else {
+        (i, None)
+    };
+    let (i, (data_status, data)) = tuple((le_u8, length_data(le_u8)))(i)?;
+
+    Ok((i, LeEvent::PaReport { sync_handle, tx_power, rssi, cte_type, subevent, data_status, data }))
+}
+
+fn past_received(v2: bool, i: &[u8]) -> PResult<'_, LeEvent<'_>> {
+    let (i, (status, handle, service_data, sync_handle, sid, addr_type, addr, phy, interval, clock_accuracy)) =
+        tuple((le_u8, le_u16, le_u16, le_u16, le_u8, le_u8, BdAddr::parse, le_u8, le_u16, le_u8))(i)?;
+    let (i, pawr) = pawr_timing(v2, i)?;
+
+    Ok((i, LeEvent::PastReceived {
+        status, handle, service_data, sync_handle, sid, addr_type, addr, phy, interval, clock_accuracy, pawr,
+    }))
+}
+
+fn cis_established(v2: bool, i: &[u8]) -> PResult<'_, LeEvent<'_>> {
+    let (i, (status, handle, cig_sync_delay, cis_sync_delay, latency_c_to_p, latency_p_to_c)) =
+        tuple((le_u8, le_u16, le_u24, le_u24, le_u24, le_u24))(i)?;
+    let (i, (phy_c_to_p, phy_p_to_c, nse, bn_c_to_p, bn_p_to_c, ft_c_to_p, ft_p_to_c)) =
+        tuple((le_u8, le_u8, le_u8, le_u8, le_u8, le_u8, le_u8))(i)?;
+    let (i, (max_pdu_c_to_p, max_pdu_p_to_c, iso_interval)) = tuple((le_u16, le_u16, le_u16))(i)?;
+    let (i, sdu) = if v2 {
+        let (i, (sub_interval, max_sdu_c_to_p, max_sdu_p_to_c, sdu_interval_c_to_p, sdu_interval_p_to_c, framing)) =
+            tuple((le_u24, le_u16, le_u16, le_u24, le_u24, le_u8))(i)?;
+        let sdu = CisSdu {
+            sub_interval,
+            max_sdu_c_to_p: max_sdu_c_to_p & 0x0fff,
+            max_sdu_p_to_c: max_sdu_p_to_c & 0x0fff,
+            sdu_interval_c_to_p, sdu_interval_p_to_c, framing,
+        };
+        (i, Some(sdu))
+    } else {
+        (i, None)
+    };
+
+    Ok((i, LeEvent::CisEstablished {
+        status, handle, cig_sync_delay, cis_sync_delay, latency_c_to_p, latency_p_to_c,
+        phy_c_to_p, phy_p_to_c, nse, bn_c_to_p, bn_p_to_c, ft_c_to_p, ft_p_to_c,
+        max_pdu_c_to_p, max_pdu_p_to_c, iso_interval, sdu,
+    }))
+}
+
+fn big_complete(i: &[u8]) -> PResult<'_, LeEvent<'_>> {
+    let (i, (status, big_handle, sync_delay, latency, phy, nse, bn, pto, irc, max_pdu, iso_interval)) =
+        tuple((le_u8, le_u8, le_u24, le_u24, le_u8, le_u8, le_u8, le_u8, le_u8, le_u16, le_u16))(i)?;
+    let (i, bis_handles) = bis_handles(i)?;
+
+    Ok((i, LeEvent::BigComplete {
+        status, big_handle, sync_delay, latency, phy, nse, bn, pto, irc, max_pdu, iso_interval, bis_handles,
+    }))
+}
+
+fn big_sync_established(i: &[u8]) -> PResult<'_, LeEvent<'_>> {
+    let (i, (status, big_handle, latency, nse, bn, pto, irc, max_pdu, iso_interval)) =
+        tuple((le_u8, le_u8, le_u24, le_u8, le_u8, le_u8, le_u8, le_u16, le_u16))(i)?;
+    let (i, bis_handles) = bis_handles(i)?;
+
+    Ok((i, LeEvent::BigSyncEstablished {
+        status, big_handle, latency, nse, bn, pto, irc, max_pdu, iso_interval, bis_handles,
+    }))
+}
+
+fn big_info_report(i: &[u8]) -> PResult<'_, LeEvent<'_>> {
+    let (i, (sync_handle, num_bis, nse, iso_interval, bn, pto, irc, max_pdu)) =
+        tuple((le_u16, le_u8, le_u8, le_u16, le_u8, le_u8, le_u8, le_u16))(i)?;
+    let (i, (sdu_interval, max_sdu, phy, framing, encryption)) =
+        tuple((le_u24, le_u16, le_u8, le_u8, le_u8))(i)?;
+
+    Ok((i, LeEvent::BigInfoReport {
+        sync_handle, num_bis, nse, iso_interval, bn, pto, irc, max_pdu,
+        sdu_interval, max_sdu, phy, framing, encryption,
+    }))
+}
+
+fn le_event(sub: u8, i: &[u8]) -> PResult<'_, LeEvent<'_>> {
+    use LeEvent::*;
+
+    match sub {
+        0x01 => conn_complete(i),
+        0x02 => {
+            let (i, num) = le_u8(i)?;
+            let (i, reports) = count(adv_report, num as usize)(i)?;
+            Ok((i, AdvReport(reports)))
+        },
+        0x03 => {
+            let (i, (status, handle, interval, latency, timeout)) =
+                tuple((le_u8, le_u16, le_u16, le_u16, le_u16))(i)?;
+            Ok((i, ConnUpdateComplete { status, handle, interval, latency, timeout }))
+        },
+        0x04 => {
+            let (i, (status, handle, features)) = tuple((le_u8, le_u16, le_u64))(i)?;
+            Ok((i, RemoteFeaturesComplete { status, handle, features }))
+        },
+        0x05 => {
+            let (i, (handle, rand, ediv)) = tuple((le_u16, le_u64, le_u16))(i)?;
+            Ok((i, LtkRequest { handle, rand, ediv }))
+        },
+        0x06 => {
+            let (i, (handle, interval_min, interval_max, latency, timeout)) =
+                tuple((le_u16, le_u16, le_u16, le_u16, le_u16))(i)?;
+            Ok((i, RemoteConnParamRequest { handle, interval_min, interval_max, latency, timeout }))
+        },
+        0x07 => {
+            let (i, (handle, max_tx_octets, max_tx_time, max_rx_octets, max_rx_time)) =
+                tuple((le_u16, le_u16, le_u16, le_u16, le_u16))(i)?;
+            Ok((i, DataLengthChange { handle, max_tx_octets, max_tx_time, max_rx_octets, max_rx_time }))
+        },
+        0x08 => {
+            let (i, (status, key)) = tuple((le_u8, take(64usize)))(i)?;
+            Ok((i, P256KeyComplete { status, key }))
+        },
+        0x09 => {
+            let (i, (status, key)) = tuple((le_u8, take(32usize)))(i)?;
+            Ok((i, DhKeyComplete { status, key }))
+        },
+        0x0a => enh_conn_complete(false, i),
+        0x0b => {
+            let (i, num) = le_u8(i)?;
+            let (i, reports) = count(directed_adv_report, num as usize)(i)?;
+            Ok((i, DirectedAdvReport(reports)))
+        },
+        0x0c => {
+            let (i, (status, handle, tx_phy, rx_phy)) = tuple((le_u8, le_u16, le_u8, le_u8))(i)?;
+            Ok((i, PhyUpdateComplete { status, handle, tx_phy, rx_phy }))
+        },
+        0x0d => {
+            let (i, num) = le_u8(i)?;
+            let (i, reports) = count(ext_adv_report, num as usize)(i)?;
+            Ok((i, ExtAdvReport(reports)))
+        },
+        0x0e => pa_sync_established(false, i),
+        0x0f => pa_report(false, i),
+        0x10 => {
+            let (i, sync_handle) = le_u16(i)?;
+            Ok((i, PaSyncLost { sync_handle }))
+        },
+        0x11 => Ok((i, ScanTimeout)),
+        0x12 => {
+            let (i, (status, adv_handle, handle, num_events)) = tuple((le_u8, le_u8, le_u16, le_u8))(i)?;
+            Ok((i, AdvSetTerminated { status, adv_handle, handle, num_events }))
+        },
+        0x13 => {
+            let (i, (adv_handle, addr_type, addr)) = tuple((le_u8, le_u8, BdAddr::parse))(i)?;
+            Ok((i, ScanRequestReceived { adv_handle, addr_type, addr }))
+        },
+        0x14 => {
+            let (i, (handle, algorithm)) = tuple((le_u16, le_u8))(i)?;
+            Ok((i, ChannelSelection { handle, algorithm }))
+        },
+        0x18 => past_received(false, i),
+        0x19 => cis_established(false, i),
+        0x1a => {
+            let (i, (acl_handle, cis_handle, cig_id, cis_id)) = tuple((le_u16, le_u16, le_u8, le_u8))(i)?;
+            Ok((i, CisRequest { acl_handle, cis_handle, cig_id, cis_id }))
+        },
+        0x1b => big_complete(i),
+        0x1c => {
+            let (i, (big_handle, reason)) = tuple((le_u8, le_u8))(i)?;
+            Ok((i, BigTerminated { big_handle, reason }))
+        },
+        0x1d => big_sync_established(i),
+        0x1e => {
+            let (i, (big_handle, reason)) = tuple((le_u8, le_u8))(i)?;
+            Ok((i, BigSyncLost { big_handle, reason }))
+        },
+        0x1f => {
+            let (i, (status, handle, sca)) = tuple((le_u8, le_u16, le_u8))(i)?;
+            Ok((i, PeerScaComplete { status, handle, sca }))
+        },
+        0x20 => {
+            let (i, (handle, path_loss, zone)) = tuple((le_u16, le_u8, le_u8))(i)?;
+            Ok((i, PathLossThreshold { handle, path_loss, zone }))
+        },
+        0x21 => {
+            let (i, (status, handle, reason, phy, level, flags, delta)) =
+                tuple((le_u8, le_u16, le_u8, le_u8, le_i8, le_u8, le_i8))(i)?;
+            Ok((i, TxPowerReporting { status, handle, reason, phy, level, flags, delta }))
+        },
+        0x22 => big_info_report(i),
+        0x23 => {
+            let (i, (status, handle, factor, latency, continuation, timeout)) =
+                tuple((le_u8, le_u16, le_u16, le_u16, le_u16, le_u16))(i)?;
+            Ok((i, SubrateChange { status, handle, factor, latency, continuation, timeout }))
+        },
+        0x24 => pa_sync_established(true, i),
+        0x25 => pa_report(true, i),
+        0x26 => past_received(true, i),
+        0x29 => enh_conn_complete(true, i),
+        0x2a => cis_established(true, i),
+        _ => Ok((&i[i.len()..], Unknown(i))),
+    }
+}
+
+impl<'a> LeEvent<'a> {
+    /// Decode the parameters of LE Meta subevent `sub`
+    pub fn parse(sub: u8, param: &'a [u8]) -> error::Result<LeEvent<'a>> {
+        error::finish(Layer::Hci, param, le_event(sub, param)).map(|(_, ev)| ev)
+    }
+}
+
+impl fmt::Display for LeEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use LeEvent::*;
+
+        match self {
+            ConnComplete { status, handle, role, peer_addr_type, peer_addr, interval, latency, timeout, clock_accuracy } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Role", role_str(*role))?;
+                field(f, "Peer address", Addr(*peer_addr_type, *peer_addr))?;
+                field(f, "Connection interval", ConnInterval(*interval))?;
+                field(f, "Connection latency", latency)?;
+                field(f, "Supervision timeout", Timeout(*timeout))?;
+                field(f, "Central clock accuracy", clock_accuracy_str(*clock_accuracy))
+            },
+            AdvReport(reports) => {
+                field(f, "Num reports", reports.len())?;
+                for r in reports {
+                    field(f, "Event type", format!("{} (0x{:02x})", adv_type_str(r.event_type), r.event_type))?;
+                    field(f, "Address", Addr(r.addr_type, r.addr))?;
+                    field(f, "Data", AdData(r.data))?;
+                    field(f, "RSSI", Rssi(r.rssi))?;
+                }
+                Ok(())
+            },
+            ConnUpdateComplete { status, handle, interval, latency, timeout } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Connection interval", ConnInterval(*interval))?;
+                field(f, "Connection latency", latency)?;
+                field(f, "Supervision timeout", Timeout(*timeout))
+            },
+            RemoteFeaturesComplete { status, handle, features } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Features", le_features(*features))
+            },
+            LtkRequest { handle, rand, ediv } => {
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Random number", format!("0x{:016x}", rand))?;
+                field(f, "Encrypted diversifier", format!("0x{:04x}", ediv))
+            },
+            RemoteConnParamRequest { handle, interval_min, interval_max, latency, timeout } => {
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Min connection interval", ConnInterval(*interval_min))?;
+                field(f, "Max connection interval", ConnInterval(*interval_max))?;
+                field(f, "Connection latency", latency)?;
+                field(f, "Supervision timeout", Timeout(*timeout))
+            },
+            DataLengthChange { handle, max_tx_octets, max_tx_time, max_rx_octets, max_rx_time } => {
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Max TX octets", max_tx_octets)?;
+                field(f, "Max TX time", Usec(*max_tx_time as u32))?;
+                field(f, "Max RX octets", max_rx_octets)?;
+                field(f, "Max RX time", Usec(*max_rx_time as u32))
+            },
+            P256KeyComplete { status, key } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Local P-256 public key", format!("{:02x?}", key))
+            },
+            DhKeyComplete { status, key } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Diffie-Hellman key", format!("{:02x?}", key))
+            },
+            EnhConnComplete {
+                status, handle, role, peer_addr_type, peer_addr, local_rpa, peer_rpa,
+                interval, latency, timeout, clock_accuracy, adv_handle,
+            } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Role", role_str(*role))?;
+                field(f, "Peer address", Addr(*peer_addr_type, *peer_addr))?;
+                field(f, "Local resolvable private address", local_rpa)?;
+                field(f, "Peer resolvable private address", peer_rpa)?;
+                field(f, "Connection interval", ConnInterval(*interval))?;
+                field(f, "Connection latency", latency)?;
+                field(f, "Supervision timeout", Timeout(*timeout))?;
+                field(f, "Central clock accuracy", clock_accuracy_str(*clock_accuracy))?;
+                if let Some((adv_handle, sync_handle)) = adv_handle {
+                    field(f, "Advertising handle", format!("0x{:02x}", adv_handle))?;
+                    field(f, "Sync handle", format!("0x{:04x}", sync_handle))?;
+                }
+                Ok(())
+            },
+            DirectedAdvReport(reports) => {
+                field(f, "Num reports", reports.len())?;
+                for r in reports {
+                    field(f, "Event type", format!("{} (0x{:02x})", adv_type_str(r.event_type), r.event_type))?;
+                    field(f, "Address", Addr(r.addr_type, r.addr))?;
+                    field(f, "Direct address", Addr(r.direct_addr_type, r.direct_addr))?;
+                    field(f, "RSSI", Rssi(r.rssi))?;
+                }
+                Ok(())
+            },
+            PhyUpdateComplete { status, handle, tx_phy, rx_phy } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "TX PHY", Phy(*tx_phy))?;
+                field(f, "RX PHY", Phy(*rx_phy))
+            },
+            ExtAdvReport(reports) => {
+                field(f, "Num reports", reports.len())?;
+                for r in reports {
+                    field(f, "Event type", ExtAdvType(r.event_type))?;
+                    field(f, "Address", Addr(r.addr_type, r.addr))?;
+                    field(f, "Primary PHY", Phy(r.primary_phy))?;
+                    match r.secondary_phy {
+                        0 => field(f, "Secondary PHY", "No packets (0x00)")?,
+                        phy => field(f, "Secondary PHY", Phy(phy))?,
+                    }
+                    field(f, "SID", format!("0x{:02x}", r.sid))?;
+                    field(f, "TX power", match r.tx_power {
+                        127 => "not available".to_string(),
+                        p => format!("{} dBm", p),
+                    })?;
+                    field(f, "RSSI", Rssi(r.rssi))?;
+                    field(f, "Periodic advertising interval", ConnInterval(r.interval))?;
+                    if r.event_type & 0x04 != 0 {
+                        field(f, "Direct address", Addr(r.direct_addr_type, r.direct_addr))?;
+                    }
+                    field(f, "Data", AdData(r.data))?;
+                }
+                Ok(())
+            },
+            PaSyncEstablished { status, sync_handle, sid, addr_type, addr, phy, interval, clock_accuracy, pawr } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Sync handle", format!("0x{:04x}", sync_handle))?;
+                field(f, "SID", format!("0x{:02x}", sid))?;
+                field(f, "Advertiser address", Addr(*addr_type, *addr))?;
+                field(f, "Advertiser PHY", Phy(*phy))?;
+                field(f, "Periodic advertising interval", ConnInterval(*interval))?;
+                field(f, "Advertiser clock accuracy", clock_accuracy_str(*clock_accuracy))?;
+                pawr.map_or(Ok(()), |t| t.fmt(f))
+            },
+            PaReport { sync_handle, tx_power, rssi, cte_type, subevent, data_status, data } => {
+                field(f, "Sync handle", format!("0x{:04x}", sync_handle))?;
+                field(f, "TX power", format!("{} dBm", tx_power))?;
+                field(f, "RSSI", Rssi(*rssi))?;
+                field(f, "CTE type", format!("0x{:02x}", cte_type))?;
+                if let Some((counter, subevent)) = subevent {
+                    field(f, "Periodic event counter", counter)?;
+                    field(f, "Subevent", subevent)?;
+                }
+                field(f, "Data status", data_status_str(*data_status))?;
+                field(f, "Data", format!("{:02x?}", data))
+            },
+            PaSyncLost { sync_handle } => {
+                field(f, "Sync handle", format!("0x{:04x}", sync_handle))
+            },
+            ScanTimeout => Ok(()),
+            AdvSetTerminated { status, adv_handle, handle, num_events } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Advertising handle", format!("0x{:02x}", adv_handle))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Num completed extended advertising events", num_events)
+            },
+            ScanRequestReceived { adv_handle, addr_type, addr } => {
+                field(f, "Advertising handle", format!("0x{:02x}", adv_handle))?;
+                field(f, "Scanner address", Addr(*addr_type, *addr))
+            },
+            ChannelSelection { handle, algorithm } => {
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Algorithm", format!("#{} (0x{:02x})", algorithm + 1, algorithm))
+            },
+            PastReceived {
+                status, handle, service_data, sync_handle, sid, addr_type, addr, phy, interval, clock_accuracy, pawr,
+            } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Service data", format!("0x{:04x}", service_data))?;
+                field(f, "Sync handle", format!("0x{:04x}", sync_handle))?;
+                field(f, "SID", format!("0x{:02x}", sid))?;
+                field(f, "Advertiser address", Addr(*addr_type, *addr))?;
+                field(f, "Advertiser PHY", Phy(*phy))?;
+                field(f, "Periodic advertising interval", ConnInterval(*interval))?;
+                field(f, "Advertiser clock accuracy", clock_accuracy_str(*clock_accuracy))?;
+                pawr.map_or(Ok(()), |t| t.fmt(f))
+            },
+            CisEstablished {
+                status, handle, cig_sync_delay, cis_sync_delay, latency_c_to_p, latency_p_to_c,
+                phy_c_to_p, phy_p_to_c, nse, bn_c_to_p, bn_p_to_c, ft_c_to_p, ft_p_to_c,
+                max_pdu_c_to_p, max_pdu_p_to_c, iso_interval, sdu,
+            } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "CIG synchronization delay", Usec(*cig_sync_delay))?;
+                field(f, "CIS synchronization delay", Usec(*cis_sync_delay))?;
+                field(f, "Central to Peripheral latency", Usec(*latency_c_to_p))?;
+                field(f, "Peripheral to Central latency", Usec(*latency_p_to_c))?;
+                field(f, "Central to Peripheral PHY", Phy(*phy_c_to_p))?;
+                field(f, "Peripheral to Central PHY", Phy(*phy_p_to_c))?;
+                field(f, "Number of subevents", nse)?;
+                field(f, "Central to Peripheral burst number", bn_c_to_p)?;
+                field(f, "Peripheral to Central burst number", bn_p_to_c)?;
+                field(f, "Central to Peripheral flush timeout", ft_c_to_p)?;
+                field(f, "Peripheral to Central flush timeout", ft_p_to_c)?;
+                field(f, "Central to Peripheral MTU", max_pdu_c_to_p)?;
+                field(f, "Peripheral to Central MTU", max_pdu_p_to_c)?;
+                field(f, "ISO interval", ConnInterval(*iso_interval))?;
+                if let Some(sdu) = sdu {
+                    field(f, "Sub-interval", Usec(sdu.sub_interval))?;
+                    field(f, "Central to Peripheral maximum SDU", sdu.max_sdu_c_to_p)?;
+                    field(f, "Peripheral to Central maximum SDU", sdu.max_sdu_p_to_c)?;
+                    field(f, "Central to Peripheral SDU interval", Usec(sdu.sdu_interval_c_to_p))?;
+                    field(f, "Peripheral to Central SDU interval", Usec(sdu.sdu_interval_p_to_c))?;
+                    field(f, "Framing", if sdu.framing == 0 { "Unframed" } else { "Framed" })?;
+                }
+                Ok(())
+            },
+            CisRequest { acl_handle, cis_handle, cig_id, cis_id } => {
+                field(f, "ACL handle", format!("0x{:04x}", acl_handle))?;
+                field(f, "CIS handle", format!("0x{:04x}", cis_handle))?;
+                field(f, "CIG ID", format!("0x{:02x}", cig_id))?;
+                field(f, "CIS ID", format!("0x{:02x}", cis_id))
+            },
+            BigComplete {
+                status, big_handle, sync_delay, latency, phy, nse, bn, pto, irc, max_pdu, iso_interval, bis_handles,
+            } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "BIG handle", format!("0x{:02x}", big_handle))?;
+                field(f, "BIG synchronization delay", Usec(*sync_delay))?;
+                field(f, "Transport latency", Usec(*latency))?;
+                field(f, "PHY", Phy(*phy))?;
+                field(f, "Number of subevents", nse)?;
+                field(f, "Burst number", bn)?;
+                field(f, "Pre-transmission offset", pto)?;
+                field(f, "Immediate repetition count", irc)?;
+                field(f, "Maximum PDU size", max_pdu)?;
+                field(f, "ISO interval", ConnInterval(*iso_interval))?;
+                for h in bis_handles {
+                    field(f, "BIS handle", format!("0x{:04x}", h))?;
+                }
+                Ok(())
+            },
+            BigTerminated { big_handle, reason } | BigSyncLost { big_handle, reason } => {
+                field(f, "BIG handle", format!("0x{:02x}", big_handle))?;
+                field(f, "Reason", StatusCode(*reason))
+            },
+            BigSyncEstablished { status, big_handle, latency, nse, bn, pto, irc, max_pdu, iso_interval, bis_handles } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "BIG handle", format!("0x{:02x}", big_handle))?;
+                field(f, "Transport latency", Usec(*latency))?;
+                field(f, "Number of subevents", nse)?;
+                field(f, "Burst number", bn)?;
+                field(f, "Pre-transmission offset", pto)?;
+                field(f, "Immediate repetition count", irc)?;
+                field(f, "Maximum PDU size", max_pdu)?;
+                field(f, "ISO interval", ConnInterval(*iso_interval))?;
+                for h in bis_handles {
+                    field(f, "BIS handle", format!("0x{:04x}", h))?;
+                }
+                Ok(())
+            },
+            PeerScaComplete { status, handle, sca } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Sleep clock accuracy", clock_accuracy_str(*sca))
+            },
+            PathLossThreshold { handle, path_loss, zone } => {
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Current path loss", format!("{} dB", path_loss))?;
+                field(f, "Zone entered", match zone {
+                    0x00 => "Low",
+                    0x01 => "Middle",
+                    0x02 => "High",
+                    _    => "Unknown",
+                })
+            },
+            TxPowerReporting { status, handle, reason, phy, level, flags, delta } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Reason", match reason {
+                    0x00 => "Local transmit power changed",
+                    0x01 => "Remote transmit power changed",
+                    0x02 => "Read remote transmit power level completed",
+                    _    => "Unknown",
+                })?;
+                field(f, "PHY", Phy(*phy))?;
+                field(f, "TX power level", format!("{} dBm", level))?;
+                field(f, "TX power level flags", format!("0x{:02x}", flags))?;
+                field(f, "Delta", format!("{} dB", delta))
+            },
+            BigInfoReport {
+                sync_handle, num_bis, nse, iso_interval, bn, pto, irc, max_pdu,
+                sdu_interval, max_sdu, phy, framing, encryption,
+            } => {
+                field(f, "Sync handle", format!("0x{:04x}", sync_handle))?;
+                field(f, "Number of BIS", num_bis)?;
+                field(f, "Number of subevents", nse)?;
+                field(f, "ISO interval", ConnInterval(*iso_interval))?;
+                field(f, "Burst number", bn)?;
+                field(f, "Pre-transmission offset", pto)?;
+                field(f, "Immediate repetition count", irc)?;
+                field(f, "Maximum PDU size", max_pdu)?;
+                field(f, "SDU interval", Usec(*sdu_interval))?;
+                field(f, "Maximum SDU size", max_sdu)?;
+                field(f, "PHY", Phy(*phy))?;
+                field(f, "Framing", if *framing == 0 { "Unframed" } else { "Framed" })?;
+                field(f, "Encryption", if *encryption == 0 { "Unencrypted" } else { "Encrypted" })
+            },
+            SubrateChange { status, handle, factor, latency, continuation, timeout } => {
+                field(f, "Status", StatusCode(*status))?;
+                field(f, "Handle", format!("0x{:04x}", handle))?;
+                field(f, "Subrate factor", factor)?;
+                field(f, "Connection latency", latency)?;
+                field(f, "Continuation number", continuation)?;
+                field(f, "Supervision timeout", Timeout(*timeout))
+            },
+            Unknown(param) => write!(f, " param {:02x?}", param),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LeEvent;
+
+    #[test]
+    fn enhanced_conn_complete() {
+        let param = [
+            0x00, 0x40, 0x00, 0x01, 0x01, 0x66, 0x55, 0x44, 0x33, 0x22, 0xc1,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x18, 0x00, 0x00, 0x00, 0x2a, 0x00, 0x05,
+        ];
+
+        let ev = LeEvent::parse(0x0a, &param).unwrap();
+        let text = ev.to_string();
+
+        assert!(matches!(ev, LeEvent::EnhConnComplete { handle: 0x0040, role: 1, interval: 0x18, .. }));
+        assert!(text.contains("Peer address: c1:22:33:44:55:66 (Random)"));
+        assert!(text.contains("Connection interval: 30.00 msec (0x0018)"));
+        assert!(text.contains("Supervision timeout: 420 msec (0x002a)"));
+    }
+
+    #[test]
+    fn cis_established_v2() {
+        let v1 = [
+            0x00, 0x60, 0x00, 0x10, 0x27, 0x00, 0x10, 0x27, 0x00, 0x10, 0x27, 0x00, 0x10, 0x27, 0x00,
+            0x02, 0x02, 0x02, 0x01, 0x01, 0x01, 0x01, 0x78, 0x00, 0x78, 0x00, 0x08, 0x00,
+        ];
+        let mut v2 = v1.to_vec();
+        v2.extend_from_slice(&[0xc4, 0x09, 0x00, 0x64, 0x00, 0x64, 0x00, 0x10, 0x27, 0x00, 0x10, 0x27, 0x00, 0x00]);
+
+        assert!(matches!(LeEvent::parse(0x19, &v1).unwrap(), LeEvent::CisEstablished { sdu: None, .. }));
+        assert!(LeEvent::parse(0x2a, &v1).is_err());
+
+        let ev = LeEvent::parse(0x2a, &v2).unwrap();
+        assert!(matches!(ev, LeEvent::CisEstablished { handle: 0x0060, iso_interval: 8, sdu: Some(_), .. }));
+        assert!(ev.to_string().ends_with("\
+            \n        Sub-interval: 2500 us (0x09c4)\
+            \n        Central to Peripheral maximum SDU: 100\
+            \n        Peripheral to Central maximum SDU: 100\
+            \n        Central to Peripheral SDU interval: 10000 us (0x2710)\
+            \n        Peripheral to Central SDU interval: 10000 us (0x2710)\
+            \n        Framing: Unframed"));
+    }
+
+    #[test]
+    fn truncated_phy_update() {
+        assert!(LeEvent::parse(0x0c, &[0x00, 0x40]).is_err());
+    }
+}
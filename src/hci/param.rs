@@ -0,0 +1,311 @@
+use nom::{
+    bytes::complete::take,
+    combinator::{all_consuming, rest},
+    multi::length_data,
+    number::complete::{le_i8, le_i16, le_u8, le_u16, le_u24, le_u32, le_u64},
+};
+use std::fmt::Write;
+use crate::error::{self, Kind, PResult};
+use crate::monitor::BdAddr;
+use super::ad::AdData;
+use super::company::company_str;
+use super::ret::SUPPORTED_COMMANDS;
+use super::{hex, set_bits, StatusCode, ConnInterval, AdvInterval, Timeout, addr_type_str, role_str, event_str, le_sub_str};
+
+/// How one HCI parameter is encoded and printed
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Param {
+    U8,
+    U16,
+    U32,
+    I8,
+    Hex8,
+    Hex16,
+    Hex24,
+    Hex32,
+    Hex64,
+    Status,
+    Addr,
+    AddrType,
+    Role,
+    Phy,
+    ConnInterval,
+    AdvInterval,
+    AdvInterval24,
+    Timeout,
+    Seconds,
+    Usec,
+    Usec24,
+    TxPower,
+    Db10,
+    RfChannel,
+    Dbm,
+    Company,
+    LeFeatures,
+    // 64 byte Supported_Commands bitmask
+    Commands,
+    // Fixed size byte array printed as a hex string, e.g. keys
+    Key(usize),
+    Bytes(usize),
+    // Length prefixed data
+    Data,
+    // Legacy advertising data: length followed by 31 bytes
+    AdvData,
+    // Extended advertising data: length followed by that many bytes
+    ExtAdvData,
+    // Zero terminated UTF-8 name in a 248 byte field
+    Name,
+    Rest,
+    EventMask,
+    LeEventMask,
+    // u8 value naming the entries of the table by index
+    Named(&'static [&'static str]),
+    // u8 or u16 bitmask naming the bits of the table
+    Flags8(&'static [&'static str]),
+    Flags16(&'static [&'static str]),
+    // u8 count followed by that many entries
+    List(Layout),
+    // u8 PHY bitmask followed by one entry per PHY set
+    PerPhy(Layout),
+}
+
+pub(crate) type Layout = &'static [(&'static str, Param)];
+
+/// Decoded parameters as name and printed value, in wire order
+pub(crate) type Fields = Vec<(&'static str, String)>;
+
+pub(crate) const ENABLE: &[&str] = &["Disabled", "Enabled"];
+
+pub(crate) const OWN_ADDR_TYPE: &[&str] = &[
+    "Public", "Random", "Resolvable or public", "Resolvable or random",
+];
+
+pub(crate) const PHYS: &[&str] = &["LE 1M", "LE 2M", "LE Coded"];
+
+pub(crate) const LINK_TYPES: &[&str] = &["SCO", "Reserved", "eSCO"];
+
+pub(crate) const AIR_MODES: &[&str] = &["u-law log", "A-law log", "CVSD", "Transparent Data"];
+
+pub(crate) const VERSIONS: &[&str] = &[
+    "Bluetooth 1.0b", "Bluetooth 1.1", "Bluetooth 1.2", "Bluetooth 2.0", "Bluetooth 2.1",
+    "Bluetooth 3.0", "Bluetooth 4.0", "Bluetooth 4.1", "Bluetooth 4.2", "Bluetooth 5.0",
+    "Bluetooth 5.1", "Bluetooth 5.2", "Bluetooth 5.3", "Bluetooth 5.4", "Bluetooth 6.0",
+];
+
+const LE_FEATURES: &[&str] = &[
+    "LE Encryption",
+    "Connection Parameters Request Procedure",
+    "Extended Reject Indication",
+    "Peripheral-initiated Features Exchange",
+    "LE Ping",
+    "LE Data Packet Length Extension",
+    "LL Privacy",
+    "Extended Scanner Filter Policies",
+    "LE 2M PHY",
+    "Stable Modulation Index - Transmitter",
+    "Stable Modulation Index - Receiver",
+    "LE Coded PHY",
+    "LE Extended Advertising",
+    "LE Periodic Advertising",
+    "Channel Selection Algorithm #2",
+    "LE Power Class 1",
+    "Minimum Number of Used Channels Procedure",
+    "Connection CTE Request",
+    "Connection CTE Response",
+    "Connectionless CTE Transmitter",
+    "Connectionless CTE Receiver",
+    "Antenna Switching During CTE Transmission (AoD)",
+    "Antenna Switching During CTE Reception (AoA)",
+    "Receiving Constant Tone Extensions",
+    "Periodic Advertising Sync Transfer - Sender",
+    "Periodic Advertising Sync Transfer - Recipient",
+    "Sleep Clock Accuracy Updates",
+    "Remote Public Key Validation",
+    "Connected Isochronous Stream - Central",
+    "Connected Isochronous Stream - Peripheral",
+    "Isochronous Broadcaster",
+    "Synchronized Receiver",
+    "Connected Isochronous Stream (Host Support)",
+    "LE Power Control Request",
+    "LE Power Control Request",
+    "LE Path Loss Monitoring",
+    "Periodic Advertising ADI support",
+    "Connection Subrating",
+    "Connection Subrating (Host Support)",
+    "Channel Classification",
+    "Advertising Coding Selection",
+    "Advertising Coding Selection (Host Support)",
+    "Decision-Based Advertising Filtering",
+    "Periodic Advertising with Responses - Advertiser",
+    "Periodic Advertising with Responses - Scanner",
+    "Unsegmented Framed Mode",
+    "Channel Sounding",
+    "Channel Sounding (Host Support)",
+];
+
+/// LE feature bits, one line per supported feature
+pub(crate) fn le_features(val: u64) -> String {
+    mask(val, |bit| LE_FEATURES.get(bit as usize).copied())
+}
+
+fn named(val: u8, names: &[&str]) -> String {
+    format!("{} (0x{:02x})", names.get(val as usize).unwrap_or(&"Reserved"), val)
+}
+
+fn flags(val: u16, names: &[&'static str], width: usize) -> String {
+    let set = set_bits(val, names);
+
+    match set.is_empty() {
+        true  => format!("0x{:0w$x}", val, w = width),
+        false => format!("{} (0x{:0w$x})", set.join(", "), val, w = width),
+    }
+}
+
+/// A 64 bit event mask with one line per set bit
+fn mask(val: u64, name: impl Fn(u8) -> Option<&'static str>) -> String {
+    let mut s = format!("0x{:016x}", val);
+
+    for bit in (0..64).filter(|bit| val & (1 << bit) != 0) {
+        let _ = match name(bit) {
+            Some(name) => write!(s, "\n          {}", name),
+            None => write!(s, "\n          Reserved (bit {})", bit),
+        };
+    }
+    s
+}
+
+/// Supported_Commands octets, one line per supported command
+fn commands(data: &[u8]) -> String {
+    let mut lines = String::new();
+    let mut num = 0;
+
+    for (octet, byte) in data.iter().enumerate() {
+        for bit in (0..8).filter(|bit| byte & (1 << bit) != 0) {
+            let _ = match SUPPORTED_COMMANDS.get(octet).map(|names| names[bit]) {
+                Some("") | None => write!(lines, "\n          Octet {} bit {}", octet, bit),
+                Some(name) => write!(lines, "\n          {}", name),
+            };
+            num += 1;
+        }
+    }
+
+    format!("{} entries{}", num, lines)
+}
+
+fn event_bit(bit: u8) -> Option<&'static str> {
+    // Bit n enables event code n + 1, except for events that can't be masked
+    match bit {
+        13 | 14 | 18 => None,
+        bit => event_str(bit + 1),
+    }
+}
+
+fn le_event_bit(bit: u8) -> Option<&'static str> {
+    match le_sub_str(bit + 1) {
+        "LE <Unknown>" => None,
+        name => Some(name),
+    }
+}
+
+fn value(param: Param, i: &[u8]) -> PResult<'_, String> {
+    use Param::*;
+
+    match param {
+        U8 => le_u8(i).map(|(i, v)| (i, v.to_string())),
+        U16 => le_u16(i).map(|(i, v)| (i, v.to_string())),
+        U32 => le_u32(i).map(|(i, v)| (i, v.to_string())),
+        I8 => le_i8(i).map(|(i, v)| (i, v.to_string())),
+        Hex8 => le_u8(i).map(|(i, v)| (i, format!("0x{:02x}", v))),
+        Hex16 => le_u16(i).map(|(i, v)| (i, format!("0x{:04x}", v))),
+        Hex24 => le_u24(i).map(|(i, v)| (i, format!("0x{:06x}", v))),
+        Hex32 => le_u32(i).map(|(i, v)| (i, format!("0x{:08x}", v))),
+        Hex64 => le_u64(i).map(|(i, v)| (i, format!("0x{:016x}", v))),
+        Status => le_u8(i).map(|(i, v)| (i, StatusCode(v).to_string())),
+        Addr => BdAddr::parse(i).map(|(i, v)| (i, v.to_string())),
+        AddrType => le_u8(i).map(|(i, v)| (i, format!("{} (0x{:02x})", addr_type_str(v), v))),
+        Role => le_u8(i).map(|(i, v)| (i, format!("{} (0x{:02x})", role_str(v), v))),
+        Phy => le_u8(i).map(|(i, v)| (i, super::Phy(v).to_string())),
+        ConnInterval => le_u16(i).map(|(i, v)| (i, self::ConnInterval(v).to_string())),
+        AdvInterval => le_u16(i).map(|(i, v)| (i, self::AdvInterval(v as u32).to_string())),
+        AdvInterval24 => le_u24(i).map(|(i, v)| (i, self::AdvInterval(v).to_string())),
+        Timeout => le_u16(i).map(|(i, v)| (i, self::Timeout(v).to_string())),
+        Seconds => le_u16(i).map(|(i, v)| (i, format!("{} s (0x{:04x})", v, v))),
+        Usec => le_u16(i).map(|(i, v)| (i, super::Usec(v as u32).to_string())),
+        Usec24 => le_u24(i).map(|(i, v)| (i, super::Usec(v).to_string())),
+        TxPower => le_i8(i).map(|(i, v)| match v {
+            127 => (i, "No preference (0x7f)".to_string()),
+            v => (i, format!("{} dBm (0x{:02x})", v, v as u8)),
+        }),
+        Db10 => le_i16(i).map(|(i, v)| (i, format!("{:.1} dB (0x{:04x})", v as f32 / 10.0, v as u16))),
+        RfChannel => le_u8(i).map(|(i, v)| (i, format!("{} MHz (0x{:02x})", 2402 + 2 * v as u32, v))),
+        Dbm => le_i8(i).map(|(i, v)| (i, format!("{} dBm (0x{:02x})", v, v as u8))),
+        Company => le_u16(i).map(|(i, v)| (i, format!("{} (0x{:04x})", company_str(v), v))),
+        LeFeatures => le_u64(i).map(|(i, v)| (i, le_features(v))),
+        Commands => take(64usize)(i).map(|(i, v)| (i, commands(v))),
+        Key(n) => take(n)(i).map(|(i, v)| (i, hex(v))),
+        Bytes(n) => take(n)(i).map(|(i, v): (_, &[u8])| (i, format!("{:02x?}", v))),
+        Data => length_data(le_u8)(i).map(|(i, v)| (i, format!("{:02x?}", v))),
+        AdvData => {
+            let (i, len) = le_u8(i)?;
+            let (i, data) = take(31usize)(i)?;
+            match data.get(..len as usize) {
+                Some(data) => Ok((i, AdData(data).to_string())),
+                None => error::fail(i, Kind::BadLength),
+            }
+        },
+        ExtAdvData => length_data(le_u8)(i).map(|(i, v)| (i, AdData(v).to_string())),
+        Name => take(248usize)(i).map(|(i, v): (_, &[u8])| {
+            let end = v.iter().position(|b| *b == 0).unwrap_or(v.len());
+            (i, String::from_utf8_lossy(&v[..end]).into_owned())
+        }),
+        Rest => rest(i).map(|(i, v): (_, &[u8])| (i, format!("{:02x?}", v))),
+        EventMask => le_u64(i).map(|(i, v)| (i, mask(v, event_bit))),
+        LeEventMask => le_u64(i).map(|(i, v)| (i, mask(v, le_event_bit))),
+        Named(names) => le_u8(i).map(|(i, v)| (i, named(v, names))),
+        Flags8(names) => le_u8(i).map(|(i, v)| (i, flags(v as u16, names, 2))),
+        Flags16(names) => le_u16(i).map(|(i, v)| (i, flags(v, names, 4))),
+        List(_) | PerPhy(_) => unreachable!("nested layouts are expanded by decode"),
+    }
+}
+
+fn decode<'a>(layout: Layout, mut i: &'a [u8], out: &mut Fields) -> PResult<'a, ()> {
+    for &(name, param) in layout {
+        match param {
+            Param::List(entry) => {
+                let (rem, num) = le_u8(i)?;
+                out.push((name, num.to_string()));
+                i = rem;
+                for _ in 0..num {
+                    (i, _) = decode(entry, i, out)?;
+                }
+            },
+            Param::PerPhy(entry) => {
+                let (rem, phys) = le_u8(i)?;
+                out.push((name, flags(phys as u16, PHYS, 2)));
+                i = rem;
+                for (bit, phy) in PHYS.iter().enumerate() {
+                    if phys & (1 << bit) != 0 {
+                        out.push(("Entry", phy.to_string()));
+                        (i, _) = decode(entry, i, out)?;
+                    }
+                }
+            },
+            param => {
+                let (rem, val) = value(param, i)?;
+                out.push((name, val));
+                i = rem;
+            },
+        }
+    }
+
+    Ok((i, ()))
+}
+
+/// Decode `data` according to `layout`. All of the data must be consumed,
+/// anything else is treated as a mismatch and yields `None`.
+pub(crate) fn fields(layout: Layout, data: &[u8]) -> Option<Fields> {
+    let mut out = Vec::new();
+
+    all_consuming(|i| decode(layout, i, &mut out))(data).ok()?;
+    Some(out)
+}
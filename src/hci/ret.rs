@@ -0,0 +1,359 @@
+use super::Op;
+use super::cmd::{SCAN_ENABLE, LINK_POLICY, INQUIRY_MODE};
+use super::param::{self, Fields, Layout, Param::*, ENABLE, VERSIONS};
+
+/// Command names of the Supported_Commands bitmask, indexed by octet and bit
+pub(super) const SUPPORTED_COMMANDS: &[[&str; 8]] = &[
+    ["Inquiry", "Inquiry Cancel", "Periodic Inquiry Mode", "Exit Periodic Inquiry Mode",
+     "Create Connection", "Disconnect", "Add SCO Connection", "Create Connection Cancel"],
+    ["Accept Connection Request", "Reject Connection Request", "Link Key Request Reply",
+     "Link Key Request Negative Reply", "PIN Code Request Reply", "PIN Code Request Negative Reply",
+     "Change Connection Packet Type", "Authentication Requested"],
+    ["Set Connection Encryption", "Change Connection Link Key", "Link Key Selection",
+     "Remote Name Request", "Remote Name Request Cancel", "Read Remote Supported Features",
+     "Read Remote Extended Features", "Read Remote Version Information"],
+    ["Read Clock Offset", "Read LMP Handle", "", "", "", "", "", ""],
+    ["", "Hold Mode", "Sniff Mode", "Exit Sniff Mode", "Park State", "Exit Park State",
+     "QoS Setup", "Role Discovery"],
+    ["Switch Role", "Read Link Policy Settings", "Write Link Policy Settings",
+     "Read Default Link Policy Settings", "Write Default Link Policy Settings",
+     "Flow Specification", "Set Event Mask", "Reset"],
+    ["Set Event Filter", "Flush", "Read PIN Type", "Write PIN Type", "Create New Unit Key",
+     "Read Stored Link Key", "Write Stored Link Key", "Delete Stored Link Key"],
+    ["Write Local Name", "Read Local Name", "Read Connection Accept Timeout",
+     "Write Connection Accept Timeout", "Read Page Timeout", "Write Page Timeout",
+     "Read Scan Enable", "Write Scan Enable"],
+    ["Read Page Scan Activity", "Write Page Scan Activity", "Read Inquiry Scan Activity",
+     "Write Inquiry Scan Activity", "Read Authentication Enable", "Write Authentication Enable",
+     "Read Encryption Mode", "Write Encryption Mode"],
+    ["Read Class Of Device", "Write Class Of Device", "Read Voice Setting", "Write Voice Setting",
+     "Read Automatic Flush Timeout", "Write Automatic Flush Timeout",
+     "Read Num Broadcast Retransmissions", "Write Num Broadcast Retransmissions"],
+    ["Read Hold Mode Activity", "Write Hold Mode Activity", "Read Transmit Power Level",
+     "Read Synchronous Flow Control Enable", "Write Synchronous Flow Control Enable",
+     "Set Controller To Host Flow Control", "Host Buffer Size", "Host Number Of Completed Packets"],
+    ["Read Link Supervision Timeout", "Write Link Supervision Timeout",
+     "Read Number of Supported IAC", "Read Current IAC LAP", "Write Current IAC LAP",
+     "Read Page Scan Mode Period", "Write Page Scan Mode Period", "Read Page Scan Mode"],
+    ["Write Page Scan Mode", "Set AFH Host Channel Classification", "", "",
+     "Read Inquiry Scan Type", "Write Inquiry Scan Type", "Read Inquiry Mode", "Write Inquiry Mode"],
+    ["Read Page Scan Type", "Write Page Scan Type", "Read AFH Channel Assessment Mode",
+     "Write AFH Channel Assessment Mode", "", "", "", ""],
+    ["", "", "", "Read Local Version Information", "", "Read Local Supported Features",
+     "Read Local Extended Features", "Read Buffer Size"],
+    ["Read Country Code", "Read BD ADDR", "Read Failed Contact Counter",
+     "Reset Failed Contact Counter", "Read Link Quality", "Read RSSI", "Read AFH Channel Map",
+     "Read Clock"],
+    ["Read Loopback Mode", "Write Loopback Mode", "Enable Device Under Test Mode",
+     "Setup Synchronous Connection", "Accept Synchronous Connection Request",
+     "Reject Synchronous Connection Request", "", ""],
+    ["Read Extended Inquiry Response", "Write Extended Inquiry Response", "Refresh Encryption Key",
+     "", "Sniff Subrating", "Read Simple Pairing Mode", "Write Simple Pairing Mode",
+     "Read Local OOB Data"],
+    ["Read Inquiry Response Transmit Power Level", "Write Inquiry Transmit Power Level",
+     "Read Default Erroneous Data Reporting", "Write Default Erroneous Data Reporting", "", "", "",
+     "IO Capability Request Reply"],
+    ["User Confirmation Request Reply", "User Confirmation Request Negative Reply",
+     "User Passkey Request Reply", "User Passkey Request Negative Reply",
+     "Remote OOB Data Request Reply", "Write Simple Pairing Debug Mode", "Enhanced Flush",
+     "Remote OOB Data Request Negative Reply"],
+    ["", "", "Send Keypress Notification", "IO Capability Request Negative Reply",
+     "Read Encryption Key Size", "", "", ""],
+    ["Create Physical Link", "Accept Physical Link", "Disconnect Physical Link",
+     "Create Logical Link", "Accept Logical Link", "Disconnect Logical Link",
+     "Logical Link Cancel", "Flow Spec Modify"],
+    ["Read Logical Link Accept Timeout", "Write Logical Link Accept Timeout",
+     "Set Event Mask Page 2", "Read Location Data", "Write Location Data", "Read Local AMP Info",
+     "Read Local AMP ASSOC", "Write Remote AMP ASSOC"],
+    ["Read Flow Control Mode", "Write Flow Control Mode", "Read Data Block Size", "", "",
+     "Enable AMP Receiver Reports", "AMP Test End", "AMP Test"],
+    ["Read Enhanced Transmit Power Level", "", "Read Best Effort Flush Timeout",
+     "Write Best Effort Flush Timeout", "Short Range Mode", "Read LE Host Support",
+     "Write LE Host Support", ""],
+    ["LE Set Event Mask", "LE Read Buffer Size [v1]", "LE Read Local Supported Features Page 0", "",
+     "LE Set Random Address", "LE Set Advertising Parameters",
+     "LE Read Advertising Physical Channel Tx Power", "LE Set Advertising Data"],
+    ["LE Set Scan Response Data", "LE Set Advertising Enable", "LE Set Scan Parameters",
+     "LE Set Scan Enable", "LE Create Connection", "LE Create Connection Cancel",
+     "LE Read Filter Accept List Size", "LE Clear Filter Accept List"],
+    ["LE Add Device To Filter Accept List", "LE Remove Device From Filter Accept List",
+     "LE Connection Update", "LE Set Host Channel Classification", "LE Read Channel Map",
+     "LE Read Remote Features Page 0", "LE Encrypt", "LE Rand"],
+    ["LE Enable Encryption", "LE Long Term Key Request Reply",
+     "LE Long Term Key Request Negative Reply", "LE Read Supported States",
+     "LE Receiver Test [v1]", "LE Transmitter Test [v1]", "LE Test End", ""],
+    ["", "", "", "Enhanced Setup Synchronous Connection",
+     "Enhanced Accept Synchronous Connection Request", "Read Local Supported Codecs",
+     "Set MWS Channel Parameters", "Set External Frame Configuration"],
+    ["Set MWS Signaling", "Set MWS Transport Layer", "Set MWS Scan Frequency Table",
+     "Get MWS Transport Layer Configuration", "Set MWS PATTERN Configuration",
+     "Set Triggered Clock Capture", "Truncated Page", "Truncated Page Cancel"],
+    ["Set Connectionless Peripheral Broadcast", "Set Connectionless Peripheral Broadcast Receive",
+     "Start Synchronization Train", "Receive Synchronization Train", "Set Reserved LT_ADDR",
+     "Delete Reserved LT_ADDR", "Set Connectionless Peripheral Broadcast Data",
+     "Read Synchronization Train Parameters"],
+    ["Write Synchronization Train Parameters", "Remote OOB Extended Data Request Reply",
+     "Read Secure Connections Host Support", "Write Secure Connections Host Support",
+     "Read Authenticated Payload Timeout", "Write Authenticated Payload Timeout",
+     "Read Local OOB Extended Data", "Write Secure Connections Test Mode"],
+    ["Read Extended Page Timeout", "Write Extended Page Timeout", "Read Extended Inquiry Length",
+     "Write Extended Inquiry Length", "LE Remote Connection Parameter Request Reply",
+     "LE Remote Connection Parameter Request Negative Reply", "LE Set Data Length",
+     "LE Read Suggested Default Data Length"],
+    ["LE Write Suggested Default Data Length", "LE Read Local P-256 Public Key",
+     "LE Generate DHKey [v1]", "LE Add Device To Resolving List",
+     "LE Remove Device From Resolving List", "LE Clear Resolving List",
+     "LE Read Resolving List Size", "LE Read Peer Resolvable Address"],
+    ["LE Read Local Resolvable Address", "LE Set Address Resolution Enable",
+     "LE Set Resolvable Private Address Timeout", "LE Read Maximum Data Length", "LE Read PHY",
+     "LE Set Default PHY", "LE Set PHY", "LE Receiver Test [v2]"],
+    ["LE Transmitter Test [v2]", "LE Set Advertising Set Random Address",
+     "LE Set Extended Advertising Parameters [v1]", "LE Set Extended Advertising Data",
+     "LE Set Extended Scan Response Data", "LE Set Extended Advertising Enable",
+     "LE Read Maximum Advertising Data Length", "LE Read Number of Supported Advertising Sets"],
+    ["LE Remove Advertising Set", "LE Clear Advertising Sets",
+     "LE Set Periodic Advertising Parameters [v1]", "LE Set Periodic Advertising Data",
+     "LE Set Periodic Advertising Enable", "LE Set Extended Scan Parameters",
+     "LE Set Extended Scan Enable", "LE Extended Create Connection [v1]"],
+    ["LE Periodic Advertising Create Sync", "LE Periodic Advertising Create Sync Cancel",
+     "LE Periodic Advertising Terminate Sync", "LE Add Device To Periodic Advertiser List",
+     "LE Remove Device From Periodic Advertiser List", "LE Clear Periodic Advertiser List",
+     "LE Read Periodic Advertiser List Size", "LE Read Transmit Power"],
+    ["LE Read RF Path Compensation", "LE Write RF Path Compensation", "LE Set Privacy Mode",
+     "LE Receiver Test [v3]", "LE Transmitter Test [v3]",
+     "LE Set Connectionless CTE Transmit Parameters", "LE Set Connectionless CTE Transmit Enable",
+     "LE Set Connectionless IQ Sampling Enable"],
+    ["LE Set Connection CTE Receive Parameters", "LE Set Connection CTE Transmit Parameters",
+     "LE Connection CTE Request Enable", "LE Connection CTE Response Enable",
+     "LE Read Antenna Information", "LE Set Periodic Advertising Receive Enable",
+     "LE Periodic Advertising Sync Transfer", "LE Periodic Advertising Set Info Transfer"],
+    ["LE Set Periodic Advertising Sync Transfer Parameters",
+     "LE Set Default Periodic Advertising Sync Transfer Parameters", "LE Generate DHKey [v2]",
+     "Read Local Simple Pairing Options", "LE Modify Sleep Clock Accuracy",
+     "LE Read Buffer Size [v2]", "LE Read ISO TX Sync", "LE Set CIG Parameters"],
+    ["LE Set CIG Parameters Test", "LE Create CIS", "LE Remove CIG", "LE Accept CIS Request",
+     "LE Reject CIS Request", "LE Create BIG", "LE Create BIG Test", "LE Terminate BIG"],
+    ["LE BIG Create Sync", "LE BIG Terminate Sync", "LE Request Peer SCA",
+     "LE Setup ISO Data Path", "LE Remove ISO Data Path", "LE ISO Transmit Test",
+     "LE ISO Receive Test", "LE ISO Read Test Counters"],
+    ["LE ISO Test End", "LE Set Host Feature", "LE Read ISO Link Quality",
+     "LE Enhanced Read Transmit Power Level", "LE Read Remote Transmit Power Level",
+     "LE Set Path Loss Reporting Parameters", "LE Set Path Loss Reporting Enable",
+     "LE Set Transmit Power Reporting Enable"],
+    ["LE Transmitter Test [v4]", "Set Ecosystem Base Interval", "Read Local Supported Codecs [v2]",
+     "Read Local Supported Codec Capabilities", "Read Local Supported Controller Delay",
+     "Configure Data Path", "LE Set Data Related Address Changes", "Set Min Encryption Key Size"],
+    ["LE Set Default Subrate", "LE Subrate Request", "LE Set Extended Advertising Parameters [v2]",
+     "LE Set Decision Data", "LE Set Decision Instructions",
+     "LE Set Periodic Advertising Subevent Data", "LE Set Periodic Advertising Response Data",
+     "LE Set Periodic Sync Subevent"],
+    ["LE Extended Create Connection [v2]", "LE Set Periodic Advertising Parameters [v2]",
+     "", "", "", "", "", ""],
+];
+
+const STATUS: Layout = &[("Status", Status)];
+
+const STATUS_HANDLE: Layout = &[("Status", Status), ("Handle", Hex16)];
+
+const STATUS_ADDR: Layout = &[("Status", Status), ("Address", Addr)];
+
+const STATUS_SIZE: Layout = &[("Status", Status), ("Size", U8)];
+
+/// Return parameter layout of the command `op`, if it returns more than a status
+fn layout(op: Op) -> Option<Layout> {
+    use Op::*;
+
+    let layout: Layout = match op {
+        // Link Control commands
+        CreateConnCancel | LinkKeyReqReply | LinkKeyReqNegReply | PinCodeReqReply |
+        PinCodeReqNegReply | RemoteNameReqCancel | IoCapReqReply | UserConfirmReqReply |
+        UserConfirmReqNegReply | UserPasskeyReqReply | UserPasskeyReqNegReply |
+        RemoteOobDataReqReply | RemoteOobDataReqNegReply | IoCapReqNegReply |
+        TruncatedPageCancel => STATUS_ADDR,
+        ReadLmpHandle => &[("Status", Status), ("Handle", Hex16), ("LMP handle", Hex8), ("Reserved", Hex32)],
+        LogicalLinkCancel => &[("Status", Status), ("Physical handle", Hex8), ("TX flow spec ID", Hex8)],
+
+        // Link Policy commands
+        RoleDiscovery => &[("Status", Status), ("Handle", Hex16), ("Role", Role)],
+        ReadLinkPolicySettings => &[("Status", Status), ("Handle", Hex16), ("Link policy", Flags16(LINK_POLICY))],
+        ReadDefaultLinkPolicySettings => &[("Status", Status), ("Link policy", Flags16(LINK_POLICY))],
+        WriteLinkPolicySettings | SniffSubrating => STATUS_HANDLE,
+
+        // Controller & Baseband commands
+        Flush | WriteAutoFlushTimeout | WriteLinkSupervisionTimeout | ResetFailedContactCounter => STATUS_HANDLE,
+        ReadPinType => &[("Status", Status), ("PIN type", Hex8)],
+        ReadStoredLinkKey => &[("Status", Status), ("Max num keys", U16), ("Num keys", U16)],
+        WriteStoredLinkKey => &[("Status", Status), ("Num keys", U8)],
+        DeleteStoredLinkKey => &[("Status", Status), ("Num keys", U16)],
+        ReadLocalName => &[("Status", Status), ("Name", Name)],
+        ReadConnAcceptTimeout | ReadPageTimeout => &[("Status", Status), ("Timeout", AdvInterval)],
+        ReadScanEnable => &[("Status", Status), ("Scan enable", Named(SCAN_ENABLE))],
+        ReadPageScanActivity | ReadInquiryScanActivity => &[
+            ("Status", Status),
+            ("Interval", AdvInterval),
+            ("Window", AdvInterval),
+        ],
+        ReadAuthEnable => &[("Status", Status), ("Enable", Named(ENABLE))],
+        ReadEncryptMode => &[("Status", Status), ("Mode", Hex8)],
+        ReadClassOfDevice => &[("Status", Status), ("Class", Hex24)],
+        ReadVoiceSetting => &[("Status", Status), ("Setting", Hex16)],
+        ReadAutoFlushTimeout | ReadLinkSupervisionTimeout => &[
+            ("Status", Status),
+            ("Handle", Hex16),
+            ("Timeout", AdvInterval),
+        ],
+        ReadNumBroadcastRetrans => &[("Status", Status), ("Num retransmissions", U8)],
+        ReadHoldModeActivity => &[("Status", Status), ("Activity", Hex8)],
+        ReadTransmitPowerLevel => &[("Status", Status), ("Handle", Hex16), ("TX power", Dbm)],
+        ReadSynchronousFlowControlEnable => &[("Status", Status), ("Flow control", Named(ENABLE))],
+        ReadNumberOfSupportedIac => &[("Status", Status), ("Num support IAC", U8)],
+        ReadCurrentIacLap => &[("Status", Status), ("Num current IAC", List(&[("Access code", Hex24)]))],
+        ReadPageScanPeriodMode | ReadPageScanMode => &[("Status", Status), ("Mode", Hex8)],
+        ReadInquiryScanType | ReadPageScanType => &[("Status", Status), ("Type", Hex8)],
+        ReadInquiryMode => &[("Status", Status), ("Mode", Named(INQUIRY_MODE))],
+        ReadAFHChannelAssessmentMode => &[("Status", Status), ("Mode", Named(ENABLE))],
+        ReadExtendedInquiryResponse => &[("Status", Status), ("FEC", Hex8), ("Data", Bytes(240))],
+        ReadSimplePairingMode => &[("Status", Status), ("Mode", Named(ENABLE))],
+        ReadLocalOOBData => &[("Status", Status), ("Hash C", Key(16)), ("Randomizer R", Key(16))],
+        ReadInquiryResponseTransmitPowerLevel => &[("Status", Status), ("TX power", Dbm)],
+        ReadDefaultErrDataReporting => &[("Status", Status), ("Mode", Named(ENABLE))],
+
+        // Informational parameters
+        ReadLocalVerInfo => &[
+            ("Status", Status),
+            ("HCI version", Named(VERSIONS)),
+            ("HCI revision", Hex16),
+            ("LMP version", Named(VERSIONS)),
+            ("Manufacturer", Company),
+            ("LMP subversion", Hex16),
+        ],
+        ReadLocalSupportedCommands => &[("Status", Status), ("Commands", Commands)],
+        ReadLocalSupportedFeatures => &[("Status", Status), ("Features", Hex64)],
+        ReadLocalExtendedFeatures => &[
+            ("Status", Status),
+            ("Page", U8),
+            ("Max page", U8),
+            ("Features", Hex64),
+        ],
+        ReadBufSize => &[
+            ("Status", Status),
+            ("ACL MTU", U16),
+            ("SCO MTU", U8),
+            ("ACL max packets", U16),
+            ("SCO max packets", U16),
+        ],
+        ReadBdAddr => STATUS_ADDR,
+
+        // Status parameters
+        ReadFailedContactCounter => &[("Status", Status), ("Handle", Hex16), ("Counter", U16)],
+        ReadLinkQuality => &[("Status", Status), ("Handle", Hex16), ("Link quality", U8)],
+        ReadRssi => &[("Status", Status), ("Handle", Hex16), ("RSSI", Dbm)],
+        ReadAfhChannelMap => &[
+            ("Status", Status),
+            ("Handle", Hex16),
+            ("Mode", Named(ENABLE)),
+            ("Map", Bytes(10)),
+        ],
+        ReadClock => &[("Status", Status), ("Handle", Hex16), ("Clock", Hex32), ("Accuracy", Hex16)],
+        ReadEncryptionKeySize => &[("Status", Status), ("Handle", Hex16), ("Key size", U8)],
+
+        // Testing commands
+        ReadLoopbackMode => &[("Status", Status), ("Mode", Hex8)],
+        WriteSecureConnectionsTestMode => STATUS_HANDLE,
+
+        // LE Controller commands
+        LeReadBufSizeV1 => &[
+            ("Status", Status),
+            ("Data packet length", U16),
+            ("Num data packets", U8),
+        ],
+        LeReadBufSizeV2 => &[
+            ("Status", Status),
+            ("ACL data packet length", U16),
+            ("Num ACL data packets", U8),
+            ("ISO data packet length", U16),
+            ("Num ISO data packets", U8),
+        ],
+        LeReadLocalFeaturesPage0 => &[("Status", Status), ("Features", LeFeatures)],
+        LeReadAdvPhyChanTxPower => &[("Status", Status), ("TX power", Dbm)],
+        LeReadFilterAcceptListSize | LeReadResolvListSize | LeReadPeriodicAdvListSize => STATUS_SIZE,
+        LeReadChanMAp => &[("Status", Status), ("Handle", Hex16), ("Channel map", Bytes(5))],
+        LeEncrypt => &[("Status", Status), ("Encrypted data", Key(16))],
+        LeRand => &[("Status", Status), ("Random number", Hex64)],
+        LeLtkReqReply | LeLtkReqNegReply | LeRemoteConnParamReqReply |
+        LeRemoteConnParamReqNegReply | LeSetDataLength => STATUS_HANDLE,
+        LeReadSupportedStates => &[("Status", Status), ("States", Hex64)],
+        LeTestEnd => &[("Status", Status), ("Num packets", U16)],
+        LeReadSuggDefDataLength => &[("Status", Status), ("TX octets", U16), ("TX time", Usec)],
+        LeReadPeerResolvAddress | LeReadLocalResolvAddress => STATUS_ADDR,
+        LeReadMaxDataLength => &[
+            ("Status", Status),
+            ("Max TX octets", U16),
+            ("Max TX time", Usec),
+            ("Max RX octets", U16),
+            ("Max RX time", Usec),
+        ],
+        LeReadPhy => &[("Status", Status), ("Handle", Hex16), ("TX PHY", Phy), ("RX PHY", Phy)],
+        LeSetExtAdvParamV1 | LeSetExtAdvParamV2 => &[("Status", Status), ("Selected TX power", Dbm)],
+        LeReadMaxAdvDataLength => &[("Status", Status), ("Max length", U16)],
+        LeReadNumSuppAdvSets => &[("Status", Status), ("Num supported advertising sets", U8)],
+        LeReadTransmitPower => &[("Status", Status), ("Min TX power", Dbm), ("Max TX power", Dbm)],
+        LeReadRfPathCompensation => &[
+            ("Status", Status),
+            ("TX path compensation", Db10),
+            ("RX path compensation", Db10),
+        ],
+
+        _ => return None,
+    };
+
+    Some(layout)
+}
+
+/// Decode the return parameters of a Command Complete event for `op`. Every
+/// command returns a status first, so unknown commands still get that much.
+pub(crate) fn fields(op: Op, data: &[u8]) -> Option<Fields> {
+    layout(op).and_then(|layout| param::fields(layout, data))
+        .or_else(|| param::fields(STATUS, data))
+        .or_else(|| param::fields(&[("Status", Status), ("Parameters", Rest)], data))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hci::Event;
+
+    #[test]
+    fn read_local_version() {
+        let data = [0x0e, 0x0c, 0x01, 0x01, 0x10, 0x00, 0x0d, 0x00, 0x00, 0x0d, 0xff, 0x02, 0x00, 0x00];
+        let (_, ev) = Event::parse(&data).unwrap();
+
+        assert_eq!(ev.to_string(), "Command Complete: ncmd 1 Read Local Version Information\
+            \n        Status: Success (0x00)\
+            \n        HCI version: Bluetooth 5.4 (0x0d)\
+            \n        HCI revision: 0x0000\
+            \n        LMP version: Bluetooth 5.4 (0x0d)\
+            \n        Manufacturer: Silicon Laboratories (0x02ff)\
+            \n        LMP subversion: 0x0000");
+    }
+
+    #[test]
+    fn read_remote_version() {
+        let data = [0x0c, 0x08, 0x00, 0x40, 0x00, 0x0c, 0x59, 0x00, 0x34, 0x12];
+        let (_, ev) = Event::parse(&data).unwrap();
+
+        assert_eq!(ev.to_string(), "Read Remote Version Information Complete\
+            \n        Status: Success (0x00)\
+            \n        Handle: 0x0040\
+            \n        LMP version: Bluetooth 5.3 (0x0c)\
+            \n        Manufacturer: Nordic Semiconductor ASA (0x0059)\
+            \n        LMP subversion: 0x1234");
+    }
+
+    #[test]
+    fn unknown_command_status() {
+        let data = [0x0e, 0x05, 0x01, 0x01, 0xfc, 0x0c, 0xaa];
+        let (_, ev) = Event::parse(&data).unwrap();
+
+        assert!(ev.to_string().ends_with("\n        Status: Command Disallowed (0x0c)\n        Parameters: [aa]"));
+    }
+}
@@ -0,0 +1,505 @@
+use nom::{
+    bytes::complete::take,
+    combinator::{all_consuming, cond, rest},
+    number::complete::{le_u8, le_u16, le_u24, le_u32},
+    sequence::tuple,
+};
+use std::{fmt, borrow::Cow, collections::{HashMap, HashSet}};
+use crate::error::{self, Layer, PResult};
+use crate::hci::{self, Command, ConnInterval, Event, LeEvent, Op};
+use crate::monitor::Direction;
+
+/// Packet boundary flags of an ISO data packet
+const PB_FIRST: u8 = 0b00;
+const PB_CONT: u8 = 0b01;
+const PB_COMPLETE: u8 = 0b10;
+const PB_LAST: u8 = 0b11;
+
+fn pb_str(pb: u8) -> &'static str {
+    match pb {
+        PB_FIRST    => "first",
+        PB_CONT     => "continuation",
+        PB_COMPLETE => "complete",
+        PB_LAST     => "last",
+        _           => "reserved",
+    }
+}
+
+pub(crate) fn status_str(status: u8) -> &'static str {
+    match status {
+        0x00 => "Valid",
+        0x01 => "Possibly invalid",
+        0x02 => "Lost",
+        _    => "Reserved",
+    }
+}
+
+/// Fields carried by the first fragment of an SDU, or a complete one
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SduHeader {
+    pub seq: u16,
+    pub len: u16,
+    /// Packet Status Flag, only meaningful from the controller to the host
+    pub status: u8,
+}
+
+/// HCI ISO data packet
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct IsoPkt <'a> {
+    handle: u16,
+    pb: u8,
+    ts: Option<u32>,
+    header: Option<SduHeader>,
+    data: &'a [u8],
+}
+
+fn sdu_header(i: &[u8]) -> PResult<'_, SduHeader> {
+    let (i, (seq, len)) = tuple((le_u16, le_u16))(i)?;
+
+    Ok((i, SduHeader { seq, len: len & 0x0fff, status: (len >> 14) as u8 }))
+}
+
+fn load(pb: u8, ts: bool, i: &[u8]) -> PResult<'_, (Option<u32>, Option<SduHeader>, &[u8])> {
+    tuple((cond(ts, le_u32), cond(pb == PB_FIRST || pb == PB_COMPLETE, sdu_header), rest))(i)
+}
+
+impl<'a> IsoPkt<'a> {
+    fn parser(input: &'a [u8]) -> PResult<'a, IsoPkt<'a>> {
+        let (i, (handle, len)) = tuple((le_u16, le_u16))(input)?;
+        let (rem, i) = take(len & 0x3fff)(i)?;
+        let pb = ((handle >> 12) & 0b11) as u8;
+        let (_, (ts, header, data)) = all_consuming(|i| load(pb, handle & 0x4000 != 0, i))(i)?;
+
+        Ok((rem, IsoPkt { handle: handle & 0x0fff, pb, ts, header, data }))
+    }
+
+    pub fn parse(input: &'a [u8]) -> error::Result<(&'a [u8], IsoPkt<'a>)> {
+        error::finish(Layer::Iso, input, IsoPkt::parser(input))
+    }
+
+    pub fn handle(&self) -> u16 {
+        self.handle
+    }
+
+    pub fn pb(&self) -> u8 {
+        self.pb
+    }
+
+    /// Time stamp in microseconds, if the packet carries one
+    pub fn timestamp(&self) -> Option<u32> {
+        self.ts
+    }
+
+    pub fn header(&self) -> Option<SduHeader> {
+        self.header
+    }
+
+    /// The SDU or the fragment of it carried by this packet
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl fmt::Display for IsoPkt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "handle 0x{:04x} pb {}", self.handle, pb_str(self.pb))?;
+        if let Some(ts) = self.ts {
+            write!(f, " ts {} us", ts)?;
+        }
+        if let Some(h) = self.header {
+            write!(f, " seq {} SDU length {} status {} (0x{:02x})", h.seq, h.len, status_str(h.status), h.status)?;
+        }
+        write!(f, ": {} bytes", self.data.len())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StreamKind {
+    Cis,
+    Bis { big_handle: u8 },
+    /// Data seen before, or without, the event creating the stream
+    Unknown,
+}
+
+/// SDU counters and timing of one direction of a stream
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamStats {
+    pub sdus: u64,
+    pub bytes: u64,
+    /// Jumps in the sequence number and the number of SDUs skipped by them
+    pub gaps: u64,
+    pub missing: u64,
+    /// SDUs the controller flagged as possibly invalid or lost
+    pub invalid: u64,
+    pub lost: u64,
+    /// SDU interval in microseconds, if known
+    pub sdu_interval: Option<u32>,
+    /// Smoothed and maximum deviation of the time stamps from the SDU
+    /// interval, or from the ISO interval if that's unknown, in microseconds
+    pub jitter: u32,
+    pub max_jitter: u32,
+    last: Option<(u16, Option<u32>)>,
+}
+
+impl StreamStats {
+    /// Account a complete SDU. Returns the number of SDUs missing before it.
+    fn sdu(&mut self, header: SduHeader, ts: Option<u32>, len: usize, interval: u32) -> u16 {
+        self.sdus += 1;
+        self.bytes += len as u64;
+        match header.status {
+            0x01 => self.invalid += 1,
+            0x02 => self.lost += 1,
+            _ => (),
+        }
+
+        let mut missing = 0;
+        if let Some((seq, last_ts)) = self.last {
+            let steps = header.seq.wrapping_sub(seq);
+            // Anything further than half the sequence space is a repeat
+            if steps > 1 && steps < 0x8000 {
+                missing = steps - 1;
+                self.gaps += 1;
+                self.missing += missing as u64;
+            }
+            if let (Some(ts), Some(last_ts), 1..=0x7fff, 1..) = (ts, last_ts, steps, interval) {
+                let delta = ts.wrapping_sub(last_ts) as i64 - steps as i64 * interval as i64;
+                let dev = delta.unsigned_abs().min(u32::MAX as u64) as u32;
+                self.jitter = (self.jitter as i64 + (dev as i64 - self.jitter as i64) / 16) as u32;
+                self.max_jitter = self.max_jitter.max(dev);
+            }
+        }
+        self.last = Some((header.seq, ts));
+
+        missing
+    }
+}
+
+impl fmt::Display for StreamStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} SDUs, {} bytes, {} missing in {} gaps, {} invalid, {} lost",
+               self.sdus, self.bytes, self.missing, self.gaps, self.invalid, self.lost)?;
+        if self.max_jitter > 0 {
+            write!(f, ", jitter {} us (max {} us)", self.jitter, self.max_jitter)?;
+        }
+        Ok(())
+    }
+}
+
+/// Connected or broadcast isochronous stream
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stream {
+    pub index: u16,
+    pub handle: u16,
+    pub kind: StreamKind,
+    /// ISO interval in units of 1.25 ms, if known
+    pub iso_interval: Option<u16>,
+    pub tx: StreamStats,
+    pub rx: StreamStats,
+}
+
+impl Stream {
+    fn new(index: u16, handle: u16, kind: StreamKind, iso_interval: Option<u16>) -> Self {
+        Stream { index, handle, kind, iso_interval, tx: StreamStats::default(), rx: StreamStats::default() }
+    }
+}
+
+impl fmt::Display for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            StreamKind::Cis => write!(f, "CIS")?,
+            StreamKind::Bis { big_handle } => write!(f, "BIS of BIG 0x{:02x}", big_handle)?,
+            StreamKind::Unknown => write!(f, "ISO stream")?,
+        }
+        write!(f, " handle 0x{:04x} (index {})", self.handle, self.index)?;
+        if let Some(interval) = self.iso_interval {
+            hci::field(f, "ISO interval", ConnInterval(interval))?;
+        }
+        hci::field(f, "TX", &self.tx)?;
+        hci::field(f, "RX", &self.rx)
+    }
+}
+
+/// What an ISO data packet amounted to
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Sdu<'a> {
+    Complete {
+        header: SduHeader,
+        ts: Option<u32>,
+        data: Cow<'a, [u8]>,
+        /// SDUs skipped by the sequence number since the previous one
+        missing: u16,
+    },
+    /// Bytes of the SDU received so far, and its total length
+    Partial { len: usize, total: usize },
+    /// Continuation or last fragment without a first one
+    Orphaned,
+    /// Fragments adding up to more than the SDU length
+    Overflow { extra: usize },
+}
+
+/// First fragment of an SDU waiting for the rest
+#[derive(Debug)]
+struct PartialSdu {
+    header: SduHeader,
+    ts: Option<u32>,
+    buf: Vec<u8>,
+}
+
+/// BIG handle and SDU interval of LE Create BIG
+fn create_big(i: &[u8]) -> PResult<'_, (u8, u32)> {
+    let (i, (big_handle, _, _, sdu_interval)) = tuple((le_u8, le_u8, le_u8, le_u24))(i)?;
+    Ok((i, (big_handle, sdu_interval)))
+}
+
+/// BIG handle and sync handle of LE BIG Create Sync
+fn big_create_sync(i: &[u8]) -> PResult<'_, (u8, u16)> {
+    tuple((le_u8, le_u16))(i)
+}
+
+/// Isochronous streams of all controllers, fed from HCI events and ISO
+/// data packets.
+#[derive(Debug, Default)]
+pub struct Streams {
+    open: HashMap<(u16, u16), Stream>,
+    closed: Vec<Stream>,
+    partial: HashMap<(u16, u16, Direction), PartialSdu>,
+    /// CISes the local device was requested to accept, i.e. is peripheral of
+    peripheral: HashSet<(u16, u16)>,
+    /// SDU interval of BIGs being created or synchronized to
+    big_sdu_interval: HashMap<(u16, u8), u32>,
+    /// SDU interval from the BIGInfo of periodic advertising trains
+    big_info: HashMap<(u16, u16), u32>,
+}
+
+impl Streams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, index: u16, handle: u16) -> Option<&Stream> {
+        self.open.get(&(index, handle))
+    }
+
+    /// Every stream, closed ones first
+    pub fn all(&self) -> Vec<&Stream> {
+        let mut open: Vec<_> = self.open.values().collect();
+        open.sort_by_key(|s| (s.index, s.handle));
+        self.closed.iter().chain(open).collect()
+    }
+
+    fn close(&mut self, index: u16, handle: u16) {
+        self.partial.retain(|(i, h, _), _| (*i, *h) != (index, handle));
+        if let Some(stream) = self.open.remove(&(index, handle)) {
+            self.closed.push(stream);
+        }
+    }
+
+    fn establish(&mut self, index: u16, handle: u16, kind: StreamKind, iso_interval: u16,
+                 sdu_interval: (Option<u32>, Option<u32>)) {
+        self.close(index, handle);
+        let mut stream = Stream::new(index, handle, kind, Some(iso_interval));
+        (stream.tx.sdu_interval, stream.rx.sdu_interval) = sdu_interval;
+        self.open.insert((index, handle), stream);
+    }
+
+    /// Note the SDU interval of BIGs from the HCI commands creating them or
+    /// synchronizing to them
+    pub fn command(&mut self, index: u16, cmd: &Command) {
+        match cmd.op() {
+            Op::LeCreateBig => {
+                if let Ok((_, (big_handle, sdu_interval))) = create_big(cmd.param()) {
+                    self.big_sdu_interval.insert((index, big_handle), sdu_interval);
+                }
+            },
+            Op::LeBigCreateSync => {
+                if let Ok((_, (big_handle, sync_handle))) = big_create_sync(cmd.param()) {
+                    if let Some(sdu_interval) = self.big_info.get(&(index, sync_handle)).copied() {
+                        self.big_sdu_interval.insert((index, big_handle), sdu_interval);
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Update the streams from an HCI event
+    pub fn event(&mut self, index: u16, ev: &Event) {
+        // Disconnect Complete, also sent for CISes
+        if let Some((status, handle, _)) = ev.disconnect_complete() {
+            if status == 0 {
+                self.close(index, handle);
+            }
+            return;
+        }
+
+        match ev.le_event() {
+            Some(LeEvent::CisRequest { cis_handle, .. }) => {
+                self.peripheral.insert((index, cis_handle));
+            },
+            Some(LeEvent::CisEstablished { status, handle, iso_interval, sdu, .. }) => {
+                let peripheral = self.peripheral.remove(&(index, handle));
+                if status == 0 {
+                    let (c_to_p, p_to_c) = sdu.map(|s| (s.sdu_interval_c_to_p, s.sdu_interval_p_to_c)).unzip();
+                    let sdu_interval = if peripheral { (p_to_c, c_to_p) } else { (c_to_p, p_to_c) };
+                    self.establish(index, handle, StreamKind::Cis, iso_interval, sdu_interval);
+                }
+            },
+            Some(LeEvent::BigComplete { status, big_handle, iso_interval, bis_handles, .. }) => {
+                let sdu_interval = self.big_sdu_interval.remove(&(index, big_handle));
+                if status == 0 {
+                    for handle in bis_handles {
+                        self.establish(index, handle, StreamKind::Bis { big_handle }, iso_interval, (sdu_interval, None));
+                    }
+                }
+            },
+            Some(LeEvent::BigSyncEstablished { status, big_handle, iso_interval, bis_handles, .. }) => {
+                let sdu_interval = self.big_sdu_interval.remove(&(index, big_handle));
+                if status == 0 {
+                    for handle in bis_handles {
+                        self.establish(index, handle, StreamKind::Bis { big_handle }, iso_interval, (None, sdu_interval));
+                    }
+                }
+            },
+            Some(LeEvent::BigInfoReport { sync_handle, sdu_interval, .. }) => {
+                self.big_info.insert((index, sync_handle), sdu_interval);
+            },
+            Some(LeEvent::BigTerminated { big_handle, .. }) | Some(LeEvent::BigSyncLost { big_handle, .. }) => {
+                let handles: Vec<_> = self.open.values()
+                    .filter(|s| s.index == index && s.kind == StreamKind::Bis { big_handle })
+                    .map(|s| s.handle)
+                    .collect();
+                for handle in handles {
+                    self.close(index, handle);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Reassemble an ISO data packet sent in `dir` into an SDU, and account
+    /// complete SDUs to their stream
+    pub fn packet<'a>(&mut self, index: u16, dir: Direction, pkt: &IsoPkt<'a>) -> Sdu<'a> {
+        let key = (index, pkt.handle, dir);
+
+        let (header, ts, data) = match (pkt.pb, pkt.header) {
+            (PB_COMPLETE, Some(header)) => {
+                self.partial.remove(&key);
+                (header, pkt.ts, Cow::Borrowed(pkt.data))
+            },
+            (_, Some(header)) => {
+                let len = pkt.data.len();
+                self.partial.insert(key, PartialSdu { header, ts: pkt.ts, buf: pkt.data.to_vec() });
+                return Sdu::Partial { len, total: header.len as usize };
+            },
+            (_, None) => {
+                let Some(partial) = self.partial.get_mut(&key) else {
+                    return Sdu::Orphaned;
+                };
+                partial.buf.extend_from_slice(pkt.data);
+                let (len, total) = (partial.buf.len(), partial.header.len as usize);
+                if len > total {
+                    self.partial.remove(&key);
+                    return Sdu::Overflow { extra: len - total };
+                }
+                if pkt.pb == PB_CONT {
+                    return Sdu::Partial { len, total };
+                }
+                let Some(PartialSdu { header, ts, buf }) = self.partial.remove(&key) else {
+                    return Sdu::Orphaned;
+                };
+                (header, ts, Cow::Owned(buf))
+            },
+        };
+
+        let stream = self.open.entry((index, pkt.handle))
+            .or_insert_with(|| Stream::new(index, pkt.handle, StreamKind::Unknown, None));
+        let iso_interval = stream.iso_interval.map_or(0, |i| i as u32 * 1250);
+        let stats = match dir {
+            Direction::Tx => &mut stream.tx,
+            Direction::Rx => &mut stream.rx,
+        };
+        let interval = stats.sdu_interval.unwrap_or(iso_interval);
+        let missing = stats.sdu(header, ts, data.len(), interval);
+
+        Sdu::Complete { header, ts, data, missing }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IsoPkt, Sdu, SduHeader, Streams};
+    use crate::hci::Event;
+    use crate::monitor::Direction;
+
+    fn packet(streams: &mut Streams, data: &[u8]) -> Sdu<'static> {
+        let (_, pkt) = IsoPkt::parse(data).unwrap();
+        match streams.packet(0, Direction::Rx, &pkt) {
+            Sdu::Complete { header, ts, data, missing } => {
+                Sdu::Complete { header, ts, data: data.into_owned().into(), missing }
+            },
+            Sdu::Partial { len, total } => Sdu::Partial { len, total },
+            Sdu::Orphaned => Sdu::Orphaned,
+            Sdu::Overflow { extra } => Sdu::Overflow { extra },
+        }
+    }
+
+    #[test]
+    fn fragments_and_gaps() {
+        let mut streams = Streams::new();
+
+        // First fragment with time stamp, seq 1, SDU length 3
+        assert_eq!(packet(&mut streams, &[0x60, 0x40, 0x0a, 0x00, 0x10, 0x27, 0x00, 0x00, 0x01, 0x00, 0x03, 0x00, 0x0a, 0x0b]),
+                   Sdu::Partial { len: 2, total: 3 });
+        assert_eq!(packet(&mut streams, &[0x60, 0x30, 0x01, 0x00, 0x0c]), Sdu::Complete {
+            header: SduHeader { seq: 1, len: 3, status: 0 },
+            ts: Some(10000),
+            data: vec![0x0a, 0x0b, 0x0c].into(),
+            missing: 0,
+        });
+
+        // Complete SDU with seq 4, lost per the status flag
+        let Sdu::Complete { missing, .. } = packet(&mut streams, &[0x60, 0x20, 0x04, 0x00, 0x04, 0x00, 0x00, 0x80]) else {
+            panic!("SDU not complete");
+        };
+        assert_eq!(missing, 2);
+        assert_eq!(packet(&mut streams, &[0x60, 0x10, 0x01, 0x00, 0x00]), Sdu::Orphaned);
+
+        let rx = &streams.get(0, 0x0060).unwrap().rx;
+        assert_eq!((rx.sdus, rx.gaps, rx.missing, rx.lost), (2, 1, 2, 1));
+    }
+
+    #[test]
+    fn jitter_against_sdu_interval() {
+        let mut streams = Streams::new();
+
+        // CISes with a 7.5 ms ISO interval, carrying 10 ms SDUs. Only v2
+        // of CIS Established tells the SDU interval.
+        let mut v1 = vec![
+            0x3e, 0x1d, 0x19, 0x00, 0x61, 0x00, 0x10, 0x27, 0x00, 0x10, 0x27, 0x00, 0x10, 0x27, 0x00, 0x10, 0x27, 0x00,
+            0x02, 0x02, 0x02, 0x01, 0x01, 0x01, 0x01, 0x78, 0x00, 0x78, 0x00, 0x06, 0x00,
+        ];
+        let (_, ev) = Event::parse(&v1).unwrap();
+        streams.event(0, &ev);
+
+        (v1[1], v1[2], v1[4]) = (0x2b, 0x2a, 0x60);
+        v1.extend_from_slice(&[0xc4, 0x09, 0x00, 0x64, 0x00, 0x64, 0x00, 0x10, 0x27, 0x00, 0x10, 0x27, 0x00, 0x00]);
+        let (_, ev) = Event::parse(&v1).unwrap();
+        streams.event(0, &ev);
+
+        // Complete SDUs with time stamps 0, 10000 and 20000
+        for (seq, ts) in [(0u8, [0x00, 0x00]), (1, [0x10, 0x27]), (2, [0x20, 0x4e])] {
+            for handle in [0x60, 0x61] {
+                packet(&mut streams, &[handle, 0x60, 0x09, 0x00, ts[0], ts[1], 0x00, 0x00, seq, 0x00, 0x01, 0x00, 0xaa]);
+            }
+        }
+
+        let rx = &streams.get(0, 0x0060).unwrap().rx;
+        assert_eq!((rx.sdu_interval, rx.max_jitter), (Some(10000), 0));
+        let rx = &streams.get(0, 0x0061).unwrap().rx;
+        assert_eq!((rx.sdu_interval, rx.max_jitter), (None, 2500));
+    }
+}
@@ -1,10 +1,46 @@
-use nom::{IResult, bytes::complete::take, sequence::tuple, number::complete::le_u16};
-use num_enum::FromPrimitive;
-use std::fmt;
-use crate::att;
+use nom::{bytes::complete::take, sequence::tuple, number::complete::le_u16};
+use num_enum::{FromPrimitive, IntoPrimitive};
+use std::{fmt, borrow::Cow, collections::HashMap};
+use crate::{att, smp};
+use crate::monitor::{AclPkt, Direction};
+use crate::error::{self, Layer, PResult};
+
+pub mod sig;
+mod chan;
+
+pub use chan::{Channel, Channels, Endpoint, Sdu};
+
+pub(crate) const PSM_IPSP: u16 = 0x0023;
+pub(crate) const PSM_OTS: u16 = 0x0025;
+pub(crate) const PSM_EATT: u16 = 0x0027;
+
+/// Protocol/Service Multiplexer, with the name of well-known fixed values
+pub(crate) struct Psm(pub u16);
+
+impl fmt::Display for Psm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.0 {
+            0x0001 => "SDP",
+            0x0003 => "RFCOMM",
+            0x000f => "BNEP",
+            0x0011 => "HID Control",
+            0x0013 => "HID Interrupt",
+            0x0017 => "AVCTP",
+            0x0019 => "AVDTP",
+            0x001b => "AVCTP Browsing",
+            0x001f => "ATT",
+            PSM_IPSP => "IPSP",
+            PSM_OTS  => "OTS",
+            PSM_EATT => "EATT",
+            _ => return write!(f, "0x{:04x}", self.0),
+        };
+
+        write!(f, "{} (0x{:04x})", name, self.0)
+    }
+}
 
 #[repr(u16)]
-#[derive(Debug, PartialEq, Eq, FromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, IntoPrimitive)]
 enum Cid {
     Null      = 0x0000,
     Sig       = 0x0001,
@@ -40,12 +76,24 @@ pub struct Frame <'a> {
 }
 
 impl Frame <'_> {
-    pub fn parse(input: &'_[u8]) -> IResult<&[u8], Frame> {
+    fn parser(input: &[u8]) -> PResult<'_, Frame<'_>> {
         let (rem, (len, cid)) = tuple((le_u16, le_u16))(input)?;
         let (rem, data) = take(len)(rem)?;
 
         Ok((rem, Frame { cid: Cid::from(cid), data }))
     }
+
+    pub fn parse(input: &[u8]) -> error::Result<(&[u8], Frame<'_>)> {
+        error::finish(Layer::L2cap, input, Frame::parser(input))
+    }
+
+    pub fn cid(&self) -> u16 {
+        self.cid.into()
+    }
+
+    pub fn data(&self) -> &[u8] {
+        self.data
+    }
 }
 
 impl fmt::Display for Frame<'_> {
@@ -59,7 +107,186 @@ impl fmt::Display for Frame<'_> {
                     Err(_) => write!(f, "{}: {:02x?}", self.cid, self.data),
                 }
             },
+            Sig | LeSig => {
+                write!(f, "{}:", self.cid)?;
+                let mut i = self.data;
+                while !i.is_empty() {
+                    match sig::Command::parse(i) {
+                        Ok((rem, cmd)) => {
+                            write!(f, " {}", cmd)?;
+                            i = rem;
+                        },
+                        Err(_) => return write!(f, " {:02x?}", i),
+                    }
+                }
+                Ok(())
+            },
+            Smp | BrSmp => {
+                match smp::Pdu::parse(self.data) {
+                    Ok((_, pdu)) => write!(f, "{}: {}", self.cid, pdu),
+                    Err(_) => write!(f, "{}: {:02x?}", self.cid, self.data),
+                }
+            },
             _ => write!(f, "{}: {:02x?}", self.cid, self.data),
         }
     }
 }
+
+/// Packet boundary flag of a continuation fragment
+const PB_CONT: u8 = 0b01;
+
+/// Total length of the L2CAP frame starting in `data`, once the basic
+/// header is available
+fn frame_len(data: &[u8]) -> Option<usize> {
+    match data {
+        [lo, hi, _, _, ..] => Some(u16::from_le_bytes([*lo, *hi]) as usize + 4),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FragmentError {
+    /// Continuation fragment without a preceding start fragment
+    Orphaned,
+    /// Fragment carrying more data than the frame needs
+    Overflow { extra: usize },
+    /// Start fragment while the previous frame was still incomplete
+    Interrupted { dropped: usize },
+}
+
+impl fmt::Display for FragmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FragmentError::Orphaned => write!(f, "orphaned continuation fragment"),
+            FragmentError::Overflow { extra } => {
+                write!(f, "fragment overflows frame by {} bytes", extra)
+            },
+            FragmentError::Interrupted { dropped } => {
+                write!(f, "previous frame incomplete, {} bytes dropped", dropped)
+            },
+        }
+    }
+}
+
+/// Outcome of feeding one ACL fragment to the `Reassembler`
+#[derive(Debug)]
+pub struct Reassembly<'a> {
+    /// Complete L2CAP frame, if this fragment finished one
+    pub frame: Option<Cow<'a, [u8]>>,
+    pub error: Option<FragmentError>,
+}
+
+/// Reassembles ACL fragments into complete L2CAP frames, separately for each
+/// controller, connection handle and direction.
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    partial: HashMap<(u16, u16, Direction), Vec<u8>>,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<'a>(&mut self, index: u16, dir: Direction, pkt: &'a AclPkt) -> Reassembly<'a> {
+        let key = (index, pkt.handle(), dir);
+        let data = pkt.data();
+
+        if pkt.pb() != PB_CONT {
+            let error = self.partial.remove(&key)
+                .map(|buf| FragmentError::Interrupted { dropped: buf.len() });
+
+            return match frame_len(data) {
+                Some(len) if data.len() == len => Reassembly { frame: Some(Cow::Borrowed(data)), error },
+                Some(len) if data.len() > len => {
+                    let error = Some(FragmentError::Overflow { extra: data.len() - len });
+                    Reassembly { frame: Some(Cow::Borrowed(&data[..len])), error }
+                },
+                _ => {
+                    self.partial.insert(key, data.to_vec());
+                    Reassembly { frame: None, error }
+                },
+            };
+        }
+
+        let Some(buf) = self.partial.get_mut(&key) else {
+            return Reassembly { frame: None, error: Some(FragmentError::Orphaned) };
+        };
+
+        buf.extend_from_slice(data);
+
+        match frame_len(buf) {
+            Some(len) if buf.len() == len => {
+                Reassembly { frame: self.partial.remove(&key).map(Cow::Owned), error: None }
+            },
+            Some(len) if buf.len() > len => {
+                let extra = buf.len() - len;
+                self.partial.remove(&key);
+                Reassembly { frame: None, error: Some(FragmentError::Overflow { extra }) }
+            },
+            _ => Reassembly { frame: None, error: None },
+        }
+    }
+
+    /// Number of bytes buffered and still missing for an incomplete frame
+    pub fn pending(&self, index: u16, handle: u16, dir: Direction) -> Option<(usize, Option<usize>)> {
+        self.partial.get(&(index, handle, dir))
+            .map(|buf| (buf.len(), frame_len(buf).map(|len| len - buf.len())))
+    }
+
+    /// Drop any partial frames of a connection, e.g. once it's disconnected
+    pub fn reset(&mut self, index: u16, handle: u16) {
+        self.partial.retain(|(i, h, _), _| (*i, *h) != (index, handle));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reassembler, FragmentError};
+    use crate::monitor::{monitor_packet, Direction, Op};
+    use time::Time;
+
+    fn acl(pb: u8, data: &[u8]) -> Vec<u8> {
+        let mut pkt = Vec::new();
+        pkt.extend_from_slice(&(0x0040 | (pb as u16) << 12).to_le_bytes());
+        pkt.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        pkt.extend_from_slice(data);
+        pkt
+    }
+
+    fn push(r: &mut Reassembler, pb: u8, data: &[u8]) -> (Option<Vec<u8>>, Option<FragmentError>) {
+        let raw = acl(pb, data);
+        let (_, pkt) = monitor_packet(Time::MIDNIGHT, 0, 5, &raw).unwrap();
+        let Op::AclRxPkt(acl) = &pkt.op else { unreachable!() };
+        let res = r.push(0, Direction::Rx, acl);
+
+        (res.frame.map(|f| f.into_owned()), res.error)
+    }
+
+    #[test]
+    fn split_frame() {
+        let mut r = Reassembler::new();
+
+        assert_eq!(push(&mut r, 0b10, &[0x05, 0x00, 0x04, 0x00, 0x0a]), (None, None));
+        assert_eq!(r.pending(0, 0x0040, Direction::Rx), Some((5, Some(4))));
+        assert_eq!(push(&mut r, 0b01, &[0x01, 0x00]), (None, None));
+        assert_eq!(push(&mut r, 0b01, &[0x02, 0x03]),
+                   (Some(vec![0x05, 0x00, 0x04, 0x00, 0x0a, 0x01, 0x00, 0x02, 0x03]), None));
+        assert_eq!(r.pending(0, 0x0040, Direction::Rx), None);
+    }
+
+    #[test]
+    fn bad_fragments() {
+        let mut r = Reassembler::new();
+
+        assert_eq!(push(&mut r, 0b01, &[0x01]), (None, Some(FragmentError::Orphaned)));
+        assert_eq!(push(&mut r, 0b10, &[0x05, 0x00]), (None, None));
+        assert_eq!(push(&mut r, 0b10, &[0x01, 0x00, 0x04, 0x00, 0x1e]),
+                   (Some(vec![0x01, 0x00, 0x04, 0x00, 0x1e]), Some(FragmentError::Interrupted { dropped: 2 })));
+        assert_eq!(push(&mut r, 0b10, &[0x01, 0x00, 0x04]), (None, None));
+        assert_eq!(push(&mut r, 0b01, &[0x00, 0x1e, 0xff]), (None, Some(FragmentError::Overflow { extra: 1 })));
+        assert_eq!(push(&mut r, 0b10, &[0x01, 0x00, 0x04, 0x00, 0x1e, 0xff, 0xff]),
+                   (Some(vec![0x01, 0x00, 0x04, 0x00, 0x1e]), Some(FragmentError::Overflow { extra: 2 })));
+        assert_eq!(r.pending(0, 0x0040, Direction::Rx), None);
+    }
+}
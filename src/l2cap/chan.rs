@@ -0,0 +1,335 @@
+use std::{fmt, borrow::Cow, cmp::Ordering, collections::HashMap};
+use crate::conn::Counters;
+use crate::hci;
+use crate::monitor::Direction;
+use super::Psm;
+use super::sig::{Command, Param};
+
+/// Code of the Disconnection Response, the point at which a channel is gone
+const DISCONNECTION_RSP: u8 = 0x07;
+
+/// One end of a credit based channel
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Endpoint {
+    pub cid: u16,
+    pub mtu: u16,
+    pub mps: u16,
+    /// K-frames this end can still receive
+    pub credits: u16,
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CID 0x{:04x} MTU {} MPS {} credits {}", self.cid, self.mtu, self.mps, self.credits)
+    }
+}
+
+/// LE or enhanced credit based channel negotiated over signaling
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Channel {
+    pub index: u16,
+    pub handle: u16,
+    pub psm: u16,
+    /// Whether the channel was set up with the enhanced credit based procedure
+    pub enhanced: bool,
+    pub local: Endpoint,
+    pub remote: Endpoint,
+    /// SDUs and their bytes in either direction
+    pub tx: Counters,
+    pub rx: Counters,
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = if self.enhanced { "Enhanced credit based" } else { "LE credit based" };
+        write!(f, "{} channel PSM {} handle 0x{:04x} (index {})", mode, Psm(self.psm), self.handle, self.index)?;
+        hci::field(f, "Local", self.local)?;
+        hci::field(f, "Remote", self.remote)?;
+        hci::field(f, "TX", format_args!("{} SDUs, {} bytes", self.tx.packets, self.tx.bytes))?;
+        hci::field(f, "RX", format_args!("{} SDUs, {} bytes", self.rx.packets, self.rx.bytes))
+    }
+}
+
+/// What a K-frame on a tracked channel amounted to
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Sdu<'a> {
+    /// The K-frame completed an SDU
+    Complete(Cow<'a, [u8]>),
+    /// Bytes of the SDU received so far, and its total length
+    Partial { len: usize, total: usize },
+    /// K-frame too short to carry the SDU length field
+    Truncated,
+    /// K-frame carrying more data than its SDU needs
+    Overflow { extra: usize },
+}
+
+/// Request waiting for its response, keyed by the identifier
+#[derive(Debug)]
+enum Pending {
+    Connect { psm: u16, cids: Vec<u16>, mtu: u16, mps: u16, credits: u16 },
+    Reconfigure { cids: Vec<u16>, mtu: u16, mps: u16 },
+}
+
+/// SDU length and bytes so far of an incomplete SDU
+#[derive(Debug)]
+struct PartialSdu {
+    total: usize,
+    buf: Vec<u8>,
+}
+
+fn reverse(dir: Direction) -> Direction {
+    match dir {
+        Direction::Tx => Direction::Rx,
+        Direction::Rx => Direction::Tx,
+    }
+}
+
+/// Credit based channels of all connections, fed from signaling commands and
+/// the K-frames sent on them.
+#[derive(Debug, Default)]
+pub struct Channels {
+    open: Vec<Channel>,
+    closed: Vec<Channel>,
+    pending: HashMap<(u16, u16, Direction, u8), Pending>,
+    partial: HashMap<(u16, u16, Direction, u16), PartialSdu>,
+}
+
+impl Channels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open channel with `cid` as seen in a frame going in `dir`
+    pub fn get(&self, index: u16, handle: u16, dir: Direction, cid: u16) -> Option<&Channel> {
+        self.position(index, handle, dir, cid).map(|pos| &self.open[pos])
+    }
+
+    /// Every channel, closed ones first
+    pub fn all(&self) -> impl Iterator<Item = &Channel> {
+        self.closed.iter().chain(self.open.iter())
+    }
+
+    /// Endpoint receiving frames sent in `dir`
+    fn dest(chan: &Channel, dir: Direction) -> &Endpoint {
+        match dir {
+            Direction::Tx => &chan.remote,
+            Direction::Rx => &chan.local,
+        }
+    }
+
+    fn position(&self, index: u16, handle: u16, dir: Direction, cid: u16) -> Option<usize> {
+        self.open.iter().position(|c| (c.index, c.handle, Self::dest(c, dir).cid) == (index, handle, cid))
+    }
+
+    fn find_mut(&mut self, index: u16, handle: u16, dir: Direction, cid: u16) -> Option<&mut Channel> {
+        self.position(index, handle, dir, cid).map(|pos| &mut self.open[pos])
+    }
+
+    /// Follow a signaling command sent in `dir`
+    pub fn signal(&mut self, index: u16, handle: u16, dir: Direction, cmd: &Command) {
+        let req = (index, handle, dir, cmd.ident());
+        let rsp = (index, handle, reverse(dir), cmd.ident());
+
+        match cmd.param() {
+            Param::LeCreditConnReq { spsm, scid, mtu, mps, credits } => {
+                self.pending.insert(req, Pending::Connect {
+                    psm: *spsm, cids: vec![*scid], mtu: *mtu, mps: *mps, credits: *credits,
+                });
+            },
+            Param::CreditConnReq { spsm, mtu, mps, credits, scids } => {
+                self.pending.insert(req, Pending::Connect {
+                    psm: *spsm, cids: scids.clone(), mtu: *mtu, mps: *mps, credits: *credits,
+                });
+            },
+            Param::LeCreditConnRsp { dcid, mtu, mps, credits, result } => {
+                if let Some(Pending::Connect { psm, cids, mtu: req_mtu, mps: req_mps, credits: req_credits }) = self.pending.remove(&rsp) {
+                    if *result == 0x0000 {
+                        let requester = Endpoint { cid: cids[0], mtu: req_mtu, mps: req_mps, credits: req_credits };
+                        let responder = Endpoint { cid: *dcid, mtu: *mtu, mps: *mps, credits: *credits };
+                        self.open(index, handle, dir, psm, false, requester, responder);
+                    }
+                }
+            },
+            Param::CreditConnRsp { mtu, mps, credits, dcids, .. } => {
+                if let Some(Pending::Connect { psm, cids, mtu: req_mtu, mps: req_mps, credits: req_credits }) = self.pending.remove(&rsp) {
+                    // Refused channels have a zero destination CID
+                    for (scid, dcid) in cids.iter().zip(dcids).filter(|(_, dcid)| **dcid != 0) {
+                        let requester = Endpoint { cid: *scid, mtu: req_mtu, mps: req_mps, credits: req_credits };
+                        let responder = Endpoint { cid: *dcid, mtu: *mtu, mps: *mps, credits: *credits };
+                        self.open(index, handle, dir, psm, true, requester, responder);
+                    }
+                }
+            },
+            Param::CreditReconfigureReq { mtu, mps, dcids } => {
+                self.pending.insert(req, Pending::Reconfigure { cids: dcids.clone(), mtu: *mtu, mps: *mps });
+            },
+            Param::Result(result) if matches!(self.pending.get(&rsp), Some(Pending::Reconfigure { .. })) => {
+                let Some(Pending::Reconfigure { cids, mtu, mps }) = self.pending.remove(&rsp) else { return };
+                if *result != 0x0000 {
+                    return;
+                }
+                // The CIDs are those of the requester, which receives in the
+                // direction of this response
+                for cid in cids {
+                    if let Some(chan) = self.find_mut(index, handle, dir, cid) {
+                        let end = match dir {
+                            Direction::Tx => &mut chan.remote,
+                            Direction::Rx => &mut chan.local,
+                        };
+                        end.mtu = mtu;
+                        end.mps = mps;
+                    }
+                }
+            },
+            Param::FlowControlCredit { cid, credits } => {
+                // The CID is the sender's own, which receives in the other direction
+                if let Some(chan) = self.find_mut(index, handle, reverse(dir), *cid) {
+                    let end = match dir {
+                        Direction::Tx => &mut chan.local,
+                        Direction::Rx => &mut chan.remote,
+                    };
+                    end.credits = end.credits.saturating_add(*credits);
+                }
+            },
+            Param::Disconnection { dcid, scid } if cmd.code() == DISCONNECTION_RSP => {
+                // Destination CID belongs to the responder, i.e. the sender
+                let (local, remote) = match dir {
+                    Direction::Tx => (*dcid, *scid),
+                    Direction::Rx => (*scid, *dcid),
+                };
+                if let Some(pos) = self.open.iter().position(|c| {
+                    (c.index, c.handle, c.local.cid, c.remote.cid) == (index, handle, local, remote)
+                }) {
+                    let chan = self.open.remove(pos);
+                    self.partial.remove(&(index, handle, Direction::Tx, remote));
+                    self.partial.remove(&(index, handle, Direction::Rx, local));
+                    self.closed.push(chan);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// Add a channel once the response to `dir`'s peer's request is seen
+    #[allow(clippy::too_many_arguments)]
+    fn open(&mut self, index: u16, handle: u16, dir: Direction, psm: u16, enhanced: bool,
+            requester: Endpoint, responder: Endpoint) {
+        let (local, remote) = match dir {
+            Direction::Tx => (responder, requester),
+            Direction::Rx => (requester, responder),
+        };
+
+        self.open.push(Channel {
+            index, handle, psm, enhanced, local, remote,
+            tx: Counters::default(),
+            rx: Counters::default(),
+        });
+    }
+
+    /// Account a K-frame sent on channel `cid` in `dir` and reassemble it
+    /// into an SDU. Returns `None` unless the CID belongs to a known channel.
+    pub fn frame<'a>(&mut self, index: u16, handle: u16, dir: Direction, cid: u16, data: &'a [u8])
+                     -> Option<(&Channel, Sdu<'a>)> {
+        let pos = self.position(index, handle, dir, cid)?;
+        let key = (index, handle, dir, cid);
+
+        let sdu = match self.partial.get_mut(&key) {
+            None => match data {
+                [lo, hi, rest @ ..] => {
+                    let total = u16::from_le_bytes([*lo, *hi]) as usize;
+                    match rest.len().cmp(&total) {
+                        Ordering::Less => {
+                            self.partial.insert(key, PartialSdu { total, buf: rest.to_vec() });
+                            Sdu::Partial { len: rest.len(), total }
+                        },
+                        Ordering::Equal => Sdu::Complete(Cow::Borrowed(rest)),
+                        Ordering::Greater => Sdu::Overflow { extra: rest.len() - total },
+                    }
+                },
+                _ => Sdu::Truncated,
+            },
+            Some(PartialSdu { total, buf }) => {
+                buf.extend_from_slice(data);
+                let (total, len) = (*total, buf.len());
+                match len.cmp(&total) {
+                    Ordering::Less => Sdu::Partial { len, total },
+                    Ordering::Equal => Sdu::Complete(Cow::Owned(self.partial.remove(&key)?.buf)),
+                    Ordering::Greater => {
+                        self.partial.remove(&key);
+                        Sdu::Overflow { extra: len - total }
+                    },
+                }
+            },
+        };
+
+        let chan = &mut self.open[pos];
+        let (end, counters) = match dir {
+            Direction::Tx => (&mut chan.remote, &mut chan.tx),
+            Direction::Rx => (&mut chan.local, &mut chan.rx),
+        };
+        end.credits = end.credits.saturating_sub(1);
+        if let Sdu::Complete(data) = &sdu {
+            counters.packets += 1;
+            counters.bytes += data.len() as u64;
+        }
+
+        Some((&self.open[pos], sdu))
+    }
+
+    /// Close every channel of a connection, e.g. once it's disconnected
+    pub fn close(&mut self, index: u16, handle: u16) {
+        let (closed, open): (Vec<_>, _) = std::mem::take(&mut self.open).into_iter()
+            .partition(|c| (c.index, c.handle) == (index, handle));
+
+        self.open = open;
+        self.closed.extend(closed);
+        self.pending.retain(|(i, h, _, _), _| (*i, *h) != (index, handle));
+        self.partial.retain(|(i, h, _, _), _| (*i, *h) != (index, handle));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Channels, Sdu};
+    use crate::l2cap::sig::Command;
+    use crate::monitor::Direction;
+
+    fn signal(chans: &mut Channels, dir: Direction, data: &[u8]) {
+        let (_, cmd) = Command::parse(data).unwrap();
+        chans.signal(0, 0x0040, dir, &cmd);
+    }
+
+    #[test]
+    fn le_credit_based_channel() {
+        let mut chans = Channels::new();
+
+        // Request from the peer for SPSM 0x0025, Source CID 0x0041, MTU 100, MPS 23, 2 credits
+        signal(&mut chans, Direction::Rx, &[0x14, 0x01, 0x0a, 0x00, 0x25, 0x00, 0x41, 0x00, 0x64, 0x00, 0x17, 0x00, 0x02, 0x00]);
+        // Accepted with CID 0x0040, MTU 200, MPS 50, 1 credit
+        signal(&mut chans, Direction::Tx, &[0x15, 0x01, 0x0a, 0x00, 0x40, 0x00, 0xc8, 0x00, 0x32, 0x00, 0x01, 0x00, 0x00, 0x00]);
+
+        assert_eq!(chans.frame(0, 0x0040, Direction::Rx, 0x0041, &[0x00]), None);
+        let (chan, sdu) = chans.frame(0, 0x0040, Direction::Rx, 0x0040, &[0x04, 0x00, 0x01, 0x02]).unwrap();
+        assert_eq!((chan.psm, chan.remote.cid, chan.local.credits), (0x0025, 0x0041, 0));
+        assert_eq!(sdu, Sdu::Partial { len: 2, total: 4 });
+        let (_, sdu) = chans.frame(0, 0x0040, Direction::Rx, 0x0040, &[0x03, 0x04]).unwrap();
+        assert_eq!(sdu, Sdu::Complete(vec![0x01, 0x02, 0x03, 0x04].into()));
+        let (_, sdu) = chans.frame(0, 0x0040, Direction::Rx, 0x0040, &[0x01, 0x00, 0x01, 0x02]).unwrap();
+        assert_eq!(sdu, Sdu::Overflow { extra: 1 });
+        let (chan, sdu) = chans.frame(0, 0x0040, Direction::Rx, 0x0040, &[0x01, 0x00, 0x05]).unwrap();
+        assert_eq!(sdu, Sdu::Complete(vec![0x05].into()));
+        assert_eq!(chan.rx.packets, 2);
+
+        // Flow Control Credit from us for our CID
+        signal(&mut chans, Direction::Tx, &[0x16, 0x02, 0x04, 0x00, 0x40, 0x00, 0x05, 0x00]);
+        assert_eq!(chans.get(0, 0x0040, Direction::Rx, 0x0040).unwrap().local.credits, 5);
+
+        // Disconnection Request from the peer and our response
+        signal(&mut chans, Direction::Rx, &[0x06, 0x03, 0x04, 0x00, 0x40, 0x00, 0x41, 0x00]);
+        signal(&mut chans, Direction::Tx, &[0x07, 0x03, 0x04, 0x00, 0x40, 0x00, 0x41, 0x00]);
+        assert!(chans.get(0, 0x0040, Direction::Rx, 0x0040).is_none());
+        assert_eq!(chans.all().count(), 1);
+    }
+}
@@ -0,0 +1,515 @@
+use nom::{
+    bytes::complete::take,
+    combinator::{all_consuming, rest},
+    multi::many0,
+    number::complete::{le_u8, le_u16},
+    sequence::tuple,
+};
+use num_enum::{FromPrimitive, IntoPrimitive};
+use std::fmt;
+use crate::error::{self, Layer, PResult};
+use crate::hci::{field, ConnInterval, Timeout};
+use super::Psm;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, IntoPrimitive)]
+enum Code {
+    CommandReject          = 0x01,
+    ConnectionReq          = 0x02,
+    ConnectionRsp          = 0x03,
+    ConfigurationReq       = 0x04,
+    ConfigurationRsp       = 0x05,
+    DisconnectionReq       = 0x06,
+    DisconnectionRsp       = 0x07,
+    EchoReq                = 0x08,
+    EchoRsp                = 0x09,
+    InformationReq         = 0x0a,
+    InformationRsp         = 0x0b,
+    ConnParamUpdateReq     = 0x12,
+    ConnParamUpdateRsp     = 0x13,
+    LeCreditConnReq        = 0x14,
+    LeCreditConnRsp        = 0x15,
+    FlowControlCredit      = 0x16,
+    CreditConnReq          = 0x17,
+    CreditConnRsp          = 0x18,
+    CreditReconfigureReq   = 0x19,
+    CreditReconfigureRsp   = 0x1a,
+
+    #[num_enum(catch_all)]
+    Other(u8),
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Code::*;
+        let name = match self {
+            CommandReject        => "Command Reject",
+            ConnectionReq        => "Connection Request",
+            ConnectionRsp        => "Connection Response",
+            ConfigurationReq     => "Configuration Request",
+            ConfigurationRsp     => "Configuration Response",
+            DisconnectionReq     => "Disconnection Request",
+            DisconnectionRsp     => "Disconnection Response",
+            EchoReq              => "Echo Request",
+            EchoRsp              => "Echo Response",
+            InformationReq       => "Information Request",
+            InformationRsp       => "Information Response",
+            ConnParamUpdateReq   => "Connection Parameter Update Request",
+            ConnParamUpdateRsp   => "Connection Parameter Update Response",
+            LeCreditConnReq      => "LE Credit Based Connection Request",
+            LeCreditConnRsp      => "LE Credit Based Connection Response",
+            FlowControlCredit    => "Flow Control Credit Indication",
+            CreditConnReq        => "Credit Based Connection Request",
+            CreditConnRsp        => "Credit Based Connection Response",
+            CreditReconfigureReq => "Credit Based Reconfigure Request",
+            CreditReconfigureRsp => "Credit Based Reconfigure Response",
+            Other(_)             => "Unknown",
+        };
+
+        write!(f, "{} (0x{:02x})", name, u8::from(*self))
+    }
+}
+
+fn reject_str(reason: u16) -> &'static str {
+    match reason {
+        0x0000 => "Command not understood",
+        0x0001 => "Signaling MTU exceeded",
+        0x0002 => "Invalid CID in request",
+        _      => "Reserved",
+    }
+}
+
+fn connection_result_str(result: u16) -> &'static str {
+    match result {
+        0x0000 => "Connection successful",
+        0x0001 => "Connection pending",
+        0x0002 => "Connection refused - PSM not supported",
+        0x0003 => "Connection refused - security block",
+        0x0004 => "Connection refused - no resources available",
+        0x0006 => "Connection refused - invalid Source CID",
+        0x0007 => "Connection refused - Source CID already allocated",
+        _      => "Reserved",
+    }
+}
+
+fn connection_status_str(status: u16) -> &'static str {
+    match status {
+        0x0000 => "No further information available",
+        0x0001 => "Authentication pending",
+        0x0002 => "Authorization pending",
+        _      => "Reserved",
+    }
+}
+
+fn config_result_str(result: u16) -> &'static str {
+    match result {
+        0x0000 => "Success",
+        0x0001 => "Failure - unacceptable parameters",
+        0x0002 => "Failure - rejected",
+        0x0003 => "Failure - unknown options",
+        0x0004 => "Pending",
+        0x0005 => "Failure - flow spec rejected",
+        _      => "Reserved",
+    }
+}
+
+fn info_type_str(info_type: u16) -> &'static str {
+    match info_type {
+        0x0001 => "Connectionless MTU",
+        0x0002 => "Extended features supported",
+        0x0003 => "Fixed channels supported",
+        _      => "Reserved",
+    }
+}
+
+fn credit_result_str(result: u16) -> &'static str {
+    match result {
+        0x0000 => "Connection successful",
+        0x0002 => "Connection refused - SPSM not supported",
+        0x0004 => "Connection refused - no resources available",
+        0x0005 => "Connection refused - insufficient authentication",
+        0x0006 => "Connection refused - insufficient authorization",
+        0x0007 => "Connection refused - encryption key size too short",
+        0x0008 => "Connection refused - insufficient encryption",
+        0x0009 => "Connection refused - invalid Source CID",
+        0x000a => "Connection refused - Source CID already allocated",
+        0x000b => "Connection refused - unacceptable parameters",
+        0x000c => "Connection refused - invalid parameters",
+        0x000d => "Connection pending - no further information available",
+        0x000e => "Connection pending - authentication pending",
+        0x000f => "Connection pending - authorization pending",
+        _      => "Reserved",
+    }
+}
+
+fn reconfigure_result_str(result: u16) -> &'static str {
+    match result {
+        0x0000 => "Reconfiguration successful",
+        0x0001 => "Reconfiguration failed - reduction in size of MTU not allowed",
+        0x0002 => "Reconfiguration failed - reduction in size of MPS not allowed",
+        0x0003 => "Reconfiguration failed - one or more Destination CIDs invalid",
+        0x0004 => "Reconfiguration failed - unacceptable parameters",
+        _      => "Reserved",
+    }
+}
+
+fn mode_str(mode: u8) -> &'static str {
+    match mode {
+        0x00 => "Basic",
+        0x01 => "Retransmission",
+        0x02 => "Flow control",
+        0x03 => "Enhanced Retransmission",
+        0x04 => "Streaming",
+        _    => "Reserved",
+    }
+}
+
+/// Option carried by Configuration Request/Response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigOption<'a> {
+    Mtu(u16),
+    FlushTimeout(u16),
+    RetransmissionFlowControl {
+        mode: u8,
+        tx_window: u8,
+        max_transmit: u8,
+        retransmission_timeout: u16,
+        monitor_timeout: u16,
+        mps: u16,
+    },
+    Fcs(u8),
+    ExtendedWindowSize(u16),
+    /// Any other option, or one whose length doesn't match its type
+    Other { kind: u8, data: &'a [u8] },
+}
+
+fn config_option(i: &[u8]) -> PResult<'_, ConfigOption<'_>> {
+    let (i, (kind, len)) = tuple((le_u8, le_u8))(i)?;
+    let (i, data) = take(len)(i)?;
+
+    // Bit 7 only marks the option as a hint
+    let option: PResult<'_, ConfigOption<'_>> = match kind & 0x7f {
+        0x01 => all_consuming(le_u16)(data).map(|(i, mtu)| (i, ConfigOption::Mtu(mtu))),
+        0x02 => all_consuming(le_u16)(data).map(|(i, t)| (i, ConfigOption::FlushTimeout(t))),
+        0x04 => all_consuming(tuple((le_u8, le_u8, le_u8, le_u16, le_u16, le_u16)))(data)
+            .map(|(i, (mode, tx_window, max_transmit, retransmission_timeout, monitor_timeout, mps))| {
+                (i, ConfigOption::RetransmissionFlowControl {
+                    mode, tx_window, max_transmit, retransmission_timeout, monitor_timeout, mps,
+                })
+            }),
+        0x05 => all_consuming(le_u8)(data).map(|(i, fcs)| (i, ConfigOption::Fcs(fcs))),
+        0x07 => all_consuming(le_u16)(data).map(|(i, size)| (i, ConfigOption::ExtendedWindowSize(size))),
+        _ => Ok((data, ConfigOption::Other { kind, data })),
+    };
+
+    Ok((i, option.map_or(ConfigOption::Other { kind, data }, |(_, option)| option)))
+}
+
+impl fmt::Display for ConfigOption<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ConfigOption::*;
+        match self {
+            Mtu(mtu) => write!(f, "MTU {}", mtu),
+            FlushTimeout(0xffff) => write!(f, "Flush timeout infinite (0xffff)"),
+            FlushTimeout(t) => write!(f, "Flush timeout {} msec", t),
+            RetransmissionFlowControl { mode, tx_window, max_transmit, retransmission_timeout, monitor_timeout, mps } => {
+                write!(f, "{} mode (0x{:02x}), TX window {}, max transmit {}, retransmission timeout {} msec, \
+                           monitor timeout {} msec, MPS {}",
+                       mode_str(*mode), mode, tx_window, max_transmit, retransmission_timeout, monitor_timeout, mps)
+            },
+            Fcs(0x00) => write!(f, "FCS none (0x00)"),
+            Fcs(fcs) => write!(f, "FCS 16-bit (0x{:02x})", fcs),
+            ExtendedWindowSize(size) => write!(f, "Extended window size {}", size),
+            Other { kind, data } => write!(f, "Option 0x{:02x} {:02x?}", kind, data),
+        }
+    }
+}
+
+/// Decoded parameters of a signaling command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Param<'a> {
+    CommandReject { reason: u16, data: &'a [u8] },
+    ConnectionReq { psm: u16, scid: u16 },
+    ConnectionRsp { dcid: u16, scid: u16, result: u16, status: u16 },
+    ConfigurationReq { dcid: u16, flags: u16, options: Vec<ConfigOption<'a>> },
+    ConfigurationRsp { scid: u16, flags: u16, result: u16, options: Vec<ConfigOption<'a>> },
+    /// Disconnection Request and Response
+    Disconnection { dcid: u16, scid: u16 },
+    Echo(&'a [u8]),
+    InformationReq { info_type: u16 },
+    InformationRsp { info_type: u16, result: u16, data: &'a [u8] },
+    ConnParamUpdateReq { min_interval: u16, max_interval: u16, latency: u16, timeout: u16 },
+    /// Connection Parameter Update Response and Credit Based Reconfigure Response
+    Result(u16),
+    LeCreditConnReq { spsm: u16, scid: u16, mtu: u16, mps: u16, credits: u16 },
+    LeCreditConnRsp { dcid: u16, mtu: u16, mps: u16, credits: u16, result: u16 },
+    FlowControlCredit { cid: u16, credits: u16 },
+    CreditConnReq { spsm: u16, mtu: u16, mps: u16, credits: u16, scids: Vec<u16> },
+    CreditConnRsp { mtu: u16, mps: u16, credits: u16, result: u16, dcids: Vec<u16> },
+    CreditReconfigureReq { mtu: u16, mps: u16, dcids: Vec<u16> },
+    Unknown(&'a [u8]),
+}
+
+/// One command on the BR/EDR or LE signaling channel
+pub struct Command <'a> {
+    code: Code,
+    ident: u8,
+    param: Param<'a>,
+}
+
+fn param(code: Code, i: &[u8]) -> PResult<'_, Param<'_>> {
+    use Code::*;
+
+    match code {
+        CommandReject => {
+            let (i, (reason, data)) = tuple((le_u16, rest))(i)?;
+            Ok((i, Param::CommandReject { reason, data }))
+        },
+        ConnectionReq => {
+            let (i, (psm, scid)) = tuple((le_u16, le_u16))(i)?;
+            Ok((i, Param::ConnectionReq { psm, scid }))
+        },
+        ConnectionRsp => {
+            let (i, (dcid, scid, result, status)) = tuple((le_u16, le_u16, le_u16, le_u16))(i)?;
+            Ok((i, Param::ConnectionRsp { dcid, scid, result, status }))
+        },
+        ConfigurationReq => {
+            let (i, (dcid, flags, options)) = tuple((le_u16, le_u16, many0(config_option)))(i)?;
+            Ok((i, Param::ConfigurationReq { dcid, flags, options }))
+        },
+        ConfigurationRsp => {
+            let (i, (scid, flags, result, options)) = tuple((le_u16, le_u16, le_u16, many0(config_option)))(i)?;
+            Ok((i, Param::ConfigurationRsp { scid, flags, result, options }))
+        },
+        DisconnectionReq | DisconnectionRsp => {
+            let (i, (dcid, scid)) = tuple((le_u16, le_u16))(i)?;
+            Ok((i, Param::Disconnection { dcid, scid }))
+        },
+        EchoReq | EchoRsp => {
+            let (i, data) = rest(i)?;
+            Ok((i, Param::Echo(data)))
+        },
+        InformationReq => {
+            let (i, info_type) = le_u16(i)?;
+            Ok((i, Param::InformationReq { info_type }))
+        },
+        InformationRsp => {
+            let (i, (info_type, result, data)) = tuple((le_u16, le_u16, rest))(i)?;
+            Ok((i, Param::InformationRsp { info_type, result, data }))
+        },
+        ConnParamUpdateReq => {
+            let (i, (min_interval, max_interval, latency, timeout)) = tuple((le_u16, le_u16, le_u16, le_u16))(i)?;
+            Ok((i, Param::ConnParamUpdateReq { min_interval, max_interval, latency, timeout }))
+        },
+        ConnParamUpdateRsp | CreditReconfigureRsp => {
+            let (i, result) = le_u16(i)?;
+            Ok((i, Param::Result(result)))
+        },
+        LeCreditConnReq => {
+            let (i, (spsm, scid, mtu, mps, credits)) = tuple((le_u16, le_u16, le_u16, le_u16, le_u16))(i)?;
+            Ok((i, Param::LeCreditConnReq { spsm, scid, mtu, mps, credits }))
+        },
+        LeCreditConnRsp => {
+            let (i, (dcid, mtu, mps, credits, result)) = tuple((le_u16, le_u16, le_u16, le_u16, le_u16))(i)?;
+            Ok((i, Param::LeCreditConnRsp { dcid, mtu, mps, credits, result }))
+        },
+        FlowControlCredit => {
+            let (i, (cid, credits)) = tuple((le_u16, le_u16))(i)?;
+            Ok((i, Param::FlowControlCredit { cid, credits }))
+        },
+        CreditConnReq => {
+            let (i, (spsm, mtu, mps, credits, scids)) = tuple((le_u16, le_u16, le_u16, le_u16, many0(le_u16)))(i)?;
+            Ok((i, Param::CreditConnReq { spsm, mtu, mps, credits, scids }))
+        },
+        CreditConnRsp => {
+            let (i, (mtu, mps, credits, result, dcids)) = tuple((le_u16, le_u16, le_u16, le_u16, many0(le_u16)))(i)?;
+            Ok((i, Param::CreditConnRsp { mtu, mps, credits, result, dcids }))
+        },
+        CreditReconfigureReq => {
+            let (i, (mtu, mps, dcids)) = tuple((le_u16, le_u16, many0(le_u16)))(i)?;
+            Ok((i, Param::CreditReconfigureReq { mtu, mps, dcids }))
+        },
+        Other(_) => Ok((&i[i.len()..], Param::Unknown(i))),
+    }
+}
+
+impl<'a> Command<'a> {
+    fn parser(input: &'a [u8]) -> PResult<'a, Command<'a>> {
+        let (i, (code, ident, len)) = tuple((le_u8, le_u8, le_u16))(input)?;
+        let (rem, data) = take(len)(i)?;
+        let code = Code::from(code);
+        let (_, param) = all_consuming(|i| param(code, i))(data)?;
+
+        Ok((rem, Command { code, ident, param }))
+    }
+
+    /// Parse one command. The BR/EDR signaling channel may carry several
+    /// commands in a frame, which are left in the returned remainder.
+    pub fn parse(input: &'a [u8]) -> error::Result<(&'a [u8], Command<'a>)> {
+        error::finish(Layer::L2cap, input, Command::parser(input))
+    }
+
+    pub fn code(&self) -> u8 {
+        u8::from(self.code)
+    }
+
+    pub fn ident(&self) -> u8 {
+        self.ident
+    }
+
+    pub fn param(&self) -> &Param<'a> {
+        &self.param
+    }
+}
+
+fn cids(f: &mut fmt::Formatter<'_>, name: &str, cids: &[u16]) -> fmt::Result {
+    let cids: Vec<_> = cids.iter().map(|cid| format!("0x{:04x}", cid)).collect();
+    field(f, name, cids.join(", "))
+}
+
+impl fmt::Display for Command<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ident {}", self.code, self.ident)?;
+
+        match &self.param {
+            Param::CommandReject { reason, data } => {
+                field(f, "Reason", format!("{} (0x{:04x})", reject_str(*reason), reason))?;
+                match (reason, *data) {
+                    (0x0001, [lo, hi]) => field(f, "Signaling MTU", u16::from_le_bytes([*lo, *hi])),
+                    (0x0002, [l0, l1, r0, r1]) => {
+                        field(f, "Local CID", format!("0x{:04x}", u16::from_le_bytes([*l0, *l1])))?;
+                        field(f, "Remote CID", format!("0x{:04x}", u16::from_le_bytes([*r0, *r1])))
+                    },
+                    (_, []) => Ok(()),
+                    (_, data) => field(f, "Data", format!("{:02x?}", data)),
+                }
+            },
+            Param::ConnectionReq { psm, scid } => {
+                field(f, "PSM", Psm(*psm))?;
+                field(f, "Source CID", format!("0x{:04x}", scid))
+            },
+            Param::ConnectionRsp { dcid, scid, result, status } => {
+                field(f, "Destination CID", format!("0x{:04x}", dcid))?;
+                field(f, "Source CID", format!("0x{:04x}", scid))?;
+                field(f, "Result", format!("{} (0x{:04x})", connection_result_str(*result), result))?;
+                field(f, "Status", format!("{} (0x{:04x})", connection_status_str(*status), status))
+            },
+            Param::ConfigurationReq { dcid, flags, options } => {
+                field(f, "Destination CID", format!("0x{:04x}", dcid))?;
+                field(f, "Flags", format!("0x{:04x}", flags))?;
+                options.iter().try_for_each(|option| field(f, "Option", option))
+            },
+            Param::ConfigurationRsp { scid, flags, result, options } => {
+                field(f, "Source CID", format!("0x{:04x}", scid))?;
+                field(f, "Flags", format!("0x{:04x}", flags))?;
+                field(f, "Result", format!("{} (0x{:04x})", config_result_str(*result), result))?;
+                options.iter().try_for_each(|option| field(f, "Option", option))
+            },
+            Param::Disconnection { dcid, scid } => {
+                field(f, "Destination CID", format!("0x{:04x}", dcid))?;
+                field(f, "Source CID", format!("0x{:04x}", scid))
+            },
+            Param::Echo(data) => match data.is_empty() {
+                true  => Ok(()),
+                false => field(f, "Data", format!("{:02x?}", data)),
+            },
+            Param::InformationReq { info_type } => {
+                field(f, "Type", format!("{} (0x{:04x})", info_type_str(*info_type), info_type))
+            },
+            Param::InformationRsp { info_type, result, data } => {
+                field(f, "Type", format!("{} (0x{:04x})", info_type_str(*info_type), info_type))?;
+                let result_str = match result {
+                    0x0000 => "Success",
+                    0x0001 => "Not supported",
+                    _      => "Reserved",
+                };
+                field(f, "Result", format!("{} (0x{:04x})", result_str, result))?;
+                match (info_type, *result, *data) {
+                    (_, _, []) => Ok(()),
+                    (0x0001, 0x0000, &[lo, hi]) => field(f, "MTU", u16::from_le_bytes([lo, hi])),
+                    (0x0002, 0x0000, &[b0, b1, b2, b3]) => {
+                        field(f, "Features", format!("0x{:08x}", u32::from_le_bytes([b0, b1, b2, b3])))
+                    },
+                    (0x0003, 0x0000, &[b0, b1, b2, b3, b4, b5, b6, b7]) => {
+                        field(f, "Channels", format!("0x{:016x}", u64::from_le_bytes([b0, b1, b2, b3, b4, b5, b6, b7])))
+                    },
+                    (_, _, data) => field(f, "Data", format!("{:02x?}", data)),
+                }
+            },
+            Param::ConnParamUpdateReq { min_interval, max_interval, latency, timeout } => {
+                field(f, "Min connection interval", ConnInterval(*min_interval))?;
+                field(f, "Max connection interval", ConnInterval(*max_interval))?;
+                field(f, "Connection latency", latency)?;
+                field(f, "Supervision timeout", Timeout(*timeout))
+            },
+            Param::Result(result) => {
+                let result_str = match self.code {
+                    Code::CreditReconfigureRsp => reconfigure_result_str(*result),
+                    _ => match result {
+                        0x0000 => "Connection Parameters accepted",
+                        0x0001 => "Connection Parameters rejected",
+                        _      => "Reserved",
+                    },
+                };
+                field(f, "Result", format!("{} (0x{:04x})", result_str, result))
+            },
+            Param::LeCreditConnReq { spsm, scid, mtu, mps, credits } => {
+                field(f, "SPSM", Psm(*spsm))?;
+                field(f, "Source CID", format!("0x{:04x}", scid))?;
+                field(f, "MTU", mtu)?;
+                field(f, "MPS", mps)?;
+                field(f, "Credits", credits)
+            },
+            Param::LeCreditConnRsp { dcid, mtu, mps, credits, result } => {
+                field(f, "Destination CID", format!("0x{:04x}", dcid))?;
+                field(f, "MTU", mtu)?;
+                field(f, "MPS", mps)?;
+                field(f, "Credits", credits)?;
+                field(f, "Result", format!("{} (0x{:04x})", credit_result_str(*result), result))
+            },
+            Param::FlowControlCredit { cid, credits } => {
+                field(f, "CID", format!("0x{:04x}", cid))?;
+                field(f, "Credits", credits)
+            },
+            Param::CreditConnReq { spsm, mtu, mps, credits, scids } => {
+                field(f, "SPSM", Psm(*spsm))?;
+                field(f, "MTU", mtu)?;
+                field(f, "MPS", mps)?;
+                field(f, "Credits", credits)?;
+                cids(f, "Source CIDs", scids)
+            },
+            Param::CreditConnRsp { mtu, mps, credits, result, dcids } => {
+                field(f, "MTU", mtu)?;
+                field(f, "MPS", mps)?;
+                field(f, "Credits", credits)?;
+                field(f, "Result", format!("{} (0x{:04x})", credit_result_str(*result), result))?;
+                cids(f, "Destination CIDs", dcids)
+            },
+            Param::CreditReconfigureReq { mtu, mps, dcids } => {
+                field(f, "MTU", mtu)?;
+                field(f, "MPS", mps)?;
+                cids(f, "Destination CIDs", dcids)
+            },
+            Param::Unknown(data) => write!(f, " {:02x?}", data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Command;
+
+    #[test]
+    fn configuration_request() {
+        let data = [0x04, 0x03, 0x0c, 0x00, 0x41, 0x00, 0x00, 0x00, 0x01, 0x02, 0xa0, 0x02, 0x85, 0x02, 0x00, 0x00];
+        let (rem, cmd) = Command::parse(&data).unwrap();
+
+        assert!(rem.is_empty());
+        assert_eq!(cmd.to_string(), "Configuration Request (0x04) ident 3\
+            \n        Destination CID: 0x0041\
+            \n        Flags: 0x0000\
+            \n        Option: MTU 672\
+            \n        Option: Option 0x85 [00, 00]");
+        assert!(Command::parse(&[0x13, 0x01, 0x01, 0x00, 0x00]).is_err());
+    }
+}
@@ -3,3 +3,18 @@ pub mod monitor;
 pub mod hci;
 pub mod l2cap;
 pub mod att;
+pub mod smp;
+pub mod iso;
+pub mod sco;
+pub mod conn;
+pub mod decoder;
+pub mod btsnoop;
+pub mod pcapng;
+pub mod pcap;
+pub mod wav;
+pub mod export;
+pub mod error;
+#[cfg(feature = "tokio")]
+pub mod stream;
+
+pub use error::{Error, Result};
@@ -1,57 +1,115 @@
 use clap::Parser;
 use std::time::Duration;
-use std::io::Read;
+use std::io::{self, Read, Write};
+use std::fs::File;
 use std::{fmt, str};
 use probe_rs::{Core, rtt::UpChannel};
-use btmon::tty;
+use btmon::{tty, btsnoop, pcap, pcapng, monitor, decoder::Decoder, export::Export};
 
-const BUF_SIZE: usize = 2048;    // Size of buffer to read data into
-const PKT_MAX: usize = 1486 + 4; // Maximum BTSnoop packet size
-const MIN_LEN: usize = 6;        // Minumum length for a valid header
+enum Output {
+    Btsnoop(btsnoop::Writer<File>),
+    Pcapng(pcapng::Writer<File>),
+    Pcap(pcap::Writer<File>),
+}
+
+impl Output {
+    fn write_packet(&mut self, pkt: &monitor::Packet) {
+        match self {
+            Output::Btsnoop(w) => w.write_packet(pkt).expect("Unable to write BTSnoop file"),
+            Output::Pcapng(w) => w.write_packet(pkt).expect("Unable to write pcapng file"),
+            Output::Pcap(w) => w.write_packet(pkt).expect("Unable to write pcap file"),
+        }
+    }
+}
 
-fn process_data(mut source: impl Read + std::fmt::Debug) {
-    let mut buf = vec![0u8; BUF_SIZE];
-    let mut len = 0usize;
-    let mut offset = 0usize;
+/// Print what the decoder learned over the whole capture
+fn print_summary(decoder: &Decoder) {
+    for conn in decoder.connections().all() {
+        println!("{}", conn);
+    }
+    for chan in decoder.channels().all() {
+        println!("{}", chan);
+    }
+    for conn in decoder.sync_connections().all() {
+        println!("{}", conn);
+    }
+    for stream in decoder.streams().all() {
+        println!("{}", stream);
+    }
+    for db in decoder.gatt().databases() {
+        println!("{}", db);
+    }
+}
 
-    println!("{:?}", source);
+fn process_data(source: impl Read + std::fmt::Debug, mut out: Vec<Output>, mut decoder: Decoder, quiet: bool) {
+    // Stdout is reserved for control output when running as an extcap
+    eprintln!("{:?}", source);
 
-    loop {
-        if offset > (BUF_SIZE - PKT_MAX) {
-            buf.rotate_left(offset);
-            offset = 0;
-        }
+    let mut reader = tty::Reader::new(source);
 
-        len += source.read(&mut buf[(offset + len)..])
-            .expect("Unable to read from serial port");
+    for pkt in reader.by_ref() {
+        let pkt = match pkt {
+            Ok(pkt) => pkt,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            },
+        };
+
+        let line = decoder.decode(&pkt);
+
+        if !quiet {
+            println!("{}\t{}", pkt.ts, line);
+        }
 
-        // Discard garbage zero bytes which may show up on the UART
-        if len > 0 && buf[offset] == b'\0' {
-            offset +=1;
-            len -= 1;
+        for o in out.iter_mut() {
+            o.write_packet(&pkt);
         }
+    }
 
-        let mut data = &buf[offset..(offset + len)];
+    decoder.finish().expect("Unable to finish exported files");
 
-        loop {
-            let pkt: btmon::monitor::Packet;
+    if quiet {
+        return;
+    }
+
+    print_summary(&decoder);
+
+    if reader.skipped() > 0 {
+        println!("Skipped {} bytes of garbage", reader.skipped());
+    }
+    if reader.drops() != tty::Drops::default() {
+        println!("Controller reported drops: {:?}", reader.drops());
+    }
+}
 
-            if len < MIN_LEN {
+fn read_btsnoop(path: std::path::PathBuf, mut out: Vec<Output>, mut decoder: Decoder) {
+    let buf = std::fs::read(&path).expect("Unable to read BTSnoop file");
+    let (mut data, hdr) = btsnoop::parse_header(&buf).expect("Not a BTSnoop file");
+
+    println!("{} version {} datalink {:?}", path.to_string_lossy(), hdr.version, hdr.datalink);
+
+    while !data.is_empty() {
+        let pkt: btmon::monitor::Packet;
+
+        (data, pkt) = match btsnoop::parse_record(hdr.datalink, data) {
+            Ok(v) => v,
+            Err(e) => {
+                println!("Failed to parse record: {}", e);
                 break;
-            }
+            },
+        };
 
-            (data, pkt) = match tty::parse_data(data) {
-                Ok(v) => v,
-                Err(_) => {
-                    offset += len - data.len();
-                    len = data.len();
-                    break;
-                },
-            };
+        println!("{}\t{}", pkt.ts, decoder.decode(&pkt));
 
-            println!("{}\t{}", pkt.ts, pkt.op);
+        for o in out.iter_mut() {
+            o.write_packet(&pkt);
         }
     }
+
+    decoder.finish().expect("Unable to finish exported files");
+
+    print_summary(&decoder);
 }
 
 fn open_tty(tty: std::path::PathBuf, tty_speed: u32) -> impl Read + std::fmt::Debug {
@@ -61,7 +119,7 @@ fn open_tty(tty: std::path::PathBuf, tty_speed: u32) -> impl Read + std::fmt::De
         .open()
         .expect("Failed to open TTY");
 
-    println!("Successfully opened {} with speed {}", tty.to_string_lossy(), tty_speed);
+    eprintln!("Successfully opened {} with speed {}", tty.to_string_lossy(), tty_speed);
 
     port
 }
@@ -79,13 +137,100 @@ impl fmt::Debug for UpChannelReader<'_> {
 
 impl Read for UpChannelReader <'_> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        match self.chan.read(&mut self.core, buf) {
-            Ok(len) => Ok(len),
-            Err(_) => Err(std::io::Error::last_os_error()),
+        // Poll until there's data, since returning 0 would signal end of file
+        loop {
+            match self.chan.read(&mut self.core, buf) {
+                Ok(0) => std::thread::sleep(Duration::from_millis(1)),
+                Ok(len) => return Ok(len),
+                Err(_) => return Err(std::io::Error::last_os_error()),
+            }
         }
     }
 }
 
+fn capture_rtt(target: String, rtt_chan: usize, out: Vec<Output>, decoder: Decoder, quiet: bool) {
+    use probe_rs::{
+        Permissions,
+        rtt::{Rtt, ScanRegion},
+        config::TargetSelector,
+        probe::list::Lister,
+    };
+
+    let lister = Lister::new();
+    let probes = lister.list_all();
+    let probe = probes[0].open().unwrap();
+
+    let target_selector = TargetSelector::from(target);
+    let mut session = probe.attach(target_selector, Permissions::default()).unwrap();
+
+    let mut core = session.core(0).expect("Error attaching to core # 0");
+
+    eprintln!("Attaching to RTT...");
+
+    let mut rtt =
+        Rtt::attach_region(&mut core, &ScanRegion::Ram).expect("Error attaching to RTT");
+
+    eprintln!("Found control block at {:#010x}", rtt.ptr());
+
+    let chan = rtt.up_channel(rtt_chan).unwrap();
+
+    let reader = UpChannelReader {
+        core,
+        chan,
+    };
+
+    process_data(reader, out, decoder, quiet);
+}
+
+const EXTCAP_TTY: &str = "btmon-tty";
+const EXTCAP_RTT: &str = "btmon-rtt";
+
+fn extcap_interfaces(out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "extcap {{version={}}}{{help=https://github.com/jhedberg/btmon}}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(out, "interface {{value={}}}{{display=Bluetooth monitor (UART)}}", EXTCAP_TTY)?;
+    writeln!(out, "interface {{value={}}}{{display=Bluetooth monitor (RTT)}}", EXTCAP_RTT)
+}
+
+fn extcap_dlts(out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "dlt {{number=254}}{{name=BLUETOOTH_LINUX_MONITOR}}{{display=Bluetooth Linux Monitor}}")
+}
+
+fn extcap_config(out: &mut impl Write, iface: &str) -> io::Result<()> {
+    match iface {
+        EXTCAP_TTY => {
+            writeln!(out, "arg {{number=0}}{{call=--tty}}{{display=Serial port}}{{type=string}}{{required=true}}")?;
+            writeln!(out, "arg {{number=1}}{{call=--tty-speed}}{{display=Baud rate}}{{type=integer}}{{default=115200}}")
+        },
+        EXTCAP_RTT => {
+            writeln!(out, "arg {{number=0}}{{call=--rtt}}{{display=Target chip}}{{type=string}}{{required=true}}")?;
+            writeln!(out, "arg {{number=1}}{{call=--rtt-chan}}{{display=RTT channel}}{{type=integer}}{{default=0}}")
+        },
+        _ => {
+            eprintln!("Unknown extcap interface {}", iface);
+            Ok(())
+        },
+    }
+}
+
+fn extcap_capture(opts: Opts) {
+    let iface = opts.extcap_interface.expect("No extcap interface given");
+    let fifo = opts.fifo.expect("No fifo given");
+    let file = File::create(fifo).expect("Unable to open fifo");
+    let out = vec![Output::Pcap(pcap::Writer::new(file).expect("Unable to write pcap header"))];
+
+    match iface.as_str() {
+        EXTCAP_TTY => {
+            let tty = opts.tty.expect("No serial port given");
+            process_data(open_tty(tty, opts.tty_speed), out, Decoder::new(), true);
+        },
+        EXTCAP_RTT => {
+            let target = opts.rtt.expect("No target chip given");
+            capture_rtt(target, opts.rtt_chan, out, Decoder::new(), true);
+        },
+        _ => eprintln!("Unknown extcap interface {}", iface),
+    }
+}
+
 #[derive(Parser)]
 struct Opts {
     #[arg(long)]
@@ -99,44 +244,130 @@ struct Opts {
 
     #[arg(long, default_value_t = 0)]
     rtt_chan: usize,
+
+    /// Read packets from a BTSnoop file
+    #[arg(long, short)]
+    read: Option<std::path::PathBuf>,
+
+    /// Save captured packets to a BTSnoop file
+    #[arg(long, short)]
+    write: Option<std::path::PathBuf>,
+
+    /// Save captured packets to a pcapng file
+    #[arg(long)]
+    pcapng: Option<std::path::PathBuf>,
+
+    /// Export the SCO and ISO payloads of each connection handle into files
+    /// in the given directory
+    #[arg(long)]
+    export: Option<std::path::PathBuf>,
+
+    /// Write transparent SCO data as 16-bit PCM at this sample rate into WAV
+    /// files, instead of raw frames
+    #[arg(long, requires = "export")]
+    export_pcm_rate: Option<u32>,
+
+    /// List the Wireshark extcap interfaces
+    #[arg(long)]
+    extcap_interfaces: bool,
+
+    /// List the data link types of an extcap interface
+    #[arg(long)]
+    extcap_dlts: bool,
+
+    /// List the configuration options of an extcap interface
+    #[arg(long)]
+    extcap_config: bool,
+
+    #[arg(long)]
+    extcap_interface: Option<String>,
+
+    #[arg(long)]
+    extcap_version: Option<String>,
+
+    #[arg(long)]
+    extcap_capture_filter: Option<String>,
+
+    /// Start an extcap capture into the given fifo
+    #[arg(long)]
+    capture: bool,
+
+    #[arg(long)]
+    fifo: Option<std::path::PathBuf>,
 }
 
 pub fn main() {
     let opts = Opts::parse();
 
-    if let Some(tty) = opts.tty {
-        process_data(open_tty(tty, opts.tty_speed));
-    } else if let Some(target) = opts.rtt {
-        use probe_rs::{
-            Permissions,
-            rtt::{Rtt, ScanRegion},
-            config::TargetSelector,
-            probe::list::Lister,
-        };
+    if opts.extcap_interfaces {
+        extcap_interfaces(&mut io::stdout()).expect("Unable to write extcap interfaces");
+        return;
+    } else if opts.extcap_dlts {
+        extcap_dlts(&mut io::stdout()).expect("Unable to write extcap DLTs");
+        return;
+    } else if opts.extcap_config {
+        extcap_config(&mut io::stdout(), opts.extcap_interface.as_deref().unwrap_or_default())
+            .expect("Unable to write extcap config");
+        return;
+    } else if opts.capture {
+        extcap_capture(opts);
+        return;
+    }
 
-        let lister = Lister::new();
-        let probes = lister.list_all();
-        let probe = probes[0].open().unwrap();
+    let mut out = Vec::new();
 
-        let target_selector = TargetSelector::from(target);
-        let mut session = probe.attach(target_selector, Permissions::default()).unwrap();
+    if let Some(path) = opts.write {
+        let file = File::create(path).expect("Unable to create BTSnoop file");
+        out.push(Output::Btsnoop(btsnoop::Writer::new(file).expect("Unable to write BTSnoop header")));
+    }
 
-        let mut core = session.core(0).expect("Error attaching to core # 0");
+    if let Some(path) = opts.pcapng {
+        let file = File::create(path).expect("Unable to create pcapng file");
+        out.push(Output::Pcapng(pcapng::Writer::new(file).expect("Unable to write pcapng header")));
+    }
 
-        println!("Attaching to RTT...");
+    let mut decoder = Decoder::new();
 
-        let mut rtt =
-            Rtt::attach_region(&mut core, &ScanRegion::Ram).expect("Error attaching to RTT");
+    if let Some(dir) = opts.export {
+        decoder.set_export(Export::new(dir, opts.export_pcm_rate).expect("Unable to create export directory"));
+    }
 
-        println!("Found control block at {:#010x}", rtt.ptr());
+    if let Some(path) = opts.read {
+        read_btsnoop(path, out, decoder);
+        return;
+    }
 
-        let chan = rtt.up_channel(opts.rtt_chan).unwrap();
+    if let Some(tty) = opts.tty {
+        process_data(open_tty(tty, opts.tty_speed), out, decoder, false);
+    } else if let Some(target) = opts.rtt {
+        capture_rtt(target, opts.rtt_chan, out, decoder, false);
+    }
+}
 
-        let reader = UpChannelReader {
-            core,
-            chan,
-        };
+#[cfg(test)]
+mod tests {
+    use super::{extcap_config, extcap_dlts, extcap_interfaces, EXTCAP_RTT, EXTCAP_TTY};
+
+    fn output(f: impl FnOnce(&mut Vec<u8>) -> std::io::Result<()>) -> String {
+        let mut out = Vec::new();
+        f(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
 
-        process_data(reader);
+    #[test]
+    fn extcap() {
+        assert_eq!(output(extcap_interfaces), format!(
+            "extcap {{version={}}}{{help=https://github.com/jhedberg/btmon}}\n\
+             interface {{value=btmon-tty}}{{display=Bluetooth monitor (UART)}}\n\
+             interface {{value=btmon-rtt}}{{display=Bluetooth monitor (RTT)}}\n", env!("CARGO_PKG_VERSION")));
+        assert_eq!(output(extcap_dlts),
+                   "dlt {number=254}{name=BLUETOOTH_LINUX_MONITOR}{display=Bluetooth Linux Monitor}\n");
+        assert_eq!(output(|o| extcap_config(o, EXTCAP_TTY)),
+                   "arg {number=0}{call=--tty}{display=Serial port}{type=string}{required=true}\n\
+                    arg {number=1}{call=--tty-speed}{display=Baud rate}{type=integer}{default=115200}\n");
+        assert_eq!(output(|o| extcap_config(o, EXTCAP_RTT)),
+                   "arg {number=0}{call=--rtt}{display=Target chip}{type=string}{required=true}\n\
+                    arg {number=1}{call=--rtt-chan}{display=RTT channel}{type=integer}{default=0}\n");
+        assert_eq!(output(|o| extcap_config(o, "btmon-foo")), "");
     }
 }
@@ -1,12 +1,16 @@
-use std::{fmt, str};
-use nom::{IResult, sequence::tuple, bytes, number::complete::{le_u8, le_u16}, multi::length_data};
+use std::{fmt, str, borrow::Cow};
+use nom::{sequence::tuple, bytes, number::complete::{le_u8, le_u16}, multi::length_data};
 use time::Time;
 use num_enum::FromPrimitive;
 use crate::hci;
 use crate::l2cap;
+use crate::iso;
+use crate::sco;
+use crate::error::{self, Kind, Layer, PResult};
 
 #[repr(u8)]
-#[derive(Debug, Eq, PartialEq, FromPrimitive)]
+#[derive(Debug, Clone, Eq, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IndexType {
     Primary,
     Amp,
@@ -16,7 +20,8 @@ pub enum IndexType {
 }
 
 #[repr(u8)]
-#[derive(Debug, Eq, PartialEq, FromPrimitive)]
+#[derive(Debug, Clone, Eq, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum IndexBus {
     Virtual,
     Usb,
@@ -35,46 +40,69 @@ pub enum IndexBus {
     Unknown(u8),
 }
 
-#[derive(Debug, Eq, PartialEq)]
-pub struct BdAddr <'a> {
-    val: &'a[u8],
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BdAddr {
+    val: [u8; 6],
 }
 
-impl fmt::Display for BdAddr<'_> {
+impl BdAddr {
+    pub(crate) fn parse(data: &[u8]) -> PResult<'_, BdAddr> {
+        let (data, val) = bytes::complete::take(6usize)(data)?;
+        Ok((data, BdAddr { val: val.try_into().unwrap() }))
+    }
+}
+
+impl From<[u8; 6]> for BdAddr {
+    fn from(val: [u8; 6]) -> Self {
+        BdAddr { val }
+    }
+}
+
+impl fmt::Display for BdAddr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
             self.val[5], self.val[4], self.val[3], self.val[2], self.val[1], self.val[0])
     }
 }
 
-fn get_utf8(data: &[u8]) -> IResult<&[u8], &str> {
+fn get_utf8(data: &[u8]) -> PResult<'_, &str> {
     let (data, str_raw) = bytes::complete::take_until(&[b'\0'][..])(data)?;
     match str::from_utf8(str_raw) {
         Ok(str) => Ok((data, str)),
-        Err(_) => todo!(),
+        Err(e) => error::fail(&str_raw[e.valid_up_to()..], Kind::InvalidUtf8),
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NewIndex <'a> {
     ctrl_type: IndexType,
     bus: IndexBus,
-    addr: BdAddr<'a>,
-    name: &'a str,
+    addr: BdAddr,
+    name: Cow<'a, str>,
 }
 
 impl NewIndex <'_> {
-    fn parse(data: &'_[u8]) -> IResult<&[u8], Op> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn into_owned(self) -> NewIndex<'static> {
+        NewIndex { name: Cow::Owned(self.name.into_owned()), ..self }
+    }
+
+    fn parse(data: &[u8]) -> PResult<'_, Op<'_>> {
         let (data, type_raw) = le_u8(data)?;
         let (data, bus_raw) = le_u8(data)?;
-        let (data, bdaddr) = bytes::complete::take(6usize)(data)?;
+        let (data, addr) = BdAddr::parse(data)?;
         let (data, name) = get_utf8(data)?;
 
         Ok((data, Op::NewIndex(NewIndex {
             ctrl_type: IndexType::from(type_raw),
             bus: IndexBus::from(bus_raw),
-            addr: BdAddr { val: bdaddr },
-            name,
+            addr,
+            name: Cow::Borrowed(name),
         })))
     }
 }
@@ -86,7 +114,8 @@ impl fmt::Display for NewIndex<'_> {
 }
 
 #[repr(u8)]
-#[derive(Debug, Eq, PartialEq, FromPrimitive)]
+#[derive(Debug, Clone, Eq, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LogPriority {
     Emerg  = 0,
     Alert  = 1,
@@ -101,11 +130,12 @@ pub enum LogPriority {
     Unknown(u8),
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UserLogging <'a> {
     prio: LogPriority,
-    id: &'a str,
-    msg: &'a str,
+    id: Cow<'a, str>,
+    msg: Cow<'a, str>,
 }
 
 impl fmt::Display for UserLogging<'_> {
@@ -115,27 +145,40 @@ impl fmt::Display for UserLogging<'_> {
 }
 
 impl UserLogging <'_> {
-    fn parse(data: &'_ [u8]) -> IResult<&[u8], Op> {
+    pub fn into_owned(self) -> UserLogging<'static> {
+        UserLogging {
+            prio: self.prio,
+            id: Cow::Owned(self.id.into_owned()),
+            msg: Cow::Owned(self.msg.into_owned()),
+        }
+    }
+
+    fn parse(data: &[u8]) -> PResult<'_, Op<'_>> {
         let (data, prio) = le_u8(data)?;
         let (data, raw_id) = length_data(le_u8)(data)?;
         let (_, id) = get_utf8(raw_id)?;
         let (data, msg) = get_utf8(data)?;
 
-        Ok((data, Op::UserLogging(UserLogging { prio: LogPriority::from(prio), id, msg })))
+        Ok((data, Op::UserLogging(UserLogging {
+            prio: LogPriority::from(prio),
+            id: Cow::Borrowed(id),
+            msg: Cow::Borrowed(msg),
+        })))
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AclPkt <'a> {
     handle: u16,
     pb: u8,
     bc: u8,
-    data: &'a[u8],
+    data: Cow<'a, [u8]>,
 }
 
 impl fmt::Display for AclPkt<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match l2cap::Frame::parse(self.data) {
+        match l2cap::Frame::parse(&self.data) {
             Ok((_, frame)) => {
                 write!(f, "handle 0x{:04x} pb {:02b} bc {:02b}: {}", self.handle, self.pb, self.bc, frame)
             },
@@ -148,16 +191,59 @@ impl fmt::Display for AclPkt<'_> {
 }
 
 impl AclPkt <'_> {
-    fn parse(frame: &'_ [u8]) -> IResult<&[u8], AclPkt> {
-        let (rem, (mut handle, data)) = tuple((le_u16, length_data(le_u16)))(frame)?;
+    pub fn handle(&self) -> u16 {
+        self.handle
+    }
+
+    /// Packet boundary flag: 0b01 marks a continuation fragment
+    pub fn pb(&self) -> u8 {
+        self.pb
+    }
+
+    pub fn bc(&self) -> u8 {
+        self.bc
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_owned(self) -> AclPkt<'static> {
+        AclPkt { data: Cow::Owned(self.data.into_owned()), ..self }
+    }
+
+    fn parse(frame: &[u8]) -> PResult<'_, AclPkt<'_>> {
+        let (rem, (mut handle, len)) = tuple((le_u16, le_u16))(frame)?;
+        if len as usize > rem.len() {
+            return error::fail(rem, Kind::BadLength);
+        }
+        let (rem, data) = bytes::complete::take(len)(rem)?;
         let pb: u8 = (handle >> 12) as u8 & 0b11;
         let bc: u8 = (handle >> 14) as u8 & 0b11;
         handle &= 0b111111111111;
-        Ok((rem, AclPkt { handle, pb, bc, data} ))
+        Ok((rem, AclPkt { handle, pb, bc, data: Cow::Borrowed(data) } ))
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+/// Direction of a data packet as seen from the host
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    Tx,
+    Rx,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Tx => write!(f, "TX"),
+            Direction::Rx => write!(f, "RX"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Op <'a> {
     NewIndex(NewIndex<'a>),
     DelIndex,
@@ -165,21 +251,21 @@ pub enum Op <'a> {
     EventPkt(hci::Event<'a>),
     AclTxPkt(AclPkt<'a>),
     AclRxPkt(AclPkt<'a>),
-    ScoTxPkt(&'a[u8]),
-    ScoRxPkt(&'a[u8]),
+    ScoTxPkt(Cow<'a, [u8]>),
+    ScoRxPkt(Cow<'a, [u8]>),
     OpenIndex,
     CloseIndex,
-    IndexInfo(&'a[u8]),
-    VendorDiag(&'a[u8]),
-    SystemNote(&'a[u8]),
+    IndexInfo(Cow<'a, [u8]>),
+    VendorDiag(Cow<'a, [u8]>),
+    SystemNote(Cow<'a, [u8]>),
     UserLogging(UserLogging<'a>),
-    CtrlOpen(&'a[u8]),
-    CtrlClose(&'a[u8]),
-    CtrlCommand(&'a[u8]),
-    CtrlEvent(&'a[u8]),
-    IsoTxPkt(&'a[u8]),
-    IsoRxPkt(&'a[u8]),
-    Unknown(u16, &'a[u8]),
+    CtrlOpen(Cow<'a, [u8]>),
+    CtrlClose(Cow<'a, [u8]>),
+    CtrlCommand(Cow<'a, [u8]>),
+    CtrlEvent(Cow<'a, [u8]>),
+    IsoTxPkt(Cow<'a, [u8]>),
+    IsoRxPkt(Cow<'a, [u8]>),
+    Unknown(u16, Cow<'a, [u8]>),
 }
 
 impl fmt::Display for Op<'_> {
@@ -206,27 +292,122 @@ impl fmt::Display for Op<'_> {
             Op::AclRxPkt(p) => {
                 write!(f, "ACL RX:       {}", p)
             },
+            Op::ScoTxPkt(d) | Op::ScoRxPkt(d) => {
+                let dir = match self {
+                    Op::ScoTxPkt(_) => "TX",
+                    _ => "RX",
+                };
+                match sco::ScoPkt::parse(d) {
+                    Ok((_, p)) => write!(f, "SCO {}:       {}", dir, p),
+                    Err(_) => write!(f, "SCO {}:       {:02x?}", dir, d),
+                }
+            },
+            Op::IsoTxPkt(d) | Op::IsoRxPkt(d) => {
+                let dir = match self {
+                    Op::IsoTxPkt(_) => "TX",
+                    _ => "RX",
+                };
+                match iso::IsoPkt::parse(d) {
+                    Ok((_, p)) => write!(f, "ISO {}:       {}", dir, p),
+                    Err(_) => write!(f, "ISO {}:       {:02x?}", dir, d),
+                }
+            },
             _ => write!(f, "{:02x?}", self),
         }
     }
 }
 
-#[derive(Debug)]
+impl Op <'_> {
+    pub fn opcode(&self) -> u16 {
+        match self {
+            Op::NewIndex(_)    => 0,
+            Op::DelIndex       => 1,
+            Op::CommandPkt(_)  => 2,
+            Op::EventPkt(_)    => 3,
+            Op::AclTxPkt(_)    => 4,
+            Op::AclRxPkt(_)    => 5,
+            Op::ScoTxPkt(_)    => 6,
+            Op::ScoRxPkt(_)    => 7,
+            Op::OpenIndex      => 8,
+            Op::CloseIndex     => 9,
+            Op::IndexInfo(_)   => 10,
+            Op::VendorDiag(_)  => 11,
+            Op::SystemNote(_)  => 12,
+            Op::UserLogging(_) => 13,
+            Op::CtrlOpen(_)    => 14,
+            Op::CtrlClose(_)   => 15,
+            Op::CtrlCommand(_) => 16,
+            Op::CtrlEvent(_)   => 17,
+            Op::IsoTxPkt(_)    => 18,
+            Op::IsoRxPkt(_)    => 19,
+            Op::Unknown(op, _) => *op,
+        }
+    }
+
+    pub fn into_owned(self) -> Op<'static> {
+        fn own(data: Cow<'_, [u8]>) -> Cow<'static, [u8]> {
+            Cow::Owned(data.into_owned())
+        }
+
+        match self {
+            Op::NewIndex(m)    => Op::NewIndex(m.into_owned()),
+            Op::DelIndex       => Op::DelIndex,
+            Op::CommandPkt(c)  => Op::CommandPkt(c.into_owned()),
+            Op::EventPkt(e)    => Op::EventPkt(e.into_owned()),
+            Op::AclTxPkt(p)    => Op::AclTxPkt(p.into_owned()),
+            Op::AclRxPkt(p)    => Op::AclRxPkt(p.into_owned()),
+            Op::ScoTxPkt(d)    => Op::ScoTxPkt(own(d)),
+            Op::ScoRxPkt(d)    => Op::ScoRxPkt(own(d)),
+            Op::OpenIndex      => Op::OpenIndex,
+            Op::CloseIndex     => Op::CloseIndex,
+            Op::IndexInfo(d)   => Op::IndexInfo(own(d)),
+            Op::VendorDiag(d)  => Op::VendorDiag(own(d)),
+            Op::SystemNote(d)  => Op::SystemNote(own(d)),
+            Op::UserLogging(m) => Op::UserLogging(m.into_owned()),
+            Op::CtrlOpen(d)    => Op::CtrlOpen(own(d)),
+            Op::CtrlClose(d)   => Op::CtrlClose(own(d)),
+            Op::CtrlCommand(d) => Op::CtrlCommand(own(d)),
+            Op::CtrlEvent(d)   => Op::CtrlEvent(own(d)),
+            Op::IsoTxPkt(d)    => Op::IsoTxPkt(own(d)),
+            Op::IsoRxPkt(d)    => Op::IsoRxPkt(own(d)),
+            Op::Unknown(op, d) => Op::Unknown(op, own(d)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Packet <'a> {
     pub ts: Time,
     pub index: u16,
     pub op: Op<'a>,
+    pub data: Cow<'a, [u8]>,
 }
 
-fn parse_packet(op: u16, data: &[u8]) -> IResult<&[u8], Op> {
+/// A packet which owns all of its data, e.g. for keeping it around after the
+/// capture buffer it was parsed from has been reused.
+pub type PacketBuf = Packet<'static>;
+
+impl Packet <'_> {
+    pub fn into_owned(self) -> PacketBuf {
+        Packet {
+            ts: self.ts,
+            index: self.index,
+            op: self.op.into_owned(),
+            data: Cow::Owned(self.data.into_owned()),
+        }
+    }
+}
+
+fn parse_packet(op: u16, data: &[u8]) -> PResult<'_, Op<'_>> {
     match op {
         0  => NewIndex::parse(data),
         1  => Ok((data, Op::DelIndex)),
-        2  => match hci::Command::parse(data) {
+        2  => match error::in_layer(Layer::Hci, hci::Command::parser(data)) {
             Ok((data, cmd)) => Ok((data, Op::CommandPkt(cmd))),
             Err(e) => Err(e),
         },
-        3  => match hci::Event::parse(data) {
+        3  => match error::in_layer(Layer::Hci, hci::Event::parser(data)) {
             Ok((data, ev)) => Ok((data, Op::EventPkt(ev))),
             Err(e) => Err(e),
         },
@@ -238,32 +419,39 @@ fn parse_packet(op: u16, data: &[u8]) -> IResult<&[u8], Op> {
             Ok((data, pkt)) => Ok((data, Op::AclRxPkt(pkt))),
             Err(e) => Err(e),
         }
-        6  => Ok((data, Op::ScoTxPkt(data))),
-        7  => Ok((data, Op::ScoTxPkt(data))),
+        6  => Ok((data, Op::ScoTxPkt(Cow::Borrowed(data)))),
+        7  => Ok((data, Op::ScoRxPkt(Cow::Borrowed(data)))),
         8  => Ok((data, Op::OpenIndex)),
         9  => Ok((data, Op::CloseIndex)),
-        10 => Ok((data, Op::IndexInfo(data))),
-        11 => Ok((data, Op::VendorDiag(data))),
-        12 => Ok((data, Op::SystemNote(data))),
+        10 => Ok((data, Op::IndexInfo(Cow::Borrowed(data)))),
+        11 => Ok((data, Op::VendorDiag(Cow::Borrowed(data)))),
+        12 => Ok((data, Op::SystemNote(Cow::Borrowed(data)))),
         13 => UserLogging::parse(data),
-        14 => Ok((data, Op::CtrlOpen(data))),
-        15 => Ok((data, Op::CtrlClose(data))),
-        16 => Ok((data, Op::CtrlCommand(data))),
-        17 => Ok((data, Op::CtrlEvent(data))),
-        18 => Ok((data, Op::IsoTxPkt(data))),
-        19 => Ok((data, Op::IsoRxPkt(data))),
-        unknown => Ok((&data[1..], Op::Unknown(unknown, data))),
+        14 => Ok((data, Op::CtrlOpen(Cow::Borrowed(data)))),
+        15 => Ok((data, Op::CtrlClose(Cow::Borrowed(data)))),
+        16 => Ok((data, Op::CtrlCommand(Cow::Borrowed(data)))),
+        17 => Ok((data, Op::CtrlEvent(Cow::Borrowed(data)))),
+        18 => Ok((data, Op::IsoTxPkt(Cow::Borrowed(data)))),
+        19 => Ok((data, Op::IsoRxPkt(Cow::Borrowed(data)))),
+        unknown => Ok((&data[data.len()..], Op::Unknown(unknown, Cow::Borrowed(data)))),
     }
 }
 
-pub fn monitor_packet(ts: Time, index: u16, op: u16, data: &[u8]) -> IResult<&[u8], Packet> {
-    let (data, op) = parse_packet(op, data)?;
-    Ok((data, Packet { ts, index, op }))
+pub(crate) fn parse_monitor(ts: Time, index: u16, op: u16, data: &[u8]) -> PResult<'_, Packet<'_>> {
+    let raw = data;
+    let (data, op) = error::in_layer(Layer::Monitor, parse_packet(op, data))?;
+    Ok((data, Packet { ts, index, op, data: Cow::Borrowed(raw) }))
+}
+
+pub fn monitor_packet(ts: Time, index: u16, op: u16, data: &[u8]) -> error::Result<(&[u8], Packet<'_>)> {
+    error::finish(Layer::Monitor, data, parse_monitor(ts, index, op, data))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_packet, Op};
+    use super::{parse_packet, monitor_packet, Op};
+    use crate::{Error, error::Layer};
+    use time::Time;
 
     #[test]
     fn close_index() {
@@ -271,4 +459,48 @@ mod tests {
         let result = &data[..];
         assert_eq!(parse_packet(0x0009, data), Ok((result, Op::CloseIndex)));
     }
+
+    #[test]
+    fn unknown_empty() {
+        let (_, pkt) = monitor_packet(Time::MIDNIGHT, 0, 0x0020, b"").unwrap();
+        assert_eq!(pkt.op, Op::Unknown(0x0020, b"".as_slice().into()));
+
+        let (rem, pkt) = crate::tty::parse_data(&[4, 0, 0x20, 0, 0, 0]).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(pkt.op.opcode(), 0x0020);
+    }
+
+    #[test]
+    fn sco_direction() {
+        let data = b"\x06\x20\x01\xaa";
+        assert_eq!(parse_packet(7, data).unwrap().1.to_string(),
+                   "SCO RX:       handle 0x0006 status No data received (0x02): 1 bytes");
+    }
+
+    #[test]
+    fn new_index_invalid_utf8() {
+        let data = b"\x00\x01\x11\x22\x33\x44\x55\x66hci\xff\0";
+        let err = monitor_packet(Time::MIDNIGHT, 0, 0x0000, data).unwrap_err();
+        assert_eq!(err, Error::InvalidUtf8 { layer: Layer::Monitor, offset: 11 });
+    }
+
+    #[test]
+    fn into_owned() {
+        let pkt = {
+            let data = b"\x00\x01\x11\x22\x33\x44\x55\x66hci0\0".to_vec();
+            let (_, pkt) = monitor_packet(Time::MIDNIGHT, 0, 0x0000, &data).unwrap();
+            pkt.into_owned()
+        };
+
+        assert_eq!(pkt.op.opcode(), 0);
+        assert_eq!(pkt.data.len(), 13);
+        assert_eq!(pkt.op.to_string(), "New Index:    type Primary bus Usb addr 66:55:44:33:22:11 name hci0");
+    }
+
+    #[test]
+    fn acl_bad_length() {
+        let data = b"\x01\x20\x10\x00\x01\x02";
+        let err = monitor_packet(Time::MIDNIGHT, 0, 0x0005, data).unwrap_err();
+        assert_eq!(err, Error::BadLength { layer: Layer::Monitor, offset: 4 });
+    }
 }
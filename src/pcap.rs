@@ -0,0 +1,87 @@
+use std::io::{self, Write};
+use time::{Time, Date, OffsetDateTime};
+use crate::monitor::Packet;
+
+// LINKTYPE_BLUETOOTH_LINUX_MONITOR
+const LINKTYPE: u32 = 254;
+const SNAPLEN: u32 = 0xffff;
+const MAGIC: u32 = 0xa1b2_c3d4;
+
+pub struct Writer<W: Write> {
+    out: W,
+    base: i64,
+}
+
+impl<W: Write> Writer<W> {
+    /// Create a classic pcap writer using LINKTYPE_BLUETOOTH_LINUX_MONITOR,
+    /// e.g. for streaming into a Wireshark extcap fifo.
+    pub fn new(out: W) -> io::Result<Self> {
+        Self::with_date(out, OffsetDateTime::now_utc().date())
+    }
+
+    pub fn with_date(mut out: W, date: Date) -> io::Result<Self> {
+        out.write_all(&MAGIC.to_le_bytes())?;
+        out.write_all(&2u16.to_le_bytes())?;
+        out.write_all(&4u16.to_le_bytes())?;
+        out.write_all(&0i32.to_le_bytes())?;
+        out.write_all(&0u32.to_le_bytes())?;
+        out.write_all(&SNAPLEN.to_le_bytes())?;
+        out.write_all(&LINKTYPE.to_le_bytes())?;
+        out.flush()?;
+
+        let base = date.midnight().assume_utc().unix_timestamp() * 1_000_000;
+
+        Ok(Writer { out, base })
+    }
+
+    pub fn write_packet(&mut self, pkt: &Packet) -> io::Result<()> {
+        let ts = self.base + (pkt.ts - Time::MIDNIGHT).whole_microseconds() as i64;
+        let len = (pkt.data.len() + 4) as u32;
+
+        self.out.write_all(&((ts / 1_000_000) as u32).to_le_bytes())?;
+        self.out.write_all(&((ts % 1_000_000) as u32).to_le_bytes())?;
+        self.out.write_all(&len.to_le_bytes())?;
+        self.out.write_all(&len.to_le_bytes())?;
+        self.out.write_all(&pkt.index.to_be_bytes())?;
+        self.out.write_all(&pkt.op.opcode().to_be_bytes())?;
+        self.out.write_all(&pkt.data)?;
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Writer, LINKTYPE, MAGIC, SNAPLEN};
+    use crate::monitor;
+    use time::{Date, Month, Time};
+
+    #[test]
+    fn record_layout() {
+        let date = Date::from_calendar_date(2024, Month::January, 1).unwrap();
+        let mut writer = Writer::with_date(Vec::new(), date).unwrap();
+        let ts = Time::from_hms_micro(1, 2, 3, 4).unwrap();
+        let (_, pkt) = monitor::monitor_packet(ts, 1, 0x0008, b"").unwrap();
+        writer.write_packet(&pkt).unwrap();
+
+        let file = writer.into_inner();
+        let u32_at = |pos: usize| u32::from_le_bytes(file[pos..pos + 4].try_into().unwrap());
+
+        assert_eq!(file.len(), 24 + 16 + 4);
+        assert_eq!(u32_at(0), MAGIC);
+        assert_eq!(&file[4..8], &[2, 0, 4, 0]);
+        assert_eq!(u32_at(16), SNAPLEN);
+        assert_eq!(u32_at(20), LINKTYPE);
+
+        // 2024-01-01 01:02:03.000004 UTC
+        assert_eq!(u32_at(24), 1_704_070_923);
+        assert_eq!(u32_at(28), 4);
+        assert_eq!(u32_at(32), 4);
+        assert_eq!(u32_at(36), 4);
+        // Index and opcode in network byte order
+        assert_eq!(&file[40..], &[0x00, 0x01, 0x00, 0x08]);
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use time::{Time, Date, OffsetDateTime};
+use crate::monitor::{Packet, Op};
+
+// LINKTYPE_BLUETOOTH_LINUX_MONITOR
+const LINKTYPE: u16 = 254;
+const SNAPLEN: u32 = 0xffff;
+
+const BLOCK_SHB: u32 = 0x0a0d_0d0a;
+const BLOCK_IDB: u32 = 0x0000_0001;
+const BLOCK_EPB: u32 = 0x0000_0006;
+
+const OPT_ENDOFOPT: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+const IF_NAME: u16 = 2;
+const IF_DESCRIPTION: u16 = 3;
+
+fn push_option(buf: &mut Vec<u8>, code: u16, val: &[u8]) {
+    buf.extend_from_slice(&code.to_le_bytes());
+    buf.extend_from_slice(&(val.len() as u16).to_le_bytes());
+    buf.extend_from_slice(val);
+    pad(buf);
+}
+
+fn end_options(buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&OPT_ENDOFOPT.to_le_bytes());
+    buf.extend_from_slice(&0u16.to_le_bytes());
+}
+
+fn pad(buf: &mut Vec<u8>) {
+    buf.resize(buf.len().next_multiple_of(4), 0);
+}
+
+pub struct Writer<W: Write> {
+    out: W,
+    base: i64,
+    idbs: u32,
+    // Interface id and description of the latest IDB for each index
+    interfaces: HashMap<u16, (u32, Option<String>)>,
+}
+
+impl<W: Write> Writer<W> {
+    /// Create a pcapng writer using LINKTYPE_BLUETOOTH_LINUX_MONITOR. As with
+    /// BTSnoop output, packet timestamps are anchored to the current UTC date.
+    pub fn new(out: W) -> io::Result<Self> {
+        Self::with_date(out, OffsetDateTime::now_utc().date())
+    }
+
+    pub fn with_date(out: W, date: Date) -> io::Result<Self> {
+        let base = date.midnight().assume_utc().unix_timestamp() * 1_000_000;
+        let mut writer = Writer { out, base, idbs: 0, interfaces: HashMap::new() };
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&0x1a2b_3c4du32.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&(-1i64).to_le_bytes());
+        push_option(&mut body, 4, b"btmon");
+        end_options(&mut body);
+
+        writer.write_block(BLOCK_SHB, &body)?;
+
+        Ok(writer)
+    }
+
+    fn write_block(&mut self, kind: u32, body: &[u8]) -> io::Result<()> {
+        let len = (body.len() + 12) as u32;
+
+        self.out.write_all(&kind.to_le_bytes())?;
+        self.out.write_all(&len.to_le_bytes())?;
+        self.out.write_all(body)?;
+        self.out.write_all(&len.to_le_bytes())
+    }
+
+    fn add_interface(&mut self, index: u16, desc: Option<&str>) -> io::Result<u32> {
+        let id = self.idbs;
+        let mut body = Vec::new();
+
+        body.extend_from_slice(&LINKTYPE.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        body.extend_from_slice(&SNAPLEN.to_le_bytes());
+        push_option(&mut body, IF_NAME, format!("hci{}", index).as_bytes());
+        if let Some(desc) = desc {
+            push_option(&mut body, IF_DESCRIPTION, desc.as_bytes());
+        }
+        end_options(&mut body);
+
+        self.write_block(BLOCK_IDB, &body)?;
+        self.idbs += 1;
+        self.interfaces.insert(index, (id, desc.map(str::to_string)));
+
+        Ok(id)
+    }
+
+    fn interface(&mut self, pkt: &Packet) -> io::Result<u32> {
+        if let Some((id, desc)) = self.interfaces.get(&pkt.index) {
+            match &pkt.op {
+                // The capture started after the index was added, or the index
+                // got reused. IDBs can't be amended, so write a new one.
+                Op::NewIndex(m) if desc.as_deref() != Some(m.name()) => (),
+                _ => return Ok(*id),
+            }
+        }
+
+        match &pkt.op {
+            Op::NewIndex(m) => self.add_interface(pkt.index, Some(m.name())),
+            _ => self.add_interface(pkt.index, None),
+        }
+    }
+
+    pub fn write_packet(&mut self, pkt: &Packet) -> io::Result<()> {
+        let id = self.interface(pkt)?;
+        let ts = (self.base + (pkt.ts - Time::MIDNIGHT).whole_microseconds() as i64) as u64;
+        let len = (pkt.data.len() + 4) as u32;
+        let mut body = Vec::new();
+
+        body.extend_from_slice(&id.to_le_bytes());
+        body.extend_from_slice(&((ts >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(ts as u32).to_le_bytes());
+        body.extend_from_slice(&len.to_le_bytes());
+        body.extend_from_slice(&len.to_le_bytes());
+
+        // Pseudo header: adapter index and monitor opcode, both big endian
+        body.extend_from_slice(&pkt.index.to_be_bytes());
+        body.extend_from_slice(&pkt.op.opcode().to_be_bytes());
+        body.extend_from_slice(&pkt.data);
+        pad(&mut body);
+
+        if let Op::UserLogging(m) = &pkt.op {
+            push_option(&mut body, OPT_COMMENT, m.to_string().as_bytes());
+            end_options(&mut body);
+        }
+
+        self.write_block(BLOCK_EPB, &body)?;
+        self.out.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Writer, BLOCK_SHB, BLOCK_IDB, BLOCK_EPB};
+    use crate::{tty, monitor};
+    use time::{Date, Month, Time};
+
+    fn writer() -> Writer<Vec<u8>> {
+        Writer::with_date(Vec::new(), Date::from_calendar_date(2024, Month::January, 1).unwrap()).unwrap()
+    }
+
+    // Split a pcapng file into (block type, block body) pairs
+    fn blocks(mut rem: &[u8]) -> Vec<(u32, &[u8])> {
+        let mut blocks = Vec::new();
+
+        while !rem.is_empty() {
+            let kind = u32::from_le_bytes(rem[0..4].try_into().unwrap());
+            let len = u32::from_le_bytes(rem[4..8].try_into().unwrap()) as usize;
+            let trailer = u32::from_le_bytes(rem[len - 4..len].try_into().unwrap()) as usize;
+
+            assert_eq!(len % 4, 0);
+            assert_eq!(len, trailer);
+            blocks.push((kind, &rem[8..len - 4]));
+            rem = &rem[len..];
+        }
+
+        blocks
+    }
+
+    #[test]
+    fn block_layout() {
+        let mut data = &include_bytes!("siw917_peripheral_hr.btsnoop")[..];
+        let mut writer = writer();
+        let mut count = 0;
+
+        while let Ok((rem, pkt)) = tty::parse_data(data) {
+            writer.write_packet(&pkt).unwrap();
+            count += 1;
+            data = rem;
+        }
+
+        let file = writer.into_inner();
+        let blocks: Vec<u32> = blocks(&file).into_iter().map(|(kind, _)| kind).collect();
+
+        assert_eq!(blocks[0], BLOCK_SHB);
+        assert_eq!(blocks[1], BLOCK_IDB);
+        assert_eq!(blocks.iter().filter(|b| **b == BLOCK_IDB).count(), 1);
+        assert_eq!(blocks.iter().filter(|b| **b == BLOCK_EPB).count(), count);
+    }
+
+    #[test]
+    fn late_new_index() {
+        let new_index = [0x00, 0x01, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, b'h', b'c', b'i', b'0', 0x00];
+        let mut writer = writer();
+
+        for (op, data) in [(0x0008, &[][..]), (0x0000, &new_index[..]), (0x0009, &[][..])] {
+            let (_, pkt) = monitor::monitor_packet(Time::MIDNIGHT, 0, op, data).unwrap();
+            writer.write_packet(&pkt).unwrap();
+        }
+
+        let file = writer.into_inner();
+        let blocks = blocks(&file);
+        let kinds: Vec<u32> = blocks.iter().map(|(kind, _)| *kind).collect();
+        let ids: Vec<u32> = blocks.iter()
+            .filter(|(kind, _)| *kind == BLOCK_EPB)
+            .map(|(_, body)| u32::from_le_bytes(body[0..4].try_into().unwrap()))
+            .collect();
+
+        assert_eq!(kinds, [BLOCK_SHB, BLOCK_IDB, BLOCK_EPB, BLOCK_IDB, BLOCK_EPB, BLOCK_EPB]);
+        assert_eq!(ids, [0, 1, 1]);
+        // if_name "hci0", then if_description "hci0"
+        assert_eq!(&blocks[3].1[8..], [
+            0x02, 0x00, 0x04, 0x00, b'h', b'c', b'i', b'0',
+            0x03, 0x00, 0x04, 0x00, b'h', b'c', b'i', b'0',
+            0x00, 0x00, 0x00, 0x00,
+        ]);
+    }
+
+    #[test]
+    fn user_logging_comment() {
+        let data = [0x06, 0x04, b'b', b't', b'd', 0x00, b'h', b'e', b'l', b'l', b'o', 0x00];
+        let (_, pkt) = monitor::monitor_packet(Time::MIDNIGHT, 0xffff, 0x000d, &data).unwrap();
+        let mut writer = writer();
+
+        writer.write_packet(&pkt).unwrap();
+
+        let file = writer.into_inner();
+        let blocks = blocks(&file);
+        let (kind, body) = blocks[2];
+
+        assert_eq!(kind, BLOCK_EPB);
+        // 20 bytes of EPB fields, 4 bytes pseudo header and 12 bytes of
+        // data, then opt_comment and opt_endofopt
+        assert_eq!(&body[36..], [
+            0x01, 0x00, 0x05, 0x00, b'h', b'e', b'l', b'l', b'o', 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+        ]);
+    }
+}
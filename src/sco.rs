@@ -0,0 +1,283 @@
+use nom::{
+    bytes::complete::take,
+    number::complete::{le_u8, le_u16},
+    sequence::tuple,
+};
+use std::{fmt, collections::HashMap};
+use time::Time;
+use crate::error::{self, Layer, PResult};
+use crate::hci::{self, Event};
+use crate::monitor::{BdAddr, Direction};
+
+pub(crate) fn status_str(status: u8) -> &'static str {
+    match status {
+        0x00 => "Correctly received",
+        0x01 => "Possibly invalid",
+        0x02 => "No data received",
+        _    => "Partially lost",
+    }
+}
+
+/// HCI synchronous data packet
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ScoPkt <'a> {
+    handle: u16,
+    status: u8,
+    data: &'a [u8],
+}
+
+impl<'a> ScoPkt<'a> {
+    fn parser(input: &'a [u8]) -> PResult<'a, ScoPkt<'a>> {
+        let (i, handle) = le_u16(input)?;
+        let (i, len) = le_u8(i)?;
+        let (i, data) = take(len)(i)?;
+
+        Ok((i, ScoPkt { handle: handle & 0x0fff, status: ((handle >> 12) & 0b11) as u8, data }))
+    }
+
+    pub fn parse(input: &'a [u8]) -> error::Result<(&'a [u8], ScoPkt<'a>)> {
+        error::finish(Layer::Sco, input, ScoPkt::parser(input))
+    }
+
+    pub fn handle(&self) -> u16 {
+        self.handle
+    }
+
+    /// Packet Status Flag, only meaningful from the controller to the host
+    pub fn status(&self) -> u8 {
+        self.status
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl fmt::Display for ScoPkt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "handle 0x{:04x} status {} (0x{:02x}): {} bytes",
+               self.handle, status_str(self.status), self.status, self.data.len())
+    }
+}
+
+/// Parameters of a synchronous connection, from Synchronous Connection
+/// Complete and Changed
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyncParams {
+    pub link_type: u8,
+    pub air_mode: u8,
+    /// Transmission interval and retransmission window in slots
+    pub interval: u8,
+    pub window: u8,
+    pub rx_len: u16,
+    pub tx_len: u16,
+}
+
+/// Packet counters of one direction of a synchronous connection
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyncStats {
+    pub packets: u64,
+    pub bytes: u64,
+    /// Packets by the Packet Status Flag the controller set on them
+    pub invalid: u64,
+    pub lost: u64,
+    pub partially_lost: u64,
+    pub first: Option<Time>,
+    pub last: Option<Time>,
+}
+
+impl SyncStats {
+    fn packet(&mut self, ts: Time, pkt: &ScoPkt) {
+        self.packets += 1;
+        self.bytes += pkt.data.len() as u64;
+        match pkt.status {
+            0x01 => self.invalid += 1,
+            0x02 => self.lost += 1,
+            0x03 => self.partially_lost += 1,
+            _ => (),
+        }
+        self.first.get_or_insert(ts);
+        self.last = Some(ts);
+    }
+
+    /// Packets per second between the first and the last packet
+    pub fn rate(&self) -> Option<f64> {
+        let secs = (self.last? - self.first?).as_seconds_f64();
+        (secs > 0.0).then(|| (self.packets - 1) as f64 / secs)
+    }
+}
+
+impl fmt::Display for SyncStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} packets, {} bytes, {} possibly invalid, {} lost, {} partially lost",
+               self.packets, self.bytes, self.invalid, self.lost, self.partially_lost)?;
+        if let Some(rate) = self.rate() {
+            write!(f, ", {:.1} packets/sec", rate)?;
+        }
+        Ok(())
+    }
+}
+
+/// SCO or eSCO connection
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyncConn {
+    pub index: u16,
+    pub handle: u16,
+    /// Peer and parameters are unknown for data seen without the event
+    /// establishing the connection
+    pub peer: Option<BdAddr>,
+    pub params: Option<SyncParams>,
+    pub disconnected: Option<(Time, u8)>,
+    pub tx: SyncStats,
+    pub rx: SyncStats,
+}
+
+impl SyncConn {
+    fn new(index: u16, handle: u16) -> Self {
+        SyncConn {
+            index, handle,
+            peer: None,
+            params: None,
+            disconnected: None,
+            tx: SyncStats::default(),
+            rx: SyncStats::default(),
+        }
+    }
+}
+
+impl fmt::Display for SyncConn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let link = self.params.map_or("Synchronous", |p| hci::link_type_str(p.link_type));
+        write!(f, "{} connection handle 0x{:04x} (index {})", link, self.handle, self.index)?;
+        if let Some(peer) = self.peer {
+            write!(f, ": {}", peer)?;
+        }
+        if let Some(p) = self.params {
+            hci::field(f, "Air mode", format_args!("{} (0x{:02x})", hci::air_mode_str(p.air_mode), p.air_mode))?;
+            hci::field(f, "Interval", format_args!("{} slots, retransmission window {} slots", p.interval, p.window))?;
+            hci::field(f, "Packet length", format_args!("TX {} RX {}", p.tx_len, p.rx_len))?;
+        }
+        if let Some((ts, reason)) = self.disconnected {
+            hci::field(f, "Disconnected", format_args!("{} reason {}", ts, hci::StatusCode(reason)))?;
+        }
+        hci::field(f, "TX", &self.tx)?;
+        hci::field(f, "RX", &self.rx)
+    }
+}
+
+type SyncComplete = (u8, u16, BdAddr, u8, u8, u8, u16, u16, u8);
+
+fn sync_conn_complete(i: &[u8]) -> PResult<'_, SyncComplete> {
+    tuple((le_u8, le_u16, BdAddr::parse, le_u8, le_u8, le_u8, le_u16, le_u16, le_u8))(i)
+}
+
+fn sync_conn_changed(i: &[u8]) -> PResult<'_, (u8, u16, u8, u8, u16, u16)> {
+    tuple((le_u8, le_u16, le_u8, le_u8, le_u16, le_u16))(i)
+}
+
+/// Synchronous connections of all controllers, fed from HCI events and SCO
+/// data packets.
+#[derive(Debug, Default)]
+pub struct SyncConns {
+    open: HashMap<(u16, u16), SyncConn>,
+    closed: Vec<SyncConn>,
+}
+
+impl SyncConns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, index: u16, handle: u16) -> Option<&SyncConn> {
+        self.open.get(&(index, handle))
+    }
+
+    /// Every connection, closed ones first
+    pub fn all(&self) -> Vec<&SyncConn> {
+        let mut open: Vec<_> = self.open.values().collect();
+        open.sort_by_key(|c| (c.index, c.handle));
+        self.closed.iter().chain(open).collect()
+    }
+
+    /// Update the connections from an HCI event
+    pub fn event(&mut self, ts: Time, index: u16, ev: &Event) {
+        match ev.code() {
+            // Disconnect Complete
+            0x05 => {
+                if let Some((0, handle, reason)) = ev.disconnect_complete() {
+                    if let Some(mut conn) = self.open.remove(&(index, handle)) {
+                        conn.disconnected = Some((ts, reason));
+                        self.closed.push(conn);
+                    }
+                }
+            },
+            // Synchronous Connection Complete
+            0x2c => {
+                if let Ok((_, (0, handle, peer, link_type, interval, window, rx_len, tx_len, air_mode))) =
+                    sync_conn_complete(ev.param()) {
+                    let mut conn = SyncConn::new(index, handle);
+                    conn.peer = Some(peer);
+                    conn.params = Some(SyncParams { link_type, air_mode, interval, window, rx_len, tx_len });
+                    if let Some(old) = self.open.insert((index, handle), conn) {
+                        self.closed.push(old);
+                    }
+                }
+            },
+            // Synchronous Connection Changed
+            0x2d => {
+                if let Ok((_, (0, handle, interval, window, rx_len, tx_len))) = sync_conn_changed(ev.param()) {
+                    if let Some(p) = self.open.get_mut(&(index, handle)).and_then(|c| c.params.as_mut()) {
+                        *p = SyncParams { interval, window, rx_len, tx_len, ..*p };
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Account a SCO data packet sent in `dir` to its connection
+    pub fn packet(&mut self, ts: Time, index: u16, dir: Direction, pkt: &ScoPkt) -> &SyncConn {
+        let conn = self.open.entry((index, pkt.handle))
+            .or_insert_with(|| SyncConn::new(index, pkt.handle));
+        match dir {
+            Direction::Tx => conn.tx.packet(ts, pkt),
+            Direction::Rx => conn.rx.packet(ts, pkt),
+        }
+
+        conn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScoPkt, SyncConns};
+    use crate::hci::Event;
+    use crate::monitor::Direction;
+    use time::{Duration, Time};
+
+    #[test]
+    fn packet_status() {
+        let mut conns = SyncConns::new();
+        let ev = [0x2c, 0x11, 0x00, 0x06, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x02, 0x0c, 0x02, 0x3c, 0x00, 0x3c, 0x00, 0x03];
+        let (_, ev) = Event::parse(&ev).unwrap();
+        conns.event(Time::MIDNIGHT, 0, &ev);
+
+        for (n, status) in [0x00u8, 0x30, 0x10, 0x20, 0x00].into_iter().enumerate() {
+            let data = [0x06, status, 0x02, 0xaa, 0xbb];
+            let (_, pkt) = ScoPkt::parse(&data).unwrap();
+            conns.packet(Time::MIDNIGHT + Duration::milliseconds(n as i64 * 10), 0, Direction::Rx, &pkt);
+        }
+        assert!(ScoPkt::parse(&[0x06, 0x00, 0x03, 0xaa]).is_err());
+
+        let conn = conns.get(0, 0x0006).unwrap();
+        assert_eq!(conn.to_string(), "eSCO connection handle 0x0006 (index 0): 66:55:44:33:22:11\
+            \n        Air mode: Transparent Data (0x03)\
+            \n        Interval: 12 slots, retransmission window 2 slots\
+            \n        Packet length: TX 60 RX 60\
+            \n        TX: 0 packets, 0 bytes, 0 possibly invalid, 0 lost, 0 partially lost\
+            \n        RX: 5 packets, 10 bytes, 1 possibly invalid, 1 lost, 1 partially lost, 100.0 packets/sec");
+    }
+}
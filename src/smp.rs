@@ -0,0 +1,299 @@
+use nom::{
+    bytes::complete::take,
+    combinator::all_consuming,
+    number::complete::{le_u8, le_u16, le_u64},
+    sequence::tuple,
+};
+use num_enum::{FromPrimitive, IntoPrimitive};
+use std::fmt;
+use crate::error::{self, Layer, PResult};
+use crate::hci::{bit_names, field, hex, addr_type_str};
+use crate::monitor::BdAddr;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, IntoPrimitive)]
+enum Code {
+    PairingReq             = 0x01,
+    PairingRsp             = 0x02,
+    PairingConfirm         = 0x03,
+    PairingRandom          = 0x04,
+    PairingFailed          = 0x05,
+    EncryptionInformation  = 0x06,
+    CentralIdentification  = 0x07,
+    IdentityInformation    = 0x08,
+    IdentityAddress        = 0x09,
+    SigningInformation     = 0x0a,
+    SecurityReq            = 0x0b,
+    PairingPublicKey       = 0x0c,
+    PairingDhKeyCheck      = 0x0d,
+    KeypressNotification   = 0x0e,
+
+    #[num_enum(catch_all)]
+    Other(u8),
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Code::*;
+        let name = match self {
+            PairingReq            => "Pairing Request",
+            PairingRsp            => "Pairing Response",
+            PairingConfirm        => "Pairing Confirm",
+            PairingRandom         => "Pairing Random",
+            PairingFailed         => "Pairing Failed",
+            EncryptionInformation => "Encryption Information",
+            CentralIdentification => "Central Identification",
+            IdentityInformation   => "Identity Information",
+            IdentityAddress       => "Identity Address Information",
+            SigningInformation    => "Signing Information",
+            SecurityReq           => "Security Request",
+            PairingPublicKey      => "Pairing Public Key",
+            PairingDhKeyCheck     => "Pairing DHKey Check",
+            KeypressNotification  => "Keypress Notification",
+            Other(_)              => "Unknown",
+        };
+
+        write!(f, "{} (0x{:02x})", name, u8::from(*self))
+    }
+}
+
+fn io_capability_str(io: u8) -> &'static str {
+    match io {
+        0x00 => "DisplayOnly",
+        0x01 => "DisplayYesNo",
+        0x02 => "KeyboardOnly",
+        0x03 => "NoInputNoOutput",
+        0x04 => "KeyboardDisplay",
+        _    => "Reserved",
+    }
+}
+
+fn reason_str(reason: u8) -> &'static str {
+    match reason {
+        0x01 => "Passkey Entry Failed",
+        0x02 => "OOB Not Available",
+        0x03 => "Authentication Requirements",
+        0x04 => "Confirm Value Failed",
+        0x05 => "Pairing Not Supported",
+        0x06 => "Encryption Key Size",
+        0x07 => "Command Not Supported",
+        0x08 => "Unspecified Reason",
+        0x09 => "Repeated Attempts",
+        0x0a => "Invalid Parameters",
+        0x0b => "DHKey Check Failed",
+        0x0c => "Numeric Comparison Failed",
+        0x0d => "BR/EDR pairing in progress",
+        0x0e => "Cross-transport Key Derivation/Generation not allowed",
+        0x0f => "Key Rejected",
+        0x10 => "Busy",
+        _    => "Reserved",
+    }
+}
+
+fn keypress_str(kind: u8) -> &'static str {
+    match kind {
+        0x00 => "Passkey entry started",
+        0x01 => "Passkey digit entered",
+        0x02 => "Passkey digit erased",
+        0x03 => "Passkey cleared",
+        0x04 => "Passkey entry completed",
+        _    => "Reserved",
+    }
+}
+
+/// AuthReq field of Pairing Request/Response and Security Request
+struct AuthReq(u8);
+
+impl fmt::Display for AuthReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const BITS: [(u8, &str); 4] = [(0x04, "MITM"), (0x08, "SC"), (0x10, "Keypresses"), (0x20, "CT2")];
+
+        let bonding = match self.0 & 0b11 {
+            0b00 => "No bonding",
+            0b01 => "Bonding",
+            _    => "Reserved",
+        };
+        write!(f, "0x{:02x} ({}", self.0, bonding)?;
+        for (mask, name) in BITS {
+            if self.0 & mask != 0 {
+                write!(f, ", {}", name)?;
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+/// Initiator/Responder Key Distribution
+struct KeyDist(u8);
+
+impl fmt::Display for KeyDist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const BITS: [&str; 4] = ["EncKey", "IdKey", "SignKey", "LinkKey"];
+
+        f.write_str(&bit_names(self.0 as u16, 2, &BITS))
+    }
+}
+
+/// Decoded parameters of an SMP command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Param<'a> {
+    Pairing {
+        io_capability: u8,
+        oob: u8,
+        auth_req: u8,
+        max_key_size: u8,
+        initiator_keys: u8,
+        responder_keys: u8,
+    },
+    /// 128-bit confirm or random value, key or DHKey check
+    Value(&'a [u8]),
+    Failed { reason: u8 },
+    CentralIdentification { ediv: u16, rand: u64 },
+    IdentityAddress { addr_type: u8, addr: BdAddr },
+    SecurityReq { auth_req: u8 },
+    PublicKey { x: &'a [u8], y: &'a [u8] },
+    Keypress { kind: u8 },
+    Unknown(&'a [u8]),
+}
+
+pub struct Pdu <'a> {
+    code: Code,
+    param: Param<'a>,
+}
+
+fn param(code: Code, i: &[u8]) -> PResult<'_, Param<'_>> {
+    use Code::*;
+
+    match code {
+        PairingReq | PairingRsp => {
+            let (i, (io_capability, oob, auth_req, max_key_size, initiator_keys, responder_keys)) =
+                tuple((le_u8, le_u8, le_u8, le_u8, le_u8, le_u8))(i)?;
+            Ok((i, Param::Pairing { io_capability, oob, auth_req, max_key_size, initiator_keys, responder_keys }))
+        },
+        PairingConfirm | PairingRandom | EncryptionInformation | IdentityInformation |
+        SigningInformation | PairingDhKeyCheck => {
+            let (i, value) = take(16usize)(i)?;
+            Ok((i, Param::Value(value)))
+        },
+        PairingFailed => {
+            let (i, reason) = le_u8(i)?;
+            Ok((i, Param::Failed { reason }))
+        },
+        CentralIdentification => {
+            let (i, (ediv, rand)) = tuple((le_u16, le_u64))(i)?;
+            Ok((i, Param::CentralIdentification { ediv, rand }))
+        },
+        IdentityAddress => {
+            let (i, (addr_type, addr)) = tuple((le_u8, BdAddr::parse))(i)?;
+            Ok((i, Param::IdentityAddress { addr_type, addr }))
+        },
+        SecurityReq => {
+            let (i, auth_req) = le_u8(i)?;
+            Ok((i, Param::SecurityReq { auth_req }))
+        },
+        PairingPublicKey => {
+            let (i, (x, y)) = tuple((take(32usize), take(32usize)))(i)?;
+            Ok((i, Param::PublicKey { x, y }))
+        },
+        KeypressNotification => {
+            let (i, kind) = le_u8(i)?;
+            Ok((i, Param::Keypress { kind }))
+        },
+        Other(_) => Ok((&i[i.len()..], Param::Unknown(i))),
+    }
+}
+
+impl<'a> Pdu<'a> {
+    fn parser(input: &'a [u8]) -> PResult<'a, Pdu<'a>> {
+        let (i, code) = le_u8(input)?;
+        let code = Code::from(code);
+        let (rem, param) = all_consuming(|i| param(code, i))(i)?;
+
+        Ok((rem, Pdu { code, param }))
+    }
+
+    pub fn parse(input: &'a [u8]) -> error::Result<(&'a [u8], Pdu<'a>)> {
+        error::finish(Layer::Smp, input, Pdu::parser(input))
+    }
+
+    pub fn code(&self) -> u8 {
+        u8::from(self.code)
+    }
+
+    pub fn param(&self) -> &Param<'a> {
+        &self.param
+    }
+}
+
+impl fmt::Display for Pdu<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Code::*;
+
+        write!(f, "{}", self.code)?;
+
+        match &self.param {
+            Param::Pairing { io_capability, oob, auth_req, max_key_size, initiator_keys, responder_keys } => {
+                let oob_str = match oob {
+                    0x00 => "Authentication data not present",
+                    0x01 => "Authentication data from remote device present",
+                    _    => "Reserved",
+                };
+                field(f, "IO capability", format!("{} (0x{:02x})", io_capability_str(*io_capability), io_capability))?;
+                field(f, "OOB data", format!("{} (0x{:02x})", oob_str, oob))?;
+                field(f, "Authentication requirement", AuthReq(*auth_req))?;
+                field(f, "Max encryption key size", max_key_size)?;
+                field(f, "Initiator key distribution", KeyDist(*initiator_keys))?;
+                field(f, "Responder key distribution", KeyDist(*responder_keys))
+            },
+            Param::Value(value) => {
+                let name = match self.code {
+                    PairingConfirm        => "Confirm value",
+                    PairingRandom         => "Random value",
+                    EncryptionInformation => "Long term key",
+                    IdentityInformation   => "Identity resolving key",
+                    SigningInformation    => "Signature key",
+                    _                     => "Check value",
+                };
+                field(f, name, hex(value))
+            },
+            Param::Failed { reason } => {
+                field(f, "Reason", format!("{} (0x{:02x})", reason_str(*reason), reason))
+            },
+            Param::CentralIdentification { ediv, rand } => {
+                field(f, "EDIV", format!("0x{:04x}", ediv))?;
+                field(f, "Rand", format!("0x{:016x}", rand))
+            },
+            Param::IdentityAddress { addr_type, addr } => {
+                field(f, "Address", format!("{} ({})", addr, addr_type_str(*addr_type)))
+            },
+            Param::SecurityReq { auth_req } => field(f, "Authentication requirement", AuthReq(*auth_req)),
+            Param::PublicKey { x, y } => {
+                field(f, "X", hex(x))?;
+                field(f, "Y", hex(y))
+            },
+            Param::Keypress { kind } => {
+                field(f, "Type", format!("{} (0x{:02x})", keypress_str(*kind), kind))
+            },
+            Param::Unknown(data) => write!(f, " {:02x?}", data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pdu;
+
+    #[test]
+    fn pairing_request() {
+        let (_, pdu) = Pdu::parse(&[0x01, 0x04, 0x00, 0x2d, 0x10, 0x0f, 0x0f]).unwrap();
+
+        assert_eq!(pdu.to_string(), "Pairing Request (0x01)\
+            \n        IO capability: KeyboardDisplay (0x04)\
+            \n        OOB data: Authentication data not present (0x00)\
+            \n        Authentication requirement: 0x2d (Bonding, MITM, SC, CT2)\
+            \n        Max encryption key size: 16\
+            \n        Initiator key distribution: 0x0f (EncKey, IdKey, SignKey, LinkKey)\
+            \n        Responder key distribution: 0x0f (EncKey, IdKey, SignKey, LinkKey)");
+        assert!(Pdu::parse(&[0x05]).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+use crate::{error, monitor, tty::{Drops, Framer}};
+
+/// Async counterpart of `tty::Reader`: a `Stream` of the monitor packets in
+/// a TTY/RTT byte stream read from any `AsyncRead`, using the same framing
+/// and resynchronization rules.
+pub struct PacketStream<R> {
+    source: R,
+    framer: Framer,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> PacketStream<R> {
+    pub fn new(source: R) -> Self {
+        PacketStream { source, framer: Framer::new(), done: false }
+    }
+
+    /// Total number of garbage bytes discarded while looking for frames
+    pub fn skipped(&self) -> usize {
+        self.framer.skipped()
+    }
+
+    /// Drops reported by the controller in the frames read so far
+    pub fn drops(&self) -> Drops {
+        self.framer.drops()
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.source
+    }
+
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for PacketStream<R> {
+    type Item = error::Result<monitor::PacketBuf>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        while !this.done {
+            if let Some(res) = this.framer.next_packet() {
+                return Poll::Ready(Some(res));
+            }
+
+            let mut buf = ReadBuf::new(this.framer.space());
+
+            match Pin::new(&mut this.source).poll_read(cx, &mut buf) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(())) => {
+                    let n = buf.filled().len();
+
+                    if n == 0 {
+                        this.framer.finish();
+                        this.done = true;
+                    } else {
+                        this.framer.filled(n);
+                    }
+                },
+                Poll::Ready(Err(e)) => match e.kind() {
+                    io::ErrorKind::Interrupted | io::ErrorKind::TimedOut => (),
+                    kind => {
+                        this.done = true;
+                        return Poll::Ready(Some(Err(this.framer.io_error(kind))));
+                    },
+                },
+            }
+        }
+
+        Poll::Ready(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PacketStream;
+    use std::future::poll_fn;
+    use std::pin::Pin;
+    use futures_core::Stream;
+
+    #[tokio::test]
+    async fn stream_matches_reader() {
+        let data = &include_bytes!("xg24_peripheral_hr.btsnoop")[..];
+        let mut reader = crate::tty::Reader::new(data);
+        let expected: Vec<String> = reader.by_ref()
+            .map(|pkt| pkt.unwrap().op.to_string())
+            .collect();
+
+        let mut stream = PacketStream::new(data);
+        let mut got = Vec::new();
+
+        while let Some(pkt) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            got.push(pkt.unwrap().op.to_string());
+        }
+
+        assert!(!got.is_empty());
+        assert_eq!(got, expected);
+        assert_eq!(stream.skipped(), reader.skipped());
+    }
+}
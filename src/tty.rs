@@ -1,7 +1,13 @@
 use nom::{IResult, multi::length_data, sequence::tuple, number::{streaming, complete::{le_u8, le_u16, le_u32}}};
+use std::io::{self, Read};
 use crate::monitor;
+use crate::error::{self, Error, Kind, Layer, PResult};
 use time::{Time, Duration};
 
+const BUF_SIZE: usize = 2048;    // Size of buffer to read data into
+const PKT_MAX: usize = 1486 + 4; // Maximum BTSnoop packet size
+const MIN_LEN: usize = 6;        // Minumum length for a valid header
+
 #[derive(Debug)]
 pub enum ExtHeader {
     CommandDrops(u8),
@@ -57,31 +63,234 @@ fn parse_ext(data: &[u8]) -> IResult<&[u8], ExtHeader> {
     }
 }
 
-pub fn parse_data(input: &[u8]) -> IResult<&[u8], monitor::Packet> {
+/// Counters of what the controller reported as dropped through the extended
+/// headers, summed over the frames seen so far
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Drops {
+    pub command: u64,
+    pub event: u64,
+    pub acl_tx: u64,
+    pub acl_rx: u64,
+    pub sco_tx: u64,
+    pub sco_rx: u64,
+    pub other: u64,
+    /// Extended headers of an unknown type. Their length is unknown, so the
+    /// bytes after the type are parsed as further headers
+    pub unknown_ext: u64,
+}
+
+impl Drops {
+    fn add(&mut self, other: &Drops) {
+        self.command += other.command;
+        self.event += other.event;
+        self.acl_tx += other.acl_tx;
+        self.acl_rx += other.acl_rx;
+        self.sco_tx += other.sco_tx;
+        self.sco_rx += other.sco_rx;
+        self.other += other.other;
+        self.unknown_ext += other.unknown_ext;
+    }
+}
+
+fn parse_frame(input: &[u8]) -> PResult<'_, (monitor::Packet<'_>, Drops)> {
     let (input, frame) = length_data(streaming::le_u16)(input)?;
-    let (frame, (opcode, _flags, mut ext)) = tuple((le_u16, le_u8, length_data(le_u8)))(frame)?;
+    let (hdr, (opcode, _flags, ext_len)) = tuple((le_u16, le_u8, le_u8))(frame)?;
+    if ext_len as usize > hdr.len() {
+        return error::fail(hdr, Kind::BadLength);
+    }
+    let (frame, mut ext) = nom::bytes::complete::take(ext_len)(hdr)?;
     let mut ts = Time::MIDNIGHT;
+    let mut drops = Drops::default();
 
     while let Ok((rem, hdr)) = parse_ext(ext) {
         use ExtHeader::*;
 
         ext = rem;
         match hdr {
-            CommandDrops(d) => println!("Commands dropped: {}", d),
-            EventDrops(d) => println!("Commands dropps: {}", d),
-            AclTxDrops(d) => println!("ACL TX dropps: {}", d),
-            AclRxDrops(d) => println!("ACL RX dropps: {}", d),
-            ScoTxDrops(d) => println!("SCO TX dropps: {}", d),
-            ScoRxDrops(d) => println!("SCO RX dropps: {}", d),
-            OtherDrops(d) => println!("Other dropps: {}", d),
-            Unknown(h) => println!("Unknown ext header: {}", h),
+            CommandDrops(d) => drops.command += d as u64,
+            EventDrops(d) => drops.event += d as u64,
+            AclTxDrops(d) => drops.acl_tx += d as u64,
+            AclRxDrops(d) => drops.acl_rx += d as u64,
+            ScoTxDrops(d) => drops.sco_tx += d as u64,
+            ScoRxDrops(d) => drops.sco_rx += d as u64,
+            OtherDrops(d) => drops.other += d as u64,
+            Unknown(_) => drops.unknown_ext += 1,
             TimeStamp(t) => ts += Duration::microseconds(t as i64 * 100),
         }
     }
 
-    let (_, pkt) = monitor::monitor_packet(ts, 0, opcode, frame)?;
+    let (_, pkt) = monitor::parse_monitor(ts, 0, opcode, frame)?;
+
+    Ok((input, (pkt, drops)))
+}
+
+/// Parse one frame. Drop counters in its extended headers are only reported
+/// through `Reader::drops`.
+pub fn parse_data(input: &[u8]) -> error::Result<(&[u8], monitor::Packet<'_>)> {
+    error::finish(Layer::Tty, input, parse_frame(input)).map(|(rem, (pkt, _))| (rem, pkt))
+}
+
+/// Frame synchronization state shared by the blocking and async readers
+pub(crate) struct Framer {
+    buf: Vec<u8>,
+    offset: usize,
+    len: usize,
+    pos: usize,
+    skipped: usize,
+    drops: Drops,
+}
+
+impl Framer {
+    pub(crate) fn new() -> Self {
+        Framer { buf: vec![0u8; BUF_SIZE], offset: 0, len: 0, pos: 0, skipped: 0, drops: Drops::default() }
+    }
+
+    pub(crate) fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    pub(crate) fn drops(&self) -> Drops {
+        self.drops
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.offset += n;
+        self.len -= n;
+        self.pos += n;
+    }
+
+    fn skip(&mut self, n: usize) {
+        self.consume(n);
+        self.skipped += n;
+    }
+
+    // Size of the frame at the head of the buffer, if one is complete
+    fn frame_len(&mut self) -> Option<usize> {
+        loop {
+            if self.len < MIN_LEN {
+                return None;
+            }
+
+            let data = &self.buf[self.offset..];
+            let frame = u16::from_le_bytes([data[0], data[1]]) as usize + 2;
+
+            // Resync one byte at a time, e.g. past garbage zero bytes which
+            // may show up on the UART
+            if !(MIN_LEN..=PKT_MAX).contains(&frame) {
+                self.skip(1);
+                continue;
+            }
+
+            return if frame <= self.len { Some(frame) } else { None };
+        }
+    }
+
+    /// Parse the next buffered frame, or return `None` if more data is needed
+    pub(crate) fn next_packet(&mut self) -> Option<error::Result<monitor::PacketBuf>> {
+        let frame = self.frame_len()?;
+        let pos = self.pos;
+        let data = &self.buf[self.offset..(self.offset + frame)];
+        let res = match error::finish(Layer::Tty, data, parse_frame(data)) {
+            Ok((_, (pkt, drops))) => {
+                self.drops.add(&drops);
+                Ok(pkt.into_owned())
+            },
+            Err(e) => Err(e.at(pos)),
+        };
+
+        self.consume(frame);
+        Some(res)
+    }
+
+    /// Free space to read new data into
+    pub(crate) fn space(&mut self) -> &mut [u8] {
+        if self.offset > (BUF_SIZE - PKT_MAX) {
+            self.buf.copy_within(self.offset..(self.offset + self.len), 0);
+            self.offset = 0;
+        }
 
-    Ok((input, pkt))
+        &mut self.buf[(self.offset + self.len)..]
+    }
+
+    pub(crate) fn filled(&mut self, n: usize) {
+        self.len += n;
+    }
+
+    pub(crate) fn io_error(&self, kind: io::ErrorKind) -> Error {
+        Error::Io { layer: Layer::Tty, offset: self.pos + self.len, kind }
+    }
+
+    /// End of input: whatever is left can never become a complete frame
+    pub(crate) fn finish(&mut self) {
+        let len = self.len;
+        self.skip(len);
+    }
+}
+
+/// Iterator over the monitor packets in a TTY/RTT byte stream.
+///
+/// Partial frames are buffered until the rest arrives, and bytes which can't
+/// be the start of a frame (e.g. zero bytes showing up on the UART) are
+/// skipped. Frames that are complete but fail to parse are returned as
+/// errors and then dropped, so iteration can continue.
+pub struct Reader<R: Read> {
+    source: R,
+    framer: Framer,
+    done: bool,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(source: R) -> Self {
+        Reader { source, framer: Framer::new(), done: false }
+    }
+
+    /// Total number of garbage bytes discarded while looking for frames
+    pub fn skipped(&self) -> usize {
+        self.framer.skipped()
+    }
+
+    /// Drops reported by the controller in the frames read so far
+    pub fn drops(&self) -> Drops {
+        self.framer.drops()
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.source
+    }
+
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = error::Result<monitor::PacketBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(res) = self.framer.next_packet() {
+                return Some(res);
+            }
+
+            match self.source.read(self.framer.space()) {
+                Ok(0) => {
+                    self.framer.finish();
+                    self.done = true;
+                },
+                Ok(n) => self.framer.filled(n),
+                Err(e) => match e.kind() {
+                    io::ErrorKind::Interrupted | io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => (),
+                    kind => {
+                        self.done = true;
+                        return Some(Err(self.framer.io_error(kind)));
+                    },
+                },
+            }
+        }
+
+        None
+    }
 }
 
 #[cfg(test)]
@@ -96,13 +305,93 @@ mod tests {
                     println!("{} {}", pkt.ts, pkt.op);
                 },
                 Err(e) => {
-                    println!("{:?}", e);
+                    println!("{}", e);
                     break;
                 },
             }
         }
     }
 
+    // Hand out data in small chunks, like a slow UART would
+    struct Chunked<'a>(&'a [u8]);
+
+    impl std::io::Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(7);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn reader_resync() {
+        use super::{parse_data, Reader};
+
+        let data = include_bytes!("siw917_peripheral_hr.btsnoop");
+        let mut expected = Vec::new();
+        let mut rem = &data[..];
+
+        while let Ok((r, pkt)) = parse_data(rem) {
+            expected.push(format!("{} {}", pkt.ts, pkt.op));
+            rem = r;
+        }
+
+        // Sprinkle garbage zero bytes in between the first few frames
+        let first = u16::from_le_bytes([data[0], data[1]]) as usize + 2;
+        let mut noisy = vec![0u8; 3];
+        noisy.extend_from_slice(&data[..first]);
+        noisy.extend_from_slice(&[0, 0]);
+        noisy.extend_from_slice(&data[first..]);
+
+        let mut reader = Reader::new(Chunked(&noisy));
+        let got: Vec<String> = reader.by_ref()
+            .map(|pkt| pkt.map(|pkt| format!("{} {}", pkt.ts, pkt.op)).unwrap())
+            .collect();
+
+        assert_eq!(got, expected);
+        assert_eq!(reader.skipped(), 5 + rem.len());
+    }
+
+    #[test]
+    fn length_multiple_of_256() {
+        use super::{parse_data, Reader};
+
+        // System Note whose length field has a zero low byte
+        let mut frame = vec![0x00, 0x01, 0x0c, 0x00, 0x00, 0x00];
+        frame.resize(258, b'a');
+        assert!(parse_data(&frame).is_ok());
+
+        let mut reader = Reader::new(Chunked(&frame));
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().is_none());
+        assert_eq!(reader.skipped(), 0);
+    }
+
+    #[test]
+    fn drops() {
+        use super::{Drops, Reader};
+
+        // Close Index with event drops and an unknown extended header
+        let frame = [7, 0, 9, 0, 0, 3, 2, 3, 0x20];
+        let mut reader = Reader::new(&frame[..]);
+
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().is_none());
+        assert_eq!(reader.drops(), Drops { event: 3, unknown_ext: 1, ..Drops::default() });
+    }
+
+    #[test]
+    fn truncated() {
+        use super::parse_data;
+        use crate::{Error, error::Layer};
+
+        let data = include_bytes!("xg24_peripheral_hr.btsnoop");
+
+        assert_eq!(parse_data(&data[..10]).unwrap_err(), Error::Truncated { layer: Layer::Tty, offset: 10 });
+        assert_eq!(parse_data(&data[..1]).unwrap_err(), Error::Truncated { layer: Layer::Tty, offset: 1 });
+    }
+
     #[test]
     fn peripheral_hr_xg24() {
         let data = include_bytes!("xg24_peripheral_hr.btsnoop");
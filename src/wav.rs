@@ -0,0 +1,84 @@
+use std::io::{self, Seek, SeekFrom, Write};
+
+const HEADER_LEN: u32 = 44;
+
+/// Writer of linear PCM samples into a WAV file. The chunk sizes in the
+/// header are only filled in by `finish`, hence the need to seek.
+pub struct Writer<W: Write + Seek> {
+    out: W,
+    len: u32,
+}
+
+impl<W: Write + Seek> Writer<W> {
+    pub fn new(mut out: W, rate: u32, channels: u16, bits: u16) -> io::Result<Self> {
+        let align = channels * bits.div_ceil(8);
+        let byte_rate = rate.checked_mul(align as u32)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "WAV sample rate too high"))?;
+
+        out.write_all(b"RIFF")?;
+        out.write_all(&(HEADER_LEN - 8).to_le_bytes())?;
+        out.write_all(b"WAVE")?;
+        out.write_all(b"fmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        // WAVE_FORMAT_PCM
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&channels.to_le_bytes())?;
+        out.write_all(&rate.to_le_bytes())?;
+        out.write_all(&byte_rate.to_le_bytes())?;
+        out.write_all(&align.to_le_bytes())?;
+        out.write_all(&bits.to_le_bytes())?;
+        out.write_all(b"data")?;
+        out.write_all(&0u32.to_le_bytes())?;
+
+        Ok(Writer { out, len: 0 })
+    }
+
+    /// Append little endian samples, as carried in HCI synchronous data
+    pub fn write_samples(&mut self, data: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(data.len()).ok()
+            .and_then(|len| self.len.checked_add(len))
+            .filter(|len| *len <= u32::MAX - HEADER_LEN)
+            .ok_or_else(|| io::Error::other("WAV file too large"))?;
+
+        self.out.write_all(data)?;
+        self.len = len;
+        Ok(())
+    }
+
+    /// Pad the data chunk and fill in the chunk sizes
+    pub fn finish(mut self) -> io::Result<W> {
+        let pad = self.len % 2;
+        if pad != 0 {
+            self.out.write_all(&[0])?;
+        }
+        self.out.seek(SeekFrom::Start(4))?;
+        self.out.write_all(&(HEADER_LEN - 8 + self.len + pad).to_le_bytes())?;
+        self.out.seek(SeekFrom::Start(HEADER_LEN as u64 - 4))?;
+        self.out.write_all(&self.len.to_le_bytes())?;
+        self.out.seek(SeekFrom::End(0))?;
+        self.out.flush()?;
+
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Writer;
+    use std::io::Cursor;
+
+    #[test]
+    fn header() {
+        let mut w = Writer::new(Cursor::new(Vec::new()), 8000, 1, 16).unwrap();
+        w.write_samples(&[0x01, 0x02, 0x03]).unwrap();
+        let buf = w.finish().unwrap().into_inner();
+
+        assert_eq!(buf.len(), 48);
+        assert_eq!(&buf[..16], b"RIFF\x28\x00\x00\x00WAVEfmt ");
+        assert_eq!(&buf[20..36], &[0x01, 0x00, 0x01, 0x00, 0x40, 0x1f, 0x00, 0x00,
+                                   0x80, 0x3e, 0x00, 0x00, 0x02, 0x00, 0x10, 0x00]);
+        assert_eq!(&buf[36..], b"data\x03\x00\x00\x00\x01\x02\x03\x00");
+
+        assert!(Writer::new(Cursor::new(Vec::new()), u32::MAX, 1, 16).is_err());
+    }
+}